@@ -22,6 +22,7 @@ mod construct;
 mod core;
 mod generator;
 mod import;
+mod randomness;
 mod simple_trie;
 mod state_sizes;
 mod tempdb;
@@ -74,6 +75,14 @@ struct Opt {
 	/// so that actual interval can be selected in the profiler of choice.
 	#[arg(short, long, default_value = "regular")]
 	mode: BenchmarkMode,
+
+	/// Run the randomness tests over beacon pulses instead of the benchmarks.
+	///
+	/// The pulses are read from the output of `substrate-node export-pulses`, or from stdin if
+	/// this is `-`, so that `substrate-node export-pulses | node-bench --pulses -` tests the
+	/// pulses in a node database.
+	#[arg(long, value_name = "PATH")]
+	pulses: Option<std::path::PathBuf>,
 }
 
 fn main() {
@@ -83,6 +92,10 @@ fn main() {
 		sp_tracing::try_init_simple();
 	}
 
+	if let Some(path) = opt.pulses.as_ref() {
+		return run_randomness_tests(path, opt.json)
+	}
+
 	let mut import_benchmarks = Vec::new();
 
 	for size in [
@@ -184,3 +197,31 @@ fn main() {
 		println!("{}", json_result);
 	}
 }
+
+fn run_randomness_tests(path: &std::path::Path, json: bool) {
+	let pulses = randomness::read_pulses(path).unwrap_or_else(|e| {
+		eprintln!("{}", e);
+		std::process::exit(1);
+	});
+	log::info!("Testing the randomness of {} pulses", pulses.len());
+
+	let results = randomness::run_tests(&pulses);
+	if results.is_empty() {
+		eprintln!("No pulse was found");
+		std::process::exit(1);
+	}
+
+	if json {
+		let json_result: String =
+			serde_json::to_string(&results).expect("Failed to construct json");
+		println!("{}", json_result);
+	} else {
+		for result in results.iter() {
+			println!("{}", result);
+		}
+	}
+
+	if !results.iter().all(|result| result.passed()) {
+		std::process::exit(1);
+	}
+}
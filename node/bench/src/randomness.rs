@@ -0,0 +1,330 @@
+//! Randomness quality tests for the beacon output.
+//!
+//! The tests follow NIST SP 800-22 and run over the randomness of every pulse, concatenated
+//! in block order. Pulses are read from the output of `substrate-node export-pulses`, in
+//! either of its formats.
+
+use serde::Serialize;
+use std::{
+	fmt,
+	io::{BufRead, BufReader, Read},
+	path::Path,
+};
+
+/// The significance level the p-values are compared against.
+pub const SIGNIFICANCE: f64 = 0.01;
+
+/// The block length of the block frequency test.
+pub const BLOCK_LENGTH: usize = 128;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TestOutput {
+	name: String,
+	bits: usize,
+	p_value: f64,
+	passed: bool,
+}
+
+impl TestOutput {
+	fn new(name: impl Into<String>, bits: usize, p_value: f64) -> Self {
+		TestOutput { name: name.into(), bits, p_value, passed: p_value >= SIGNIFICANCE }
+	}
+
+	pub fn passed(&self) -> bool {
+		self.passed
+	}
+}
+
+impl fmt::Display for TestOutput {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"{}: p-value {:.6} over {} bits, {}",
+			self.name,
+			self.p_value,
+			self.bits,
+			if self.passed { "passed" } else { "FAILED" },
+		)
+	}
+}
+
+/// Read the randomness of the pulses exported to `path`, or to stdin if `path` is `-`.
+pub fn read_pulses(path: &Path) -> Result<Vec<Vec<u8>>, String> {
+	let input: Box<dyn Read> = if path == Path::new("-") {
+		Box::new(std::io::stdin())
+	} else {
+		Box::new(
+			std::fs::File::open(path)
+				.map_err(|e| format!("Failed to open {}: {}", path.display(), e))?,
+		)
+	};
+
+	let mut pulses = Vec::new();
+	for line in BufReader::new(input).lines() {
+		let line = line.map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+		let line = line.trim();
+		// Skip blank lines and the CSV header.
+		if line.is_empty() || line.starts_with("blockNumber") {
+			continue
+		}
+
+		let randomness = if line.starts_with('{') {
+			let pulse: serde_json::Value =
+				serde_json::from_str(line).map_err(|e| format!("Invalid pulse: {}", e))?;
+			pulse["randomness"].as_str().map(ToOwned::to_owned)
+		} else {
+			line.split(',').nth(2).map(ToOwned::to_owned)
+		};
+		let randomness = randomness.ok_or_else(|| format!("Pulse without randomness: {}", line))?;
+		pulses.push(
+			array_bytes::hex2bytes(&randomness)
+				.map_err(|_| format!("Invalid hex encoded randomness: {}", randomness))?,
+		);
+	}
+	Ok(pulses)
+}
+
+/// Run every test over the concatenated `pulses`.
+pub fn run_tests(pulses: &[Vec<u8>]) -> Vec<TestOutput> {
+	let bits = to_bits(pulses.concat());
+	let n = bits.len();
+	if n == 0 {
+		return Vec::new()
+	}
+
+	// The pattern lengths NIST recommends for a sequence of `n` bits.
+	let log2 = (n as f64).log2().floor() as usize;
+	let serial_length = log2.saturating_sub(3).clamp(2, 16);
+	let entropy_length = log2.saturating_sub(6).clamp(2, 10);
+	let (serial_1, serial_2) = serial(&bits, serial_length);
+
+	vec![
+		TestOutput::new("monobit", n, monobit(&bits)),
+		TestOutput::new("runs", n, runs(&bits)),
+		TestOutput::new(
+			format!("block-frequency (M = {})", BLOCK_LENGTH),
+			n,
+			block_frequency(&bits, BLOCK_LENGTH),
+		),
+		TestOutput::new(format!("serial (m = {}, first)", serial_length), n, serial_1),
+		TestOutput::new(format!("serial (m = {}, second)", serial_length), n, serial_2),
+		TestOutput::new(
+			format!("approximate-entropy (m = {})", entropy_length),
+			n,
+			approximate_entropy(&bits, entropy_length),
+		),
+	]
+}
+
+fn to_bits(bytes: Vec<u8>) -> Vec<u8> {
+	bytes.into_iter().flat_map(|byte| (0..8).rev().map(move |i| (byte >> i) & 1)).collect()
+}
+
+fn monobit(bits: &[u8]) -> f64 {
+	let sum: i64 = bits.iter().map(|bit| 2 * *bit as i64 - 1).sum();
+	let s_obs = sum.unsigned_abs() as f64 / (bits.len() as f64).sqrt();
+	erfc(s_obs / 2f64.sqrt())
+}
+
+fn runs(bits: &[u8]) -> f64 {
+	let n = bits.len() as f64;
+	let pi = bits.iter().filter(|bit| **bit == 1).count() as f64 / n;
+	// The frequency test has to pass for the runs test to be meaningful.
+	if (pi - 0.5).abs() >= 2.0 / n.sqrt() {
+		return 0.0
+	}
+
+	let runs = 1 + bits.windows(2).filter(|pair| pair[0] != pair[1]).count();
+	let expected = 2.0 * n * pi * (1.0 - pi);
+	erfc((runs as f64 - expected).abs() / (2.0 * (2.0 * n).sqrt() * pi * (1.0 - pi)))
+}
+
+fn block_frequency(bits: &[u8], block_length: usize) -> f64 {
+	let blocks = bits.len() / block_length;
+	if blocks == 0 {
+		return 0.0
+	}
+
+	let chi_squared: f64 = bits
+		.chunks_exact(block_length)
+		.map(|block| {
+			let pi = block.iter().filter(|bit| **bit == 1).count() as f64 / block_length as f64;
+			(pi - 0.5).powi(2)
+		})
+		.sum::<f64>() *
+		4.0 * block_length as f64;
+	igamc(blocks as f64 / 2.0, chi_squared / 2.0)
+}
+
+/// The frequency of every overlapping `m` bit pattern, wrapping around the end of `bits`.
+fn pattern_counts(bits: &[u8], m: usize) -> Vec<usize> {
+	let mut counts = vec![0; 1 << m];
+	for i in 0..bits.len() {
+		let pattern = (0..m).fold(0, |acc, j| acc << 1 | bits[(i + j) % bits.len()] as usize);
+		counts[pattern] += 1;
+	}
+	counts
+}
+
+fn serial(bits: &[u8], m: usize) -> (f64, f64) {
+	let n = bits.len() as f64;
+	let psi_squared = |m: usize| {
+		if m == 0 {
+			return 0.0
+		}
+		let sum: f64 = pattern_counts(bits, m).iter().map(|count| (*count as f64).powi(2)).sum();
+		sum * (1u64 << m) as f64 / n - n
+	};
+
+	let (psi_m, psi_m1, psi_m2) = (psi_squared(m), psi_squared(m - 1), psi_squared(m - 2));
+	let delta_1 = psi_m - psi_m1;
+	let delta_2 = psi_m - 2.0 * psi_m1 + psi_m2;
+	(
+		igamc(2f64.powi(m as i32 - 2), delta_1 / 2.0),
+		igamc(2f64.powi(m as i32 - 3), delta_2 / 2.0),
+	)
+}
+
+fn approximate_entropy(bits: &[u8], m: usize) -> f64 {
+	let n = bits.len() as f64;
+	let phi = |m: usize| -> f64 {
+		pattern_counts(bits, m)
+			.iter()
+			.filter(|count| **count > 0)
+			.map(|count| {
+				let c = *count as f64 / n;
+				c * c.ln()
+			})
+			.sum()
+	};
+
+	let entropy = phi(m) - phi(m + 1);
+	let chi_squared = 2.0 * n * (2f64.ln() - entropy);
+	igamc(2f64.powi(m as i32 - 1), chi_squared / 2.0)
+}
+
+/// The complementary error function.
+fn erfc(x: f64) -> f64 {
+	if x < 0.0 {
+		2.0 - igamc(0.5, x * x)
+	} else {
+		igamc(0.5, x * x)
+	}
+}
+
+/// The natural logarithm of the gamma function, using the Lanczos approximation.
+fn ln_gamma(x: f64) -> f64 {
+	const COEFFICIENTS: [f64; 6] = [
+		76.180_091_729_471_46,
+		-86.505_320_329_416_77,
+		24.014_098_240_830_91,
+		-1.231_739_572_450_155,
+		0.120_865_097_386_617_9e-2,
+		-0.539_523_938_495_3e-5,
+	];
+	let tmp = x + 5.5;
+	let tmp = tmp - (x + 0.5) * tmp.ln();
+	let series = COEFFICIENTS
+		.iter()
+		.enumerate()
+		.fold(1.000_000_000_190_015, |acc, (j, c)| acc + c / (x + 1.0 + j as f64));
+	-tmp + (2.506_628_274_631_000_5 * series / x).ln()
+}
+
+/// The regularized upper incomplete gamma function `Q(a, x)`.
+fn igamc(a: f64, x: f64) -> f64 {
+	const EPSILON: f64 = 1e-15;
+	const ITERATIONS: usize = 1000;
+
+	if x <= 0.0 {
+		return 1.0
+	}
+	let prefactor = (-x + a * x.ln() - ln_gamma(a)).exp();
+
+	if x < a + 1.0 {
+		// The series of the lower function converges quickly here.
+		let (mut term, mut sum) = (1.0 / a, 1.0 / a);
+		for k in 1..ITERATIONS {
+			term *= x / (a + k as f64);
+			sum += term;
+			if term.abs() < sum.abs() * EPSILON {
+				break
+			}
+		}
+		1.0 - sum * prefactor
+	} else {
+		// Lentz's method for the continued fraction of the upper function.
+		let tiny = f64::MIN_POSITIVE / EPSILON;
+		let mut b = x + 1.0 - a;
+		let mut c = 1.0 / tiny;
+		let mut d = 1.0 / b;
+		let mut h = d;
+		for i in 1..ITERATIONS {
+			let an = -(i as f64) * (i as f64 - a);
+			b += 2.0;
+			d = an * d + b;
+			if d.abs() < tiny {
+				d = tiny;
+			}
+			c = b + an / c;
+			if c.abs() < tiny {
+				c = tiny;
+			}
+			d = 1.0 / d;
+			let delta = d * c;
+			h *= delta;
+			if (delta - 1.0).abs() < EPSILON {
+				break
+			}
+		}
+		h * prefactor
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// The examples of NIST SP 800-22 rev. 1a, section 2.
+	fn bits(s: &str) -> Vec<u8> {
+		s.bytes().map(|b| b - b'0').collect()
+	}
+
+	fn assert_p_value(actual: f64, expected: f64) {
+		assert!((actual - expected).abs() < 1e-5, "{} != {}", actual, expected);
+	}
+
+	#[test]
+	fn monobit_matches_nist_example() {
+		assert_p_value(monobit(&bits("1011010101")), 0.527089);
+	}
+
+	#[test]
+	fn runs_matches_nist_example() {
+		assert_p_value(runs(&bits("1001101011")), 0.147232);
+	}
+
+	#[test]
+	fn block_frequency_matches_nist_example() {
+		assert_p_value(block_frequency(&bits("0110011010"), 3), 0.801252);
+	}
+
+	#[test]
+	fn serial_matches_nist_example() {
+		let (first, second) = serial(&bits("0011011101"), 3);
+		assert_p_value(first, 0.808792);
+		assert_p_value(second, 0.670320);
+	}
+
+	#[test]
+	fn approximate_entropy_matches_nist_example() {
+		assert_p_value(approximate_entropy(&bits("0100110101"), 3), 0.261961);
+	}
+
+	#[test]
+	fn constant_output_fails() {
+		let outputs = run_tests(&vec![vec![0u8; 32]; 64]);
+		assert!(!outputs.is_empty());
+		assert!(outputs.iter().all(|output| !output.passed()));
+	}
+}
@@ -27,6 +27,12 @@ pub struct InspectCmd {
 	#[clap(subcommand)]
 	pub command: InspectSubCmd,
 
+	/// Print the decoded data as JSON.
+	///
+	/// Only printers that know the runtime can render JSON, the default printer ignores this.
+	#[arg(long)]
+	pub json: bool,
+
 	#[allow(missing_docs)]
 	#[clap(flatten)]
 	pub shared_params: SharedParams,
@@ -59,4 +65,16 @@ pub enum InspectSubCmd {
 		#[arg(value_name = "BLOCK:INDEX or BYTES")]
 		input: String,
 	},
+	/// List the timelock encrypted calls in the scheduler agenda, and decrypt those whose
+	/// beacon pulse has been published.
+	///
+	/// This needs to know the runtime, so it is only supported by nodes that implement it.
+	Timelocked {
+		/// The first agenda block to list. Defaults to the best block.
+		#[arg(long, value_name = "BLOCK")]
+		from: Option<u32>,
+		/// The last agenda block to list. Defaults to 100 blocks after `from`.
+		#[arg(long, value_name = "BLOCK")]
+		to: Option<u32>,
+	},
 }
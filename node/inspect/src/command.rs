@@ -20,7 +20,7 @@
 
 use crate::{
 	cli::{InspectCmd, InspectSubCmd},
-	Inspector,
+	DebugPrinter, Inspector, PrettyPrinter,
 };
 use sc_cli::{CliConfiguration, ImportParams, Result, SharedParams};
 use sc_service::Configuration;
@@ -35,10 +35,20 @@ impl InspectCmd {
 	where
 		B: Block,
 		RA: Send + Sync + 'static,
+	{
+		self.run_with_printer::<B, RA, _>(config, DebugPrinter)
+	}
+
+	/// Run the inspect command, formatting the data with `printer`.
+	pub fn run_with_printer<B, RA, P>(&self, config: Configuration, printer: P) -> Result<()>
+	where
+		B: Block,
+		RA: Send + Sync + 'static,
+		P: PrettyPrinter<B>,
 	{
 		let executor = sc_service::new_wasm_executor::<HostFunctions>(&config);
 		let client = sc_service::new_full_client::<B, RA, _>(&config, None, executor)?;
-		let inspect = Inspector::<B>::new(client);
+		let inspect = Inspector::<B, P>::with_printer(client, printer);
 
 		match &self.command {
 			InspectSubCmd::Block { input } => {
@@ -53,6 +63,8 @@ impl InspectCmd {
 				println!("{res}");
 				Ok(())
 			},
+			InspectSubCmd::Timelocked { .. } =>
+				Err(sc_cli::Error::Input("This node cannot inspect timelocked calls".into())),
 		}
 	}
 }
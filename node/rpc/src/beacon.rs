@@ -0,0 +1,429 @@
+//! RPC interface for the ETF randomness beacon.
+//!
+//! Exposes the pulses published by `pallet_randomness_beacon` under the `etf_` namespace,
+//! so that clients do not need to know the storage layout of the pallet. All pulses are read
+//! through the [`RandomnessBeaconApi`] runtime API.
+//!
+//! `etf_health` reports whether the beacon keeps up with finality, see [`beacon_health`].
+
+use std::{collections::VecDeque, sync::Arc};
+
+use futures::{future, FutureExt, StreamExt};
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::{error::ErrorObject, ErrorObjectOwned},
+	PendingSubscriptionSink,
+};
+use beefy_primitives::{bls_crypto::AuthorityId as BeefyId, BeefyApi, ValidatorSetId, KEY_TYPE};
+use codec::{Decode, Encode};
+use node_primitives::{Block, BlockNumber, Hash};
+use node_template_runtime::{
+	beacon::{pulse_written_by, Pulse},
+	beacon_api::RandomnessBeaconApi,
+	UncheckedExtrinsic,
+};
+use sc_client_api::{BlockBackend, BlockchainEvents};
+use sc_rpc::SubscriptionTaskExecutor;
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{crypto::ByteArray, Bytes};
+use sp_keystore::KeystorePtr;
+
+/// The maximum number of pulses that can be requested with a single `etf_getPulseRange` call.
+pub const MAX_PULSE_RANGE: BlockNumber = 256;
+
+/// The number of finalized blocks without a newer pulse after which the beacon is lagging.
+pub const LAGGING_AFTER: BlockNumber = 8;
+
+/// The number of finalized blocks without a newer pulse after which the beacon is stalled.
+pub const STALLED_AFTER: BlockNumber = 64;
+
+/// Top-level error code for beacon RPC errors.
+const BEACON_ERROR: i32 = 9000;
+
+/// A randomness beacon pulse, as returned over RPC.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcPulse {
+	/// The block number the pulse was produced for.
+	pub block_number: BlockNumber,
+	/// The aggregated signature of the ETF authorities.
+	pub signature: Bytes,
+	/// The randomness derived from the signature.
+	///
+	/// Backfilled pulses only carry a signature, for those this is the randomness the runtime
+	/// serves for the block.
+	pub randomness: Bytes,
+}
+
+impl From<Pulse> for RpcPulse {
+	fn from(pulse: Pulse) -> Self {
+		RpcPulse {
+			block_number: pulse.header.block_number,
+			signature: pulse.body.signature.into_inner().into(),
+			randomness: pulse.body.randomness.into_inner().into(),
+		}
+	}
+}
+
+/// How well the beacon keeps up with finality.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BeaconStatus {
+	/// The latest pulse is at most [`LAGGING_AFTER`] blocks behind the finalized head.
+	Healthy,
+	/// The latest pulse is more than [`LAGGING_AFTER`] blocks behind the finalized head.
+	Lagging,
+	/// The latest pulse is more than [`STALLED_AFTER`] blocks behind the finalized head.
+	///
+	/// Timelocked calls and randomness requests for the blocks in between wait for their
+	/// pulses until the beacon resumes or their backfill window closes.
+	Stalled,
+}
+
+impl BeaconStatus {
+	/// The status of a beacon whose latest pulse is `lag` blocks behind the finalized head.
+	pub fn from_lag(lag: BlockNumber) -> Self {
+		match lag {
+			lag if lag > STALLED_AFTER => BeaconStatus::Stalled,
+			lag if lag > LAGGING_AFTER => BeaconStatus::Lagging,
+			_ => BeaconStatus::Healthy,
+		}
+	}
+}
+
+/// The health of the beacon at the finalized head, as returned over RPC.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BeaconHealth {
+	/// How well the beacon keeps up with finality.
+	pub status: BeaconStatus,
+	/// The finalized head.
+	pub finalized_block: BlockNumber,
+	/// The block number of the latest pulse published at the finalized head.
+	pub latest_pulse: BlockNumber,
+	/// The id of the current BEEFY-ETF authority set, if BEEFY has started.
+	pub authority_set_id: Option<ValidatorSetId>,
+	/// The id of the authority set that signed the latest pulse.
+	pub latest_pulse_authority_set_id: Option<ValidatorSetId>,
+	/// Whether the local keystore holds the key of a current authority with a share of the
+	/// round secret.
+	pub holds_share: bool,
+}
+
+impl BeaconHealth {
+	/// Whether the current authority set has not published a pulse since it took over, i.e.
+	/// the latest pulse was signed by an earlier set.
+	///
+	/// A beacon that falls behind right after a rotation points at the new set, e.g. its shares
+	/// were not handed over, rather than at offline authorities.
+	pub fn current_set_is_silent(&self) -> bool {
+		match (self.authority_set_id, self.latest_pulse_authority_set_id) {
+			(Some(current), Some(signer)) => signer < current,
+			_ => false,
+		}
+	}
+}
+
+/// Beacon RPC errors.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+	/// The requested range is empty or too large.
+	#[error("Invalid pulse range {0}..={1}: ranges must be ordered and span at most {2} blocks")]
+	InvalidRange(BlockNumber, BlockNumber, BlockNumber),
+	/// Calling into the runtime failed.
+	#[error("Failed to call the beacon runtime API: {0}")]
+	RuntimeApi(#[from] sp_api::ApiError),
+	/// Reading a block failed.
+	#[error("Failed to read the block: {0}")]
+	Blockchain(#[from] sp_blockchain::Error),
+}
+
+impl From<Error> for ErrorObjectOwned {
+	fn from(error: Error) -> Self {
+		let code = match &error {
+			Error::InvalidRange(..) => BEACON_ERROR + 1,
+			Error::RuntimeApi(_) => BEACON_ERROR + 2,
+			Error::Blockchain(_) => BEACON_ERROR + 3,
+		};
+		ErrorObject::owned(code, error.to_string(), None::<()>)
+	}
+}
+
+/// Randomness beacon RPC methods.
+#[rpc(client, server)]
+pub trait BeaconApi {
+	/// Returns the pulse published for the given block, if any.
+	#[method(name = "etf_getPulse")]
+	fn get_pulse(&self, block: BlockNumber) -> RpcResult<Option<RpcPulse>>;
+
+	/// Returns the most recently published pulse, if any.
+	#[method(name = "etf_latestPulse")]
+	fn latest_pulse(&self) -> RpcResult<Option<RpcPulse>>;
+
+	/// Returns all pulses published for blocks in `from..=to`.
+	///
+	/// Blocks without a pulse are skipped.
+	#[method(name = "etf_getPulseRange")]
+	fn get_pulse_range(&self, from: BlockNumber, to: BlockNumber) -> RpcResult<Vec<RpcPulse>>;
+
+	/// Returns whether `signature` is a valid beacon signature for the given block.
+	#[method(name = "etf_verifyPulse")]
+	fn verify_pulse(&self, block: BlockNumber, signature: Bytes) -> RpcResult<bool>;
+
+	/// Returns the health of the beacon at the finalized head.
+	#[method(name = "etf_health")]
+	fn health(&self) -> RpcResult<BeaconHealth>;
+
+	/// Subscribe to newly written pulses.
+	///
+	/// Every pulse a new best block publishes or backfills is sent, in the order the block
+	/// writes them.
+	#[subscription(
+		name = "etf_subscribePulses" => "etf_pulse",
+		unsubscribe = "etf_unsubscribePulses",
+		item = RpcPulse,
+	)]
+	fn subscribe_pulses(&self);
+}
+
+/// Implements the [`BeaconApiServer`] RPC trait for interacting with the randomness beacon.
+pub struct Beacon<C> {
+	client: Arc<C>,
+	executor: SubscriptionTaskExecutor,
+	keystore: KeystorePtr,
+}
+
+impl<C> Beacon<C> {
+	/// Create a new instance of the beacon RPC handler.
+	pub fn new(client: Arc<C>, executor: SubscriptionTaskExecutor, keystore: KeystorePtr) -> Self {
+		Self { client, executor, keystore }
+	}
+}
+
+fn pulse_at<C>(client: &C, at: Hash, block: BlockNumber) -> Result<Option<RpcPulse>, Error>
+where
+	C: ProvideRuntimeApi<Block>,
+	C::Api: RandomnessBeaconApi<Block, BlockNumber, Pulse>,
+{
+	Ok(client.runtime_api().pulse(at, block)?.map(Into::into))
+}
+
+fn latest_pulse_at<C>(client: &C, at: Hash) -> Result<Option<RpcPulse>, Error>
+where
+	C: ProvideRuntimeApi<Block>,
+	C::Api: RandomnessBeaconApi<Block, BlockNumber, Pulse>,
+{
+	let height = client.runtime_api().latest_height(at)?;
+	pulse_at(client, at, height)
+}
+
+/// The pulses the extrinsics of block `at` publish or backfill, in the order they are written.
+fn pulses_written_at<C>(client: &C, at: Hash) -> Result<Vec<RpcPulse>, Error>
+where
+	C: ProvideRuntimeApi<Block> + BlockBackend<Block>,
+	C::Api: RandomnessBeaconApi<Block, BlockNumber, Pulse>,
+{
+	let runtime_api = client.runtime_api();
+	let mut pulses = Vec::new();
+	for xt in client.block_body(at)?.unwrap_or_default() {
+		let Some(block) = UncheckedExtrinsic::decode(&mut &xt.encode()[..])
+			.ok()
+			.and_then(|xt| pulse_written_by(&xt.function))
+		else {
+			continue
+		};
+		// Failed extrinsics do not write their pulse.
+		let pulse = match runtime_api.pulse(at, block)? {
+			Some(pulse) => Some(pulse.into()),
+			None => runtime_api
+				.signature_at(at, block)?
+				.zip(runtime_api.randomness_at(at, block)?)
+				.map(|(signature, randomness)| RpcPulse {
+					block_number: block,
+					signature: signature.into(),
+					randomness: randomness.to_vec().into(),
+				}),
+		};
+		pulses.extend(pulse);
+	}
+	Ok(pulses)
+}
+
+/// The health of the beacon at the finalized head of `client`.
+///
+/// The local node holds a share if `keystore` has the BEEFY key of an authority of the current
+/// set and the runtime has a share of the round secret for that authority.
+pub fn beacon_health<C>(client: &C, keystore: &KeystorePtr) -> Result<BeaconHealth, Error>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: RandomnessBeaconApi<Block, BlockNumber, Pulse> + BeefyApi<Block, BeefyId>,
+{
+	let info = client.info();
+	let at = info.finalized_hash;
+	let runtime_api = client.runtime_api();
+	let latest_pulse = runtime_api.latest_height(at)?;
+	let latest_pulse_authority_set_id = runtime_api.authority_set_at(at, latest_pulse)?;
+	let validator_set = runtime_api.validator_set(at)?;
+
+	let mut holds_share = false;
+	for authority in validator_set.iter().flat_map(|set| set.validators()) {
+		if keystore.has_keys(&[(authority.to_raw_vec(), KEY_TYPE)]) &&
+			runtime_api.read_share(at, authority.clone())?.map_or(false, |s| !s.is_empty())
+		{
+			holds_share = true;
+			break
+		}
+	}
+
+	Ok(BeaconHealth {
+		status: BeaconStatus::from_lag(info.finalized_number.saturating_sub(latest_pulse)),
+		finalized_block: info.finalized_number,
+		latest_pulse,
+		authority_set_id: validator_set.map(|set| set.id()),
+		latest_pulse_authority_set_id,
+		holds_share,
+	})
+}
+
+impl<C> BeaconApiServer for Beacon<C>
+where
+	C: ProvideRuntimeApi<Block>
+		+ HeaderBackend<Block>
+		+ BlockBackend<Block>
+		+ BlockchainEvents<Block>
+		+ Send
+		+ Sync
+		+ 'static,
+	C::Api: RandomnessBeaconApi<Block, BlockNumber, Pulse> + BeefyApi<Block, BeefyId>,
+{
+	fn get_pulse(&self, block: BlockNumber) -> RpcResult<Option<RpcPulse>> {
+		let best = self.client.info().best_hash;
+		pulse_at(&*self.client, best, block).map_err(Into::into)
+	}
+
+	fn latest_pulse(&self) -> RpcResult<Option<RpcPulse>> {
+		let best = self.client.info().best_hash;
+		latest_pulse_at(&*self.client, best).map_err(Into::into)
+	}
+
+	fn get_pulse_range(&self, from: BlockNumber, to: BlockNumber) -> RpcResult<Vec<RpcPulse>> {
+		if from > to || to - from >= MAX_PULSE_RANGE {
+			return Err(Error::InvalidRange(from, to, MAX_PULSE_RANGE).into())
+		}
+
+		let best = self.client.info().best_hash;
+		let mut pulses = Vec::new();
+		for block in from..=to {
+			if let Some(pulse) = pulse_at(&*self.client, best, block)? {
+				pulses.push(pulse);
+			}
+		}
+		Ok(pulses)
+	}
+
+	fn verify_pulse(&self, block: BlockNumber, signature: Bytes) -> RpcResult<bool> {
+		let best = self.client.info().best_hash;
+		self.client
+			.runtime_api()
+			.verify_pulse(best, block, signature.to_vec())
+			.map_err(|e| Error::from(e).into())
+	}
+
+	fn health(&self) -> RpcResult<BeaconHealth> {
+		beacon_health(&*self.client, &self.keystore).map_err(Into::into)
+	}
+
+	fn subscribe_pulses(&self, pending: PendingSubscriptionSink) {
+		let client = self.client.clone();
+		// Pulses are published and backfilled in blocks, so every imported best block may
+		// write several. We only forward a pulse once, even if it is observed again after a
+		// re-org, remembering the last `MAX_PULSE_RANGE` pulses sent.
+		let stream = self
+			.client
+			.import_notification_stream()
+			.filter(|notification| future::ready(notification.is_new_best))
+			.scan(VecDeque::<BlockNumber>::new(), move |sent, notification| {
+				let pulses = match pulses_written_at(&*client, notification.hash) {
+					Ok(pulses) => pulses,
+					Err(e) => {
+						log::debug!(target: "rpc", "Failed to read beacon pulses: {}", e);
+						Vec::new()
+					},
+				};
+				let pulses = pulses
+					.into_iter()
+					.filter(|pulse| {
+						if sent.contains(&pulse.block_number) {
+							return false
+						}
+						if sent.len() == MAX_PULSE_RANGE as usize {
+							sent.pop_front();
+						}
+						sent.push_back(pulse.block_number);
+						true
+					})
+					.collect::<Vec<_>>();
+				future::ready(Some(futures::stream::iter(pulses)))
+			})
+			.flatten();
+
+		let fut = sc_rpc::utils::pipe_from_stream(pending, stream);
+		self.executor.spawn("substrate-rpc-subscription", Some("rpc"), fut.boxed());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn pulse_serializes_in_stable_shape() {
+		let pulse = RpcPulse {
+			block_number: 42,
+			signature: vec![1, 2, 3].into(),
+			randomness: vec![4, 5].into(),
+		};
+
+		assert_eq!(
+			serde_json::to_string(&pulse).unwrap(),
+			r#"{"blockNumber":42,"signature":"0x010203","randomness":"0x0405"}"#,
+		);
+	}
+
+	#[test]
+	fn status_follows_the_lag_behind_finality() {
+		assert_eq!(BeaconStatus::from_lag(0), BeaconStatus::Healthy);
+		assert_eq!(BeaconStatus::from_lag(LAGGING_AFTER), BeaconStatus::Healthy);
+		assert_eq!(BeaconStatus::from_lag(LAGGING_AFTER + 1), BeaconStatus::Lagging);
+		assert_eq!(BeaconStatus::from_lag(STALLED_AFTER + 1), BeaconStatus::Stalled);
+	}
+
+	#[test]
+	fn health_serializes_in_stable_shape() {
+		let health = BeaconHealth {
+			status: BeaconStatus::Lagging,
+			finalized_block: 100,
+			latest_pulse: 90,
+			authority_set_id: Some(3),
+			latest_pulse_authority_set_id: Some(2),
+			holds_share: true,
+		};
+
+		assert_eq!(
+			serde_json::to_string(&health).unwrap(),
+			concat!(
+				r#"{"status":"lagging","finalizedBlock":100,"latestPulse":90,"#,
+				r#""authoritySetId":3,"latestPulseAuthoritySetId":2,"holdsShare":true}"#,
+			),
+		);
+	}
+
+	#[test]
+	fn invalid_range_maps_to_beacon_error_code() {
+		let error: ErrorObjectOwned = Error::InvalidRange(10, 1, MAX_PULSE_RANGE).into();
+		assert_eq!(error.code(), BEACON_ERROR + 1);
+	}
+}
@@ -31,11 +31,15 @@
 #![warn(missing_docs)]
 #![warn(unused_crate_dependencies)]
 
+pub mod beacon;
+
 use std::sync::Arc;
 
+use beefy_primitives::{bls_crypto::AuthorityId as BeefyId, BeefyApi};
 use jsonrpsee::RpcModule;
 use node_primitives::{AccountId, Balance, Block, BlockNumber, Hash, Nonce};
-use sc_client_api::AuxStore;
+use node_template_runtime::{beacon::Pulse, beacon_api::RandomnessBeaconApi};
+use sc_client_api::{AuxStore, BlockchainEvents};
 use sc_consensus_babe::BabeWorkerHandle;
 use sc_consensus_beefy_etf::communication::notification::{
 	BeefyBestBlockStream, BeefyVersionedFinalityProofStream,
@@ -85,6 +89,12 @@ pub struct BeefyDeps {
 	pub subscription_executor: SubscriptionTaskExecutor,
 }
 
+/// Dependencies for the ETF randomness beacon
+pub struct BeaconDeps {
+	/// Executor to drive the subscription manager in the beacon RPC handler.
+	pub subscription_executor: SubscriptionTaskExecutor,
+}
+
 /// Full client dependencies.
 pub struct FullDeps<C, P, SC, B> {
 	/// The client instance to use.
@@ -103,6 +113,8 @@ pub struct FullDeps<C, P, SC, B> {
 	pub grandpa: GrandpaDeps<B>,
 	/// BEEFY specific dependencies.
 	pub beefy: BeefyDeps,
+	/// Randomness beacon specific dependencies.
+	pub beacon: BeaconDeps,
 	/// Shared statement store reference.
 	pub statement_store: Arc<dyn sp_statement_store::StatementStore>,
 	/// The backend used by the node.
@@ -122,6 +134,7 @@ pub fn create_full<C, P, SC, B>(
 		babe,
 		grandpa,
 		beefy,
+		beacon,
 		statement_store,
 		backend,
 		mixnet_api,
@@ -132,6 +145,7 @@ where
 		+ sc_client_api::BlockBackend<Block>
 		+ HeaderBackend<Block>
 		+ AuxStore
+		+ BlockchainEvents<Block>
 		+ HeaderMetadata<Block, Error = BlockChainError>
 		+ Sync
 		+ Send
@@ -141,11 +155,14 @@ where
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
 	C::Api: BabeApi<Block>,
 	C::Api: BlockBuilder<Block>,
+	C::Api: RandomnessBeaconApi<Block, BlockNumber, Pulse>,
+	C::Api: BeefyApi<Block, BeefyId>,
 	P: TransactionPool + 'static,
 	SC: SelectChain<Block> + 'static,
 	B: sc_client_api::Backend<Block> + Send + Sync + 'static,
 	B::State: sc_client_api::backend::StateBackend<sp_runtime::traits::HashingFor<Block>>,
 {
+	use beacon::{Beacon, BeaconApiServer};
 	use mmr_rpc::{Mmr, MmrApiServer};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApiServer};
 	use sc_consensus_babe_rpc::{Babe, BabeApiServer};
@@ -191,8 +208,14 @@ where
 	)?;
 	io.merge(TransactionPayment::new(client.clone()).into_rpc())?;
 	io.merge(
-		Babe::new(client.clone(), babe_worker_handle.clone(), keystore, select_chain, deny_unsafe)
-			.into_rpc(),
+		Babe::new(
+			client.clone(),
+			babe_worker_handle.clone(),
+			keystore.clone(),
+			select_chain,
+			deny_unsafe,
+		)
+		.into_rpc(),
 	)?;
 	io.merge(
 		Grandpa::new(
@@ -211,7 +234,7 @@ where
 	)?;
 
 	io.merge(StateMigration::new(client.clone(), backend, deny_unsafe).into_rpc())?;
-	io.merge(Dev::new(client, deny_unsafe).into_rpc())?;
+	io.merge(Dev::new(client.clone(), deny_unsafe).into_rpc())?;
 	let statement_store =
 		sc_rpc::statement::StatementStore::new(statement_store, deny_unsafe).into_rpc();
 	io.merge(statement_store)?;
@@ -230,5 +253,7 @@ where
 		.into_rpc(),
 	)?;
 
+	io.merge(Beacon::new(client, beacon.subscription_executor, keystore).into_rpc())?;
+
 	Ok(io)
 }
\ No newline at end of file
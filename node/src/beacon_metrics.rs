@@ -0,0 +1,227 @@
+//! Prometheus metrics for the ETF randomness beacon.
+//!
+//! The heights of the beacon are followed in imported and finalized blocks through the
+//! [`RandomnessBeaconApi`] runtime API. The pulses of the local authority are measured on their
+//! way into the transaction pool: the BEEFY-ETF gadget submits them through the offchain
+//! transaction pool it is given, which is wrapped in a [`MeteredPool`].
+
+use polkadot_sdk::*;
+
+use codec::{Decode, Encode};
+use futures::{future, stream, StreamExt};
+use node_primitives::{Block, BlockNumber};
+use node_template_runtime::{
+	beacon::{self, Pulse},
+	beacon_api::RandomnessBeaconApi,
+	RuntimeCall, UncheckedExtrinsic,
+};
+use sc_client_api::BlockchainEvents;
+use sc_transaction_pool_api::LocalTransactionPool;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::traits::Block as BlockT;
+use std::{sync::Arc, time::Instant};
+use substrate_prometheus_endpoint::{
+	exponential_buckets, register, Counter, Gauge, Histogram, HistogramOpts, PrometheusError,
+	Registry, U64,
+};
+
+const LOG_TARGET: &str = "etf-beacon";
+
+/// Metrics of the randomness beacon, registered on the node's Prometheus endpoint.
+#[derive(Clone)]
+pub struct Metrics {
+	latest_pulse: Gauge<U64>,
+	best_lag: Gauge<U64>,
+	finalized_lag: Gauge<U64>,
+	pulses_submitted: Counter<U64>,
+	pulses_rejected: Counter<U64>,
+	shares_per_pulse: Histogram,
+	pulse_verification_time: Histogram,
+}
+
+impl Metrics {
+	/// Register the beacon metrics in `registry`.
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			latest_pulse: register(
+				Gauge::new(
+					"substrate_etf_beacon_latest_pulse",
+					"Block number of the latest pulse published in the best chain",
+				)?,
+				registry,
+			)?,
+			best_lag: register(
+				Gauge::new(
+					"substrate_etf_beacon_best_lag",
+					"Number of blocks between the best block and the latest pulse",
+				)?,
+				registry,
+			)?,
+			finalized_lag: register(
+				Gauge::new(
+					"substrate_etf_beacon_finalized_lag",
+					"Number of blocks between the finalized block and its latest pulse",
+				)?,
+				registry,
+			)?,
+			pulses_submitted: register(
+				Counter::new(
+					"substrate_etf_beacon_pulses_submitted_total",
+					"Number of pulses submitted by the local authority",
+				)?,
+				registry,
+			)?,
+			pulses_rejected: register(
+				Counter::new(
+					"substrate_etf_beacon_pulses_rejected_total",
+					"Number of pulses of the local authority rejected by the transaction pool",
+				)?,
+				registry,
+			)?,
+			shares_per_pulse: register(
+				Histogram::with_opts(
+					HistogramOpts::new(
+						"substrate_etf_beacon_shares_per_pulse",
+						"Number of authority shares collected in the pulses of the local authority",
+					)
+					.buckets(vec![1.0, 2.0, 4.0, 8.0, 16.0, 32.0, 64.0, 100.0]),
+				)?,
+				registry,
+			)?,
+			pulse_verification_time: register(
+				Histogram::with_opts(
+					HistogramOpts::new(
+						"substrate_etf_beacon_pulse_verification_seconds",
+						"Time to validate a pulse of the local authority in the transaction pool",
+					)
+					.buckets(exponential_buckets(0.001, 2.0, 12)?),
+				)?,
+				registry,
+			)?,
+		})
+	}
+
+	/// Update the heights of the beacon from the best and finalized blocks of `client`.
+	fn update_heights<C>(&self, client: &C)
+	where
+		C: ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+		C::Api: RandomnessBeaconApi<Block, BlockNumber, Pulse>,
+	{
+		let info = client.info();
+		match client.runtime_api().latest_height(info.best_hash) {
+			Ok(latest) => {
+				self.latest_pulse.set(latest.into());
+				self.best_lag.set(info.best_number.saturating_sub(latest).into());
+			},
+			Err(e) => log::debug!(target: LOG_TARGET, "Failed to read the beacon height: {}", e),
+		}
+		match client.runtime_api().latest_height(info.finalized_hash) {
+			Ok(latest) =>
+				self.finalized_lag.set(info.finalized_number.saturating_sub(latest).into()),
+			Err(e) => log::debug!(target: LOG_TARGET, "Failed to read the beacon height: {}", e),
+		}
+	}
+}
+
+/// Follow the heights of the beacon as blocks are imported and finalized.
+pub async fn run_height_metrics<C>(client: Arc<C>, metrics: Metrics)
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + BlockchainEvents<Block>,
+	C::Api: RandomnessBeaconApi<Block, BlockNumber, Pulse>,
+{
+	let imported = client
+		.import_notification_stream()
+		.filter(|notification| future::ready(notification.is_new_best))
+		.map(|_| ());
+	let finalized = client.finality_notification_stream().map(|_| ());
+	let mut updates = stream::select(imported, finalized);
+	while updates.next().await.is_some() {
+		metrics.update_heights(&*client);
+	}
+}
+
+/// A local transaction pool that measures the pulses submitted through it.
+pub struct MeteredPool<P> {
+	inner: P,
+	metrics: Metrics,
+}
+
+impl<P> MeteredPool<P> {
+	/// Measure the pulses submitted to `inner`.
+	pub fn new(inner: P, metrics: Metrics) -> Self {
+		Self { inner, metrics }
+	}
+}
+
+impl<P: LocalTransactionPool<Block = Block>> LocalTransactionPool for MeteredPool<P> {
+	type Block = Block;
+	type Hash = P::Hash;
+	type Error = P::Error;
+
+	fn submit_local(
+		&self,
+		at: <Block as BlockT>::Hash,
+		xt: <Block as BlockT>::Extrinsic,
+	) -> Result<Self::Hash, Self::Error> {
+		let Some(signatures) = pulse_signatures(&xt) else {
+			return self.inner.submit_local(at, xt)
+		};
+		self.metrics.pulses_submitted.inc();
+		self.metrics.shares_per_pulse.observe(beacon::signers(&signatures).count() as f64);
+
+		// Validating a pulse in the pool verifies the signature it recovers.
+		let started = Instant::now();
+		let result = self.inner.submit_local(at, xt);
+		self.metrics.pulse_verification_time.observe(started.elapsed().as_secs_f64());
+		if let Err(e) = &result {
+			self.metrics.pulses_rejected.inc();
+			log::debug!(target: LOG_TARGET, "Pulse rejected by the transaction pool: {:?}", e);
+		}
+		result
+	}
+}
+
+/// The signatures of `xt`, if it submits a pulse.
+fn pulse_signatures(xt: &<Block as BlockT>::Extrinsic) -> Option<Vec<Vec<u8>>> {
+	match UncheckedExtrinsic::decode(&mut &xt.encode()[..]).ok()?.function {
+		RuntimeCall::RandomnessBeacon(pallet_randomness_beacon::Call::write_pulse {
+			signatures,
+			..
+		}) => Some(signatures),
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn opaque(call: RuntimeCall) -> <Block as BlockT>::Extrinsic {
+		let xt = UncheckedExtrinsic::new_unsigned(call);
+		Decode::decode(&mut &xt.encode()[..]).unwrap()
+	}
+
+	#[test]
+	fn pulses_are_recognised() {
+		let signatures = vec![vec![1; 48], vec![], vec![2; 48]];
+		let pulse = opaque(RuntimeCall::RandomnessBeacon(
+			pallet_randomness_beacon::Call::write_pulse {
+				signatures: signatures.clone(),
+				block_number: 7,
+			},
+		));
+		let remark =
+			opaque(RuntimeCall::System(frame_system::Call::remark { remark: vec![1, 2, 3] }));
+
+		assert_eq!(pulse_signatures(&pulse), Some(signatures));
+		assert_eq!(pulse_signatures(&remark), None);
+	}
+
+	#[test]
+	fn metrics_register_once() {
+		let registry = Registry::new();
+		assert!(Metrics::register(&registry).is_ok());
+		assert!(Metrics::register(&registry).is_err());
+	}
+}
@@ -0,0 +1,146 @@
+//! Watchdog of the ETF randomness beacon.
+//!
+//! When the beacon stalls, timelocked calls and randomness requests silently wait for pulses
+//! that do not come. The watchdog checks the health of the beacon on every finalized block, as
+//! reported by the `etf_health` RPC, and warns as soon as the beacon falls behind finality.
+
+use polkadot_sdk::*;
+
+use beefy_primitives::{bls_crypto::AuthorityId as BeefyId, BeefyApi};
+use futures::StreamExt;
+use node_primitives::{Block, BlockNumber};
+use node_rpc::beacon::{beacon_health, BeaconHealth, BeaconStatus, STALLED_AFTER};
+use node_template_runtime::{beacon::Pulse, beacon_api::RandomnessBeaconApi};
+use sc_client_api::BlockchainEvents;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_keystore::KeystorePtr;
+use std::sync::Arc;
+
+const LOG_TARGET: &str = "etf-beacon";
+
+/// Check the health of the beacon on every finalized block and log when it degrades.
+///
+/// Warnings are repeated every [`STALLED_AFTER`] blocks for as long as the beacon is behind.
+pub async fn run<C>(client: Arc<C>, keystore: KeystorePtr)
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + BlockchainEvents<Block>,
+	C::Api: RandomnessBeaconApi<Block, BlockNumber, Pulse> + BeefyApi<Block, BeefyId>,
+{
+	let mut finality = client.finality_notification_stream();
+	let mut last = (BeaconStatus::Healthy, 0);
+	while finality.next().await.is_some() {
+		match beacon_health(&*client, &keystore) {
+			Ok(health) => last = report(&health, last),
+			Err(e) => log::debug!(target: LOG_TARGET, "Failed to check the beacon health: {}", e),
+		}
+	}
+}
+
+/// Log `health` if it differs from the `last` status reported, or if the beacon is still
+/// behind [`STALLED_AFTER`] blocks after it was last reported.
+///
+/// Returns the status reported last and the finalized block it was reported at.
+fn report(
+	health: &BeaconHealth,
+	(status, reported_at): (BeaconStatus, BlockNumber),
+) -> (BeaconStatus, BlockNumber) {
+	let repeat = health.finalized_block.saturating_sub(reported_at) >= STALLED_AFTER;
+	if health.status == status && (status == BeaconStatus::Healthy || !repeat) {
+		return (status, reported_at)
+	}
+
+	let lag = health.finalized_block.saturating_sub(health.latest_pulse);
+	match health.status {
+		BeaconStatus::Healthy => log::info!(
+			target: LOG_TARGET,
+			"Randomness beacon caught up with finality at pulse #{}",
+			health.latest_pulse,
+		),
+		BeaconStatus::Lagging => log::warn!(
+			target: LOG_TARGET,
+			"Randomness beacon is lagging: latest pulse #{} is {} blocks behind finalized #{}",
+			health.latest_pulse,
+			lag,
+			health.finalized_block,
+		),
+		BeaconStatus::Stalled => log::warn!(
+			target: LOG_TARGET,
+			"Randomness beacon has stalled: no pulse since #{}, {} blocks behind finalized #{}. \
+			 Timelocked calls for later blocks are not executed until it resumes.",
+			health.latest_pulse,
+			lag,
+			health.finalized_block,
+		),
+	}
+	if health.status != BeaconStatus::Healthy && health.current_set_is_silent() {
+		log::warn!(
+			target: LOG_TARGET,
+			"Authority set #{} has not published a pulse since it took over, the latest pulse was \
+			 signed by set #{}",
+			health.authority_set_id.unwrap_or_default(),
+			health.latest_pulse_authority_set_id.unwrap_or_default(),
+		);
+	}
+	if health.status != BeaconStatus::Healthy && !health.holds_share {
+		log::debug!(target: LOG_TARGET, "The local node holds no share of the round secret");
+	}
+	(health.status, health.finalized_block)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn health(finalized_block: BlockNumber, latest_pulse: BlockNumber) -> BeaconHealth {
+		BeaconHealth {
+			status: BeaconStatus::from_lag(finalized_block - latest_pulse),
+			finalized_block,
+			latest_pulse,
+			authority_set_id: Some(0),
+			latest_pulse_authority_set_id: Some(0),
+			holds_share: true,
+		}
+	}
+
+	#[test]
+	fn status_changes_are_reported_once() {
+		let last = report(&health(10, 9), (BeaconStatus::Healthy, 0));
+		assert_eq!(last, (BeaconStatus::Healthy, 0));
+
+		let last = report(&health(30, 10), last);
+		assert_eq!(last, (BeaconStatus::Lagging, 30));
+		assert_eq!(report(&health(31, 10), last), last);
+
+		let last = report(&health(100, 10), last);
+		assert_eq!(last, (BeaconStatus::Stalled, 100));
+		// Still stalled, reported again once enough blocks have passed.
+		assert_eq!(report(&health(120, 10), last), last);
+		assert_eq!(report(&health(164, 10), last), (BeaconStatus::Stalled, 164));
+
+		assert_eq!(report(&health(170, 168), last), (BeaconStatus::Healthy, 170));
+	}
+
+	#[test]
+	fn silent_authority_sets_are_told_apart_after_a_rotation() {
+		// Set 1 took over at block 95, the latest pulse is still the one set 0 signed.
+		let mut rotated = health(100, 90);
+		rotated.authority_set_id = Some(1);
+		assert_eq!(rotated.status, BeaconStatus::Lagging);
+		assert!(rotated.current_set_is_silent());
+		assert_eq!(report(&rotated, (BeaconStatus::Healthy, 0)), (BeaconStatus::Lagging, 100));
+
+		// Once its pulses land, the beacon is healthy again.
+		let mut resumed = health(104, 103);
+		resumed.authority_set_id = Some(1);
+		resumed.latest_pulse_authority_set_id = Some(1);
+		assert_eq!(resumed.status, BeaconStatus::Healthy);
+		assert!(!resumed.current_set_is_silent());
+		assert_eq!(report(&resumed, (BeaconStatus::Lagging, 100)), (BeaconStatus::Healthy, 104));
+
+		// A set that keeps publishing is not silent, even while the beacon is behind.
+		let lagging = health(100, 90);
+		assert_eq!(lagging.status, BeaconStatus::Lagging);
+		assert!(!lagging.current_set_is_silent());
+	}
+}
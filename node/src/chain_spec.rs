@@ -38,19 +38,7 @@ use sp_runtime::{
 	RuntimeAppPublic,
 };
 
-use w3f_bls::{
-	DoublePublicKey,
-	DoublePublicKeyScheme,
-	EngineBLS,
-	TinyBLS377, 
-	SerializableToBytes
-};
-
-use ark_serialize::CanonicalSerialize;
-use ark_std::UniformRand;
-
-use rand::rngs::OsRng;
-use etf_crypto_primitives::dpss::acss::DoubleSecret;
+use crate::etf_genesis::{default_threshold, etf_genesis, verify_genesis, EtfGenesis};
 
 pub use node_template_runtime::RuntimeGenesisConfig;
 use node_template_runtime::constants::currency::DOLLARS;
@@ -77,6 +65,23 @@ pub struct Extensions {
 	pub light_sync_state: sc_sync_state_rpc::LightSyncStateExtension,
 }
 
+/// Options for the ETF genesis of the built-in chain specs.
+#[derive(Debug, Clone, Default)]
+pub struct EtfGenesisParams {
+	/// The number of ETF shares needed to recover the round secret, for the genesis authorities
+	/// and every later set.
+	///
+	/// `pallet_etf` keeps no threshold, so it is recorded in the `etfResharing` genesis config
+	/// next to the `etf` one. Defaults to a two thirds majority of each set that tolerates one
+	/// offline authority, see [`pallet_etf_resharing::default_threshold`].
+	pub threshold: Option<usize>,
+	/// Derive the round secret deterministically from this seed.
+	///
+	/// Anyone knowing the seed knows the round secret, so this is only accepted for the `dev`
+	/// and `local` chains.
+	pub seed: Option<[u8; 32]>,
+}
+
 /// Specialized `ChainSpec`.
 pub type ChainSpec = sc_service::GenericChainSpec<Extensions>;
 /// Flaming Fir testnet generator
@@ -242,24 +247,30 @@ fn configure_accounts_for_staging_testnet() -> (
 	(initial_authorities, root_key, endowed_accounts)
 }
 
-fn staging_testnet_config_genesis() -> serde_json::Value {
+fn staging_testnet_config_genesis() -> Result<serde_json::Value, String> {
 	let (initial_authorities, root_key, endowed_accounts) =
 		configure_accounts_for_staging_testnet();
-	testnet_genesis(initial_authorities, vec![], root_key, Some(endowed_accounts))
+	testnet_genesis(
+		initial_authorities,
+		vec![],
+		root_key,
+		Some(endowed_accounts),
+		Default::default(),
+	)
 }
 
 /// Staging testnet config.
-pub fn staging_testnet_config() -> ChainSpec {
-	ChainSpec::builder(wasm_binary_unwrap(), Default::default())
+pub fn staging_testnet_config() -> Result<ChainSpec, String> {
+	Ok(ChainSpec::builder(wasm_binary_unwrap(), Default::default())
 		.with_name("Staging Testnet")
 		.with_id("staging_testnet")
 		.with_chain_type(ChainType::Live)
-		.with_genesis_config_patch(staging_testnet_config_genesis())
+		.with_genesis_config_patch(staging_testnet_config_genesis()?)
 		.with_telemetry_endpoints(
 			TelemetryEndpoints::new(vec![(STAGING_TELEMETRY_URL.to_string(), 0)])
 				.expect("Staging telemetry url is valid; qed"),
 		)
-		.build()
+		.build())
 }
 
 /// Helper function to generate a crypto pair from seed.
@@ -323,7 +334,8 @@ fn configure_accounts(
 	initial_nominators: Vec<AccountId>,
 	endowed_accounts: Option<Vec<AccountId>>,
 	stash: Balance,
-) -> (
+	etf: EtfGenesisParams,
+) -> Result<(
 	Vec<(
 		AccountId,
 		AccountId,
@@ -339,7 +351,7 @@ fn configure_accounts(
 	Vec<(AccountId, AccountId, Balance, StakerStatus<AccountId>)>,
 	Vec<u8>,
 	Vec<(BeefyId, Vec<u8>)>
-) {
+), String> {
 	let mut endowed_accounts: Vec<AccountId> = endowed_accounts.unwrap_or_else(|| {
 		vec![
 			// the ETF faucet 5GCW5pYV7gdhRY7VAhn6WACiVyxn5sFcqfdBd5UayKbWipQn
@@ -390,62 +402,94 @@ fn configure_accounts(
 
 	let num_endowed_accounts = endowed_accounts.len();
 
-	// Aggregate BLS signature scheme with Signature in G1 for BLS12-377 curve.
-	let (double_public_bytes, genesis_shares) = etf_genesis::<TinyBLS377>(
-		initial_authorities.iter().map(|x| x.7.clone()).collect::<Vec<_>>(),
-		vec!["Alice", "Bob", "Charlie"],
-	);
-	(initial_authorities, endowed_accounts, num_endowed_accounts, stakers, double_public_bytes, genesis_shares)
+	let etf_threshold =
+		etf.threshold.unwrap_or_else(|| default_threshold(initial_authorities.len()));
+	let genesis = etf_genesis(
+		&initial_authorities.iter().map(|x| x.7.clone()).collect::<Vec<_>>(),
+		etf_threshold,
+		etf.seed,
+	)
+	.map_err(|e| format!("Failed to run the ETF genesis ceremony: {}", e))?;
+	Ok((
+		initial_authorities,
+		endowed_accounts,
+		num_endowed_accounts,
+		stakers,
+		genesis.round_pubkey,
+		genesis.resharings,
+	))
 }
 
-/// Helper function to prepare initial secrets and resharing for ETF conensus
-/// return a vec of (authority id, resharing, pubkey commitment) along with ibe public key against the master secret
-pub fn etf_genesis<E: EngineBLS>(
-		initial_authorities: Vec<BeefyId>, 
-		seeds: Vec<&str>
-	) -> (Vec<u8>, Vec<(BeefyId, Vec<u8>)>) {
-	let msk_prime = E::Scalar::rand(&mut OsRng);
-	let keypair = w3f_bls::KeypairVT::<E>::generate(&mut OsRng);
-	let msk: E::Scalar = keypair.secret.0;
-	let double_public: DoublePublicKey<E> =  DoublePublicKey(
-		keypair.into_public_key_in_signature_group().0,
-		keypair.public.0,
-	);
+/// Read the `etf` genesis config of `spec` and the threshold of its `etfResharing` genesis
+/// config, along with the BEEFY keys of the authorities in `session.keys`.
+///
+/// Returns `None` for raw chain specs, which no longer carry the genesis config.
+pub fn read_etf_genesis(
+	spec: &dyn sc_service::ChainSpec,
+) -> Result<Option<(EtfGenesis, Vec<BeefyId>)>, String> {
+	let json: serde_json::Value = serde_json::from_str(&spec.as_json(false)?)
+		.map_err(|e| format!("Failed to parse chain spec: {}", e))?;
+	let Some(config) = json
+		.pointer("/genesis/runtimeGenesis/patch")
+		.or_else(|| json.pointer("/genesis/runtimeGenesis/config"))
+	else {
+		return Ok(None)
+	};
+
+	let invalid = |field: &str, e: serde_json::Error| format!("Invalid `etf.{}`: {}", field, e);
+	let etf = config.get("etf").ok_or("The chain spec has no `etf` genesis config")?;
+	let resharings: Vec<(BeefyId, Vec<u8>)> =
+		serde_json::from_value(etf["genesisResharing"].clone())
+			.map_err(|e| invalid("genesisResharing", e))?;
+	let round_pubkey: Vec<u8> = serde_json::from_value(etf["roundPubkey"].clone())
+		.map_err(|e| invalid("roundPubkey", e))?;
+
+	let authorities = config
+		.pointer("/session/keys")
+		.and_then(|keys| keys.as_array())
+		.map_or(&[][..], Vec::as_slice)
+		.iter()
+		.map(|keys| serde_json::from_value::<BeefyId>(keys[2]["beefy"].clone()))
+		.collect::<Result<Vec<_>, _>>()
+		.map_err(|e| format!("Invalid BEEFY key in `session.keys`: {}", e))?;
+	let genesis = EtfGenesis {
+		round_pubkey,
+		threshold: config
+			.pointer("/etfResharing/threshold")
+			.and_then(|threshold| threshold.as_u64())
+			.filter(|threshold| *threshold > 0)
+			.map_or_else(
+				|| default_threshold(authorities.len()),
+				|threshold| threshold as usize,
+			),
+		resharings,
+	};
+	Ok(Some((genesis, authorities)))
+}
 
-	let double_secret = DoubleSecret::<E>(msk, msk_prime);
-
-	let mut double_public_bytes = Vec::new();
-	double_public.serialize_compressed(&mut double_public_bytes).unwrap();
-
-	let genesis_resharing = double_secret.reshare(
-		&initial_authorities.iter().map(|authority| {
-			w3f_bls::single::PublicKey::<E>(
-				w3f_bls::double::DoublePublicKey::<E>::from_bytes(
-					&authority.to_raw_vec()
-				).unwrap().1
-			)
-		}).collect::<Vec<_>>(), 
-		initial_authorities.len() as u8, // threshold = full set of authorities for now
-		&mut OsRng,
-	).unwrap();
-
-	let resharings = initial_authorities.iter().enumerate().map(|(idx, _)| {
-		let pok = &genesis_resharing[idx].1;
-		let mut bytes = Vec::new();
-		pok.serialize_compressed(&mut bytes).unwrap();
-
-		let seed = seeds[idx];
-		let test = get_pair_from_seed::<BeefyId>(seed);
-		let t = sp_core::bls::Pair::<TinyBLS377>::from(test);
-		let o = t.acss_recover(&bytes, initial_authorities.len() as u8)
-			.expect("genesis shares should be well formatted");
-		let etf_id = BeefyId::from(o.public());
-		(etf_id, bytes)
-	}).collect::<Vec<_>>();
-	(double_public_bytes, resharings)
+/// Check the `etf` genesis config of `spec` before it is used.
+///
+/// The number of resharings has to match the BEEFY authorities in `session.keys`, every
+/// resharing has to prove that it encrypts a share to the authority at its index and the share
+/// commitments have to be consistent with `roundPubkey`. Raw chain specs no longer carry the
+/// genesis config and are not checked.
+pub fn verify_etf_genesis(spec: &dyn sc_service::ChainSpec) -> Result<(), String> {
+	let Some((genesis, authorities)) = read_etf_genesis(spec)? else { return Ok(()) };
+	if genesis.resharings.len() != authorities.len() {
+		return Err(format!(
+			"The `etf` genesis config has {} resharings for {} BEEFY authorities in \
+			`session.keys`",
+			genesis.resharings.len(),
+			authorities.len(),
+		))
+	}
+
+	verify_genesis(&genesis, &authorities).map_err(|e| format!("Invalid `etf` genesis config: {}", e))
 }
 
 /// Helper function to create RuntimeGenesisConfig json patch for testing.
+///
+/// Fails if the ETF genesis ceremony rejects the authorities or the options in `etf`.
 pub fn testnet_genesis(
 	initial_authorities: Vec<(
 		AccountId,
@@ -460,12 +504,21 @@ pub fn testnet_genesis(
 	initial_nominators: Vec<AccountId>,
 	root_key: AccountId,
 	endowed_accounts: Option<Vec<AccountId>>,
-) -> serde_json::Value {
+	etf: EtfGenesisParams,
+) -> Result<serde_json::Value, String> {
+	// Zero keeps the default threshold for every later authority set.
+	let etf_threshold = etf.threshold.unwrap_or_default();
 	let (initial_authorities, endowed_accounts, num_endowed_accounts, stakers, round_key, genesis_shares) =
-		configure_accounts(initial_authorities, initial_nominators, endowed_accounts, STASH);
+		configure_accounts(
+			initial_authorities,
+			initial_nominators,
+			endowed_accounts,
+			STASH,
+			etf,
+		)?;
 
 	// panic!("{:?}", round_key.len());
-	serde_json::json!({
+	Ok(serde_json::json!({
 		"balances": {
 			"balances": endowed_accounts.iter().cloned().map(|x| (x, ENDOWMENT)).collect::<Vec<_>>(),
 		},
@@ -518,6 +571,9 @@ pub fn testnet_genesis(
 			"genesisResharing": genesis_shares,
 			"roundPubkey": round_key,			
 		},
+		"etfResharing": {
+			"threshold": etf_threshold,
+		},
 		"beefy": {
 			"authorities": Vec::<BeefyId>::new(),
 			"genesisBlock": Some(1),
@@ -531,10 +587,10 @@ pub fn testnet_genesis(
 			"minCreateBond": 10 * DOLLARS,
 			"minJoinBond": 1 * DOLLARS,
 		},
-	})
+	}))
 }
 
-fn development_config_genesis_json() -> serde_json::Value {
+fn development_config_genesis_json(etf: EtfGenesisParams) -> Result<serde_json::Value, String> {
 	testnet_genesis(
 		vec![
 			authority_keys_from_seed("Alice"), 
@@ -544,36 +600,50 @@ fn development_config_genesis_json() -> serde_json::Value {
 		vec![],
 		get_account_id_from_seed::<sr25519::Public>("Alice"),
 		None,
+		etf,
 	)
 }
 
 /// Development config (single validator Alice).
 pub fn development_config() -> ChainSpec {
-	ChainSpec::builder(wasm_binary_unwrap(), Default::default())
+	development_config_with_etf(Default::default())
+		.expect("the default ETF genesis of the development authorities is valid; qed")
+}
+
+/// Development config (single validator Alice) with the given ETF genesis options.
+pub fn development_config_with_etf(etf: EtfGenesisParams) -> Result<ChainSpec, String> {
+	Ok(ChainSpec::builder(wasm_binary_unwrap(), Default::default())
 		.with_name("Development")
 		.with_id("dev")
 		.with_chain_type(ChainType::Development)
-		.with_genesis_config_patch(development_config_genesis_json())
-		.build()
+		.with_genesis_config_patch(development_config_genesis_json(etf)?)
+		.build())
 }
 
-fn local_testnet_genesis() -> serde_json::Value {
+fn local_testnet_genesis(etf: EtfGenesisParams) -> Result<serde_json::Value, String> {
 	testnet_genesis(
 		vec![authority_keys_from_seed("Alice"), authority_keys_from_seed("Bob")],
 		vec![],
 		get_account_id_from_seed::<sr25519::Public>("Alice"),
 		None,
+		etf,
 	)
 }
 
 /// Local testnet config (multivalidator Alice + Bob).
 pub fn local_testnet_config() -> ChainSpec {
-	ChainSpec::builder(wasm_binary_unwrap(), Default::default())
+	local_testnet_config_with_etf(Default::default())
+		.expect("the default ETF genesis of the local testnet authorities is valid; qed")
+}
+
+/// Local testnet config (multivalidator Alice + Bob) with the given ETF genesis options.
+pub fn local_testnet_config_with_etf(etf: EtfGenesisParams) -> Result<ChainSpec, String> {
+	Ok(ChainSpec::builder(wasm_binary_unwrap(), Default::default())
 		.with_name("Local Testnet")
 		.with_id("local_testnet")
 		.with_chain_type(ChainType::Local)
-		.with_genesis_config_patch(local_testnet_genesis())
-		.build()
+		.with_genesis_config_patch(local_testnet_genesis(etf)?)
+		.build())
 }
 
 #[cfg(test)]
@@ -585,16 +655,19 @@ pub(crate) mod tests {
 
 	/// Local testnet config (single validator - Alice).
 	pub fn integration_test_config_with_single_authority() -> ChainSpec {
+		let genesis = testnet_genesis(
+			vec![authority_keys_from_seed("Alice")],
+			vec![],
+			get_account_id_from_seed::<sr25519::Public>("Alice"),
+			None,
+			Default::default(),
+		)
+		.unwrap();
 		ChainSpec::builder(wasm_binary_unwrap(), Default::default())
 			.with_name("Integration Test")
 			.with_id("test")
 			.with_chain_type(ChainType::Development)
-			.with_genesis_config_patch(testnet_genesis(
-				vec![authority_keys_from_seed("Alice")],
-				vec![],
-				get_account_id_from_seed::<sr25519::Public>("Alice"),
-				None,
-			))
+			.with_genesis_config_patch(genesis)
 			.build()
 	}
 
@@ -604,7 +677,7 @@ pub(crate) mod tests {
 			.with_name("Integration Test")
 			.with_id("test")
 			.with_chain_type(ChainType::Development)
-			.with_genesis_config_patch(local_testnet_genesis())
+			.with_genesis_config_patch(local_testnet_genesis(Default::default()).unwrap())
 			.build()
 	}
 
@@ -636,8 +709,90 @@ pub(crate) mod tests {
 		local_testnet_config().build_storage().unwrap();
 	}
 
+	#[test]
+	fn test_create_chain_spec_with_etf_threshold() {
+		let etf = EtfGenesisParams { threshold: Some(2), ..Default::default() };
+		let genesis = testnet_genesis(
+			vec![
+				authority_keys_from_seed("Alice"),
+				authority_keys_from_seed("Bob"),
+				authority_keys_from_seed("Charlie"),
+			],
+			vec![],
+			get_account_id_from_seed::<sr25519::Public>("Alice"),
+			None,
+			etf,
+		)
+		.unwrap();
+		let spec = ChainSpec::builder(wasm_binary_unwrap(), Default::default())
+			.with_name("Integration Test")
+			.with_id("test")
+			.with_chain_type(ChainType::Development)
+			.with_genesis_config_patch(genesis)
+			.build();
+		spec.build_storage().unwrap();
+
+		let (genesis, authorities) = read_etf_genesis(&spec).unwrap().unwrap();
+		assert_eq!((genesis.threshold, authorities.len()), (2, 3));
+		verify_etf_genesis(&spec).unwrap();
+	}
+
+	#[test]
+	fn test_seeded_development_chain_spec_is_reproducible() {
+		let etf = || EtfGenesisParams { seed: Some([7; 32]), ..Default::default() };
+		let first = development_config_with_etf(etf()).unwrap().as_json(false).unwrap();
+		let second = development_config_with_etf(etf()).unwrap().as_json(false).unwrap();
+
+		assert_eq!(first, second);
+	}
+
+	#[test]
+	fn test_local_testnet_etf_genesis_is_valid() {
+		verify_etf_genesis(&local_testnet_config()).unwrap();
+	}
+
+	#[test]
+	fn test_local_testnet_tolerates_an_offline_validator() {
+		let (genesis, authorities) = read_etf_genesis(&local_testnet_config()).unwrap().unwrap();
+
+		assert_eq!((genesis.threshold, authorities.len()), (1, 2));
+	}
+
+	#[test]
+	fn test_etf_genesis_with_missing_resharing_is_rejected() {
+		let mut json: serde_json::Value =
+			serde_json::from_str(&local_testnet_config().as_json(false).unwrap()).unwrap();
+		json["genesis"]["runtimeGenesis"]["patch"]["etf"]["genesisResharing"]
+			.as_array_mut()
+			.unwrap()
+			.pop();
+		let spec = ChainSpec::from_json_bytes(serde_json::to_vec(&json).unwrap()).unwrap();
+
+		assert!(verify_etf_genesis(&spec).is_err());
+	}
+
+	#[test]
+	fn test_etf_genesis_with_resharings_for_other_authorities_is_rejected() {
+		let mut json: serde_json::Value =
+			serde_json::from_str(&local_testnet_config().as_json(false).unwrap()).unwrap();
+		json["genesis"]["runtimeGenesis"]["patch"]["session"]["keys"]
+			.as_array_mut()
+			.unwrap()
+			.swap(0, 1);
+		let spec = ChainSpec::from_json_bytes(serde_json::to_vec(&json).unwrap()).unwrap();
+
+		assert!(verify_etf_genesis(&spec).is_err());
+	}
+
 	#[test]
 	fn test_staging_test_net_chain_spec() {
-		staging_testnet_config().build_storage().unwrap();
+		staging_testnet_config().unwrap().build_storage().unwrap();
+	}
+
+	#[test]
+	fn test_invalid_etf_threshold_is_an_error() {
+		let etf = EtfGenesisParams { threshold: Some(3), ..Default::default() };
+
+		assert!(local_testnet_config_with_etf(etf).is_err());
 	}
 }
\ No newline at end of file
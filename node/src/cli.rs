@@ -46,6 +46,13 @@ pub struct Cli {
 	#[allow(missing_docs)]
 	#[clap(flatten)]
 	pub storage_monitor: sc_storage_monitor::StorageMonitorParams,
+
+	/// Derive the ETF round secret of the `dev` or `local` chain from this phrase.
+	///
+	/// This makes the round public key reproducible, e.g. to precompute ciphertexts in tests.
+	/// Anyone knowing the phrase knows the round secret, so it is refused for any other chain.
+	#[arg(long, global = true, value_name = "PHRASE")]
+	pub etf_seed: Option<String>,
 }
 
 /// Possible subcommands of the main binary.
@@ -76,6 +83,14 @@ pub enum Subcommand {
 	/// Sign a message, with a given (secret) key.
 	Sign(sc_cli::SignCmd),
 
+	/// Sub-commands concerned with the randomness beacon.
+	#[command(subcommand)]
+	Beacon(BeaconCmd),
+
+	/// Timelock encrypt messages for, or decrypt them with, a randomness beacon pulse.
+	#[command(subcommand)]
+	Timelock(TimelockCmd),
+
 	/// Build a chain specification.
 	BuildSpec(sc_cli::BuildSpecCmd),
 
@@ -88,6 +103,9 @@ pub enum Subcommand {
 	/// Export the state of a given block into a chain spec.
 	ExportState(sc_cli::ExportStateCmd),
 
+	/// Export the randomness beacon pulses over a block range.
+	ExportPulses(ExportPulsesCmd),
+
 	/// Import blocks.
 	ImportBlocks(sc_cli::ImportBlocksCmd),
 
@@ -99,4 +117,205 @@ pub enum Subcommand {
 
 	/// Db meta columns information.
 	ChainInfo(sc_cli::ChainInfoCmd),
+
+	/// Run the ETF genesis ceremony and write the `etf` and `etfResharing` genesis configs.
+	EtfGenesis(EtfGenesisCmd),
+}
+
+/// The `etf-genesis` command used to produce ETF genesis material offline.
+///
+/// Only the public BEEFY keys of the authorities are needed, so the ceremony can be run on an
+/// air-gapped machine and its output copied into a chain spec.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct EtfGenesisCmd {
+	/// A JSON file holding an array of the authorities' SS58 encoded BEEFY keys.
+	#[arg(long, value_name = "PATH", required_unless_present = "keystore_path")]
+	pub authorities: Option<std::path::PathBuf>,
+
+	/// Use the BEEFY keys held by the keystore at this path as the authorities.
+	#[arg(long, value_name = "PATH", conflicts_with = "authorities")]
+	pub keystore_path: Option<std::path::PathBuf>,
+
+	/// The number of shares needed to recover the round secret, for the genesis authorities
+	/// and every later set.
+	///
+	/// Defaults to a two thirds majority of each set, floor(2n/3) + 1, capped to n - 1 so that
+	/// one authority can be offline.
+	#[arg(long)]
+	pub threshold: Option<usize>,
+
+	/// Write the `etf` and `etfResharing` genesis config patch to this file.
+	#[arg(long, short, value_name = "PATH")]
+	pub output: std::path::PathBuf,
+
+	/// Also merge the patch into this (non-raw) chain spec, in place.
+	#[arg(long, value_name = "PATH")]
+	pub merge_into: Option<std::path::PathBuf>,
+}
+
+/// The `export-pulses` command.
+///
+/// Pulses are read from the best block of the node database and written one per line, for
+/// statistical analysis of the beacon output.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct ExportPulsesCmd {
+	/// Output file name or stdout if unspecified.
+	#[arg(value_name = "PATH")]
+	pub output: Option<std::path::PathBuf>,
+
+	/// The first block to export the pulse of.
+	#[arg(long, value_name = "BLOCK", default_value_t = 1)]
+	pub from: u32,
+
+	/// The last block to export the pulse of. Defaults to the latest pulse.
+	#[arg(long, value_name = "BLOCK")]
+	pub to: Option<u32>,
+
+	/// The output format, JSON Lines or CSV.
+	#[arg(long, default_value = "jsonl", value_parser = ["jsonl", "csv"])]
+	pub format: String,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: sc_cli::SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: sc_cli::DatabaseParams,
+}
+
+/// The `beacon` subcommands.
+#[derive(Debug, clap::Subcommand)]
+pub enum BeaconCmd {
+	/// Audit the pulses published in exported blocks against the keys in the node database.
+	Verify(BeaconVerifyCmd),
+}
+
+/// The `beacon verify` command.
+///
+/// Every pulse in the export is checked against the authority set that was active at its
+/// block and the shares the set held, as recorded in the state of the node database. Missing,
+/// duplicate and invalid pulses are reported, and the command fails if there are any.
+///
+/// The shares of a set are read from the state of the blocks it signed, so pulses older than
+/// the kept state can only be audited against an archive node.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct BeaconVerifyCmd {
+	/// Blocks written by `export-blocks`.
+	#[arg(value_name = "PATH")]
+	pub input: std::path::PathBuf,
+
+	/// The exported blocks are in binary format.
+	#[arg(long)]
+	pub binary: bool,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: sc_cli::SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: sc_cli::DatabaseParams,
+}
+
+/// The `timelock` subcommands.
+#[derive(Debug, clap::Subcommand)]
+pub enum TimelockCmd {
+	/// Encrypt a message so that the pulse for a future block decrypts it.
+	Encrypt(TimelockEncryptCmd),
+	/// Decrypt a message with the pulse of the block it was encrypted for.
+	Decrypt(TimelockDecryptCmd),
+}
+
+/// The `timelock encrypt` command.
+///
+/// The output is the ciphertext format of `pallet_scheduler` sealed calls and `pallet_murmur`.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct TimelockEncryptCmd {
+	/// The block whose pulse decrypts the message.
+	#[arg(long)]
+	pub block: u32,
+
+	/// The message to encrypt. Read from stdin if not given.
+	#[arg(long)]
+	pub message: Option<String>,
+
+	/// The message is hex encoded, e.g. a SCALE encoded call.
+	#[arg(long)]
+	pub hex: bool,
+
+	/// The hex encoded round public key.
+	///
+	/// Defaults to the round public key in the genesis of the chain spec.
+	#[arg(long, value_name = "HEX", conflicts_with = "from_state")]
+	pub round_pubkey: Option<String>,
+
+	/// Read the round public key from the best block in the node database.
+	#[arg(long)]
+	pub from_state: bool,
+
+	/// The id of the BEEFY authority set expected to sign the pulse.
+	///
+	/// Only the pulse signed by this set decrypts the message. The set changes every session,
+	/// so the set of a future block is not known in advance: this is required unless the block
+	/// is already imported and `--from-state` reads its set from the node database.
+	#[arg(long, value_name = "ID")]
+	pub validator_set_id: Option<u64>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: sc_cli::SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: sc_cli::DatabaseParams,
+}
+
+/// The `timelock decrypt` command.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct TimelockDecryptCmd {
+	/// The block the message was encrypted for.
+	#[arg(long)]
+	pub block: u32,
+
+	/// The hex encoded ciphertext. Read from stdin if not given.
+	#[arg(long, value_name = "HEX")]
+	pub ciphertext: Option<String>,
+
+	/// Print the plaintext hex encoded.
+	#[arg(long)]
+	pub hex: bool,
+
+	/// The hex encoded pulse signature of the block, e.g. from `etf_getPulse`.
+	///
+	/// It is checked against the round public key in the genesis of the chain spec.
+	#[arg(long, value_name = "HEX", conflicts_with = "blocks")]
+	pub signature: Option<String>,
+
+	/// Recover the pulse from blocks written by `export-blocks`.
+	///
+	/// The signatures are checked against the share commitments in the state of the block in
+	/// the node database, which only archive nodes keep for older blocks. Without this or
+	/// `--signature`, the pulse is read from the node database.
+	#[arg(long, value_name = "PATH")]
+	pub blocks: Option<std::path::PathBuf>,
+
+	/// The exported blocks are in binary format.
+	#[arg(long, requires = "blocks")]
+	pub binary: bool,
+
+	/// The id of the BEEFY authority set that signed the pulse.
+	///
+	/// Read from the authority set history in the node database if not given, which fails for
+	/// blocks that are not imported yet.
+	#[arg(long, value_name = "ID")]
+	pub validator_set_id: Option<u64>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: sc_cli::SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: sc_cli::DatabaseParams,
 }
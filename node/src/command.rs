@@ -20,11 +20,12 @@ use polkadot_sdk::*;
 
 use super::benchmarking::{inherent_benchmark_data, RemarkBuilder, TransferKeepAliveBuilder};
 use crate::{
-	chain_spec, service,
+	chain_spec, etf_genesis, inspect, service,
 	service::{new_partial, FullClient},
-	Cli, Subcommand,
+	BeaconCmd, Cli, Subcommand, TimelockCmd,
 };
 use frame_benchmarking_cli::*;
+use node_inspect::cli::InspectSubCmd;
 use node_template_runtime::{ExistentialDeposit, RuntimeApi};
 use node_primitives::Block;
 use sc_cli::{Result, SubstrateCli};
@@ -60,18 +61,33 @@ impl SubstrateCli for Cli {
 	}
 
 	fn load_spec(&self, id: &str) -> std::result::Result<Box<dyn sc_service::ChainSpec>, String> {
+		let etf = chain_spec::EtfGenesisParams {
+			seed: self.etf_seed.as_deref().map(etf_genesis::seed_from_phrase),
+			..Default::default()
+		};
+		if etf.seed.is_some() && !matches!(id, "" | "dev" | "local") {
+			return Err(format!(
+				"--etf-seed is only supported for the dev and local chains, not {:?}: a seeded \
+				round secret is public",
+				id,
+			))
+		}
+
 		let spec = match id {
 			"" =>
 				return Err(
 					"Please specify which chain you want to run, e.g. --dev or --chain=local"
 						.into(),
 				),
-			"dev" => Box::new(chain_spec::development_config()),
-			"local" => Box::new(chain_spec::local_testnet_config()),
+			"dev" => Box::new(chain_spec::development_config_with_etf(etf)?),
+			"local" => Box::new(chain_spec::local_testnet_config_with_etf(etf)?),
 			"fir" | "flaming-fir" => Box::new(chain_spec::flaming_fir_config()?),
-			"staging" => Box::new(chain_spec::staging_testnet_config()),
-			path =>
-				Box::new(chain_spec::ChainSpec::from_json_file(std::path::PathBuf::from(path))?),
+			"staging" => Box::new(chain_spec::staging_testnet_config()?),
+			path => {
+				let spec = chain_spec::ChainSpec::from_json_file(std::path::PathBuf::from(path))?;
+				chain_spec::verify_etf_genesis(&spec)?;
+				Box::new(spec)
+			},
 		};
 		Ok(spec)
 	}
@@ -91,7 +107,14 @@ pub fn run() -> Result<()> {
 		Some(Subcommand::Inspect(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 
-			runner.sync_run(|config| cmd.run::<Block, RuntimeApi>(config))
+			match cmd.command {
+				InspectSubCmd::Timelocked { from, to } =>
+					runner.sync_run(|config| inspect::timelocked(config, from, to, cmd.json)),
+				_ => runner.sync_run(|config| {
+					let printer = inspect::EtfPrinter::new(cmd.json);
+					cmd.run_with_printer::<Block, RuntimeApi, _>(config, printer)
+				}),
+			}
 		},
 		Some(Subcommand::Benchmark(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
@@ -174,7 +197,10 @@ pub fn run() -> Result<()> {
 		Some(Subcommand::Vanity(cmd)) => cmd.run(),
 		Some(Subcommand::BuildSpec(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
-			runner.sync_run(|config| cmd.run(config.chain_spec, config.network))
+			runner.sync_run(|config| {
+				chain_spec::verify_etf_genesis(&*config.chain_spec).map_err(sc_cli::Error::Input)?;
+				cmd.run(config.chain_spec, config.network)
+			})
 		},
 		Some(Subcommand::CheckBlock(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
@@ -198,6 +224,7 @@ pub fn run() -> Result<()> {
 				Ok((cmd.run(client, config.chain_spec), task_manager))
 			})
 		},
+		Some(Subcommand::ExportPulses(cmd)) => cmd.run(&cli),
 		Some(Subcommand::ImportBlocks(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
@@ -227,5 +254,9 @@ pub fn run() -> Result<()> {
 			let runner = cli.create_runner(cmd)?;
 			runner.sync_run(|config| cmd.run::<Block>(&config))
 		},
+		Some(Subcommand::EtfGenesis(cmd)) => cmd.run(),
+		Some(Subcommand::Beacon(BeaconCmd::Verify(cmd))) => cmd.run(&cli),
+		Some(Subcommand::Timelock(TimelockCmd::Encrypt(cmd))) => cmd.run(&cli),
+		Some(Subcommand::Timelock(TimelockCmd::Decrypt(cmd))) => cmd.run(&cli),
 	}
 }
@@ -0,0 +1,514 @@
+//! Implementation of the ETF specific subcommands.
+//!
+//! The argument definitions live in `cli.rs`, which is also compiled by the build script, so
+//! only the logic is kept here.
+
+use polkadot_sdk::*;
+
+use crate::{
+	chain_spec,
+	cli::{
+		BeaconVerifyCmd, EtfGenesisCmd, ExportPulsesCmd, TimelockDecryptCmd, TimelockEncryptCmd,
+	},
+	etf_genesis::{
+		authorities_from_file, authorities_from_keystore, default_threshold, etf_genesis,
+		EtfGenesis,
+	},
+	pulses::{self, PulseKeys},
+	service::{new_partial, FullClient},
+	timelock, Cli,
+};
+use beefy_primitives::bls_crypto::AuthorityId as BeefyId;
+use codec::Decode;
+use node_primitives::BlockNumber;
+use node_template_runtime::beacon_api::{PulseStatus, RandomnessBeaconApi};
+use sc_cli::{CliConfiguration, DatabaseParams, Error, Result, SharedParams, SubstrateCli};
+use sc_service::ChainSpec;
+use serde_json::{json, Value};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use std::{
+	collections::BTreeMap,
+	fs::File,
+	io::{BufWriter, Read, Write},
+	path::Path,
+};
+
+impl EtfGenesisCmd {
+	/// Run the ceremony and write its output.
+	pub fn run(&self) -> Result<()> {
+		let authorities = match (&self.authorities, &self.keystore_path) {
+			(Some(path), _) => authorities_from_file(path).map_err(Error::Input)?,
+			(None, Some(path)) => {
+				let keystore = sc_keystore::LocalKeystore::open(path, None)
+					.map_err(|e| Error::Input(format!("Failed to open keystore: {}", e)))?;
+				authorities_from_keystore(&keystore)
+			},
+			(None, None) => return Err(Error::Input("No authorities were given".into())),
+		};
+
+		let threshold = self.threshold.unwrap_or_else(|| default_threshold(authorities.len()));
+		let genesis = etf_genesis(&authorities, threshold, None).map_err(Error::Input)?;
+
+		let patch = json!({
+			"etf": etf_genesis_config(&genesis),
+			"etfResharing": etf_resharing_genesis_config(self.threshold),
+		});
+		let patch = serde_json::to_string_pretty(&patch).map_err(|e| Error::Input(e.to_string()))?;
+		std::fs::write(&self.output, patch)?;
+		println!(
+			"Wrote the ETF genesis for {} authorities with a threshold of {} to {}",
+			authorities.len(),
+			threshold,
+			self.output.display(),
+		);
+
+		if let Some(path) = &self.merge_into {
+			merge_into_chain_spec(path, &genesis, self.threshold).map_err(Error::Input)?;
+			println!("Merged the ETF genesis into {}", path.display());
+		}
+
+		Ok(())
+	}
+}
+
+/// The `etf` section of the runtime genesis config.
+fn etf_genesis_config(genesis: &EtfGenesis) -> Value {
+	json!({
+		"genesisResharing": genesis.resharings,
+		"roundPubkey": genesis.round_pubkey,
+	})
+}
+
+/// The `etfResharing` section of the runtime genesis config, holding the configured ETF
+/// threshold.
+///
+/// Without a configured threshold every authority set, the genesis one included, uses the
+/// default for its size, which is stored as zero.
+fn etf_resharing_genesis_config(threshold: Option<usize>) -> Value {
+	json!({ "threshold": threshold.unwrap_or_default() })
+}
+
+/// Replace the `etf` genesis config and the ETF threshold of the chain spec at `path`.
+fn merge_into_chain_spec(
+	path: &Path,
+	genesis: &EtfGenesis,
+	threshold: Option<usize>,
+) -> std::result::Result<(), String> {
+	let bytes = std::fs::read(path)
+		.map_err(|e| format!("Failed to read chain spec {}: {}", path.display(), e))?;
+	let mut spec: Value = serde_json::from_slice(&bytes)
+		.map_err(|e| format!("Failed to parse chain spec {}: {}", path.display(), e))?;
+
+	let runtime_genesis = spec
+		.pointer_mut("/genesis/runtimeGenesis")
+		.and_then(Value::as_object_mut)
+		.ok_or_else(|| {
+			format!(
+				"{} has no runtime genesis config; raw chain specs cannot be merged into, \
+				merge before running `build-spec --raw`",
+				path.display(),
+			)
+		})?;
+	// Specs built from a genesis patch only carry the patch, others carry the full config.
+	let config = ["patch", "config"]
+		.into_iter()
+		.find(|key| runtime_genesis.contains_key(*key))
+		.ok_or_else(|| format!("{} has neither a genesis patch nor config", path.display()))?;
+	runtime_genesis[config]["etf"] = etf_genesis_config(genesis);
+	runtime_genesis[config]["etfResharing"] = etf_resharing_genesis_config(threshold);
+
+	let merged = serde_json::to_string_pretty(&spec)
+		.map_err(|e| format!("Failed to serialize chain spec: {}", e))?;
+	std::fs::write(path, merged)
+		.map_err(|e| format!("Failed to write chain spec {}: {}", path.display(), e))
+}
+
+impl BeaconVerifyCmd {
+	/// Audit the exported pulses and print the findings.
+	pub fn run(&self, cli: &Cli) -> Result<()> {
+		let blocks = pulses::read_exported_blocks(&self.input, self.binary).map_err(Error::Input)?;
+		let pulses = blocks.iter().flat_map(|signed| pulses::submitted_pulses(&signed.block));
+
+		let mut audit = None;
+		cli.create_runner(self)?.sync_run(|config| {
+			// The task manager has to outlive the runtime calls.
+			let partial = new_partial(&config, None)?;
+			// The shares only change along with the authority set.
+			let mut keys = BTreeMap::<u64, PulseKeys>::new();
+			let keys_at = |block| {
+				let set_id = authority_set_at(&partial.client, block)?;
+				if let Some(keys) = keys.get(&set_id) {
+					return Ok(keys.clone())
+				}
+				let set_keys = pulse_keys_at(&partial.client, set_id, block)?;
+				keys.insert(set_id, set_keys.clone());
+				Ok(set_keys)
+			};
+			audit = Some(pulses::audit(keys_at, pulses).map_err(Error::Input)?);
+			Ok::<_, Error>(())
+		})?;
+		let audit = audit.expect("set by the runner above; qed");
+
+		println!("Checked {} pulses in {} blocks", audit.pulses, blocks.len());
+		for gap in &audit.gaps {
+			println!("Missing pulses for blocks {} to {}", gap.start(), gap.end());
+		}
+		for block in &audit.duplicates {
+			println!("Duplicate pulses for block {}", block);
+		}
+		for block in &audit.invalid {
+			println!("Invalid pulse for block {}", block);
+		}
+		for (block, indices) in &audit.invalid_signatures {
+			println!("Invalid signatures from authorities {:?} for block {}", indices, block);
+		}
+
+		if audit.is_ok() {
+			Ok(())
+		} else {
+			Err(Error::Input("The beacon audit failed".into()))
+		}
+	}
+}
+
+/// A pulse as written by `export-pulses`.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportedPulse {
+	block_number: BlockNumber,
+	signature: String,
+	randomness: String,
+	authority_set_id: u64,
+}
+
+impl ExportPulsesCmd {
+	/// Write the pulses of the requested range to the output.
+	pub fn run(&self, cli: &Cli) -> Result<()> {
+		cli.create_runner(self)?.sync_run(|config| {
+			// The task manager has to outlive the runtime calls.
+			let partial = new_partial(&config, None)?;
+			let client = &partial.client;
+			let best = client.info().best_hash;
+			let api = client.runtime_api();
+			let api_error = |e: sp_api::ApiError| Error::Application(Box::new(e));
+			let to = match self.to {
+				Some(to) => to,
+				None => api.latest_height(best).map_err(api_error)?,
+			};
+
+			let mut output: Box<dyn Write> = match &self.output {
+				Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+				None => Box::new(std::io::stdout().lock()),
+			};
+			let csv = self.format == "csv";
+			if csv {
+				writeln!(output, "blockNumber,signature,randomness,authoritySetId")?;
+			}
+
+			for block in self.from..=to {
+				let Some(pulse) = api.pulse(best, block).map_err(api_error)? else { continue };
+				let authority_set_id = authority_set_at(client, block).map_err(Error::Input)?;
+				let pulse = ExportedPulse {
+					block_number: block,
+					signature: array_bytes::bytes2hex("0x", pulse.body.signature.into_inner()),
+					randomness: array_bytes::bytes2hex("0x", pulse.body.randomness.into_inner()),
+					authority_set_id,
+				};
+
+				if csv {
+					writeln!(
+						output,
+						"{},{},{},{}",
+						pulse.block_number,
+						pulse.signature,
+						pulse.randomness,
+						pulse.authority_set_id,
+					)?;
+				} else {
+					let line =
+						serde_json::to_string(&pulse).map_err(|e| Error::Input(e.to_string()))?;
+					writeln!(output, "{}", line)?;
+				}
+			}
+
+			output.flush()?;
+			Ok::<_, Error>(())
+		})
+	}
+}
+
+impl TimelockEncryptCmd {
+	/// Encrypt the message and print the hex encoded ciphertext.
+	pub fn run(&self, cli: &Cli) -> Result<()> {
+		let message = read_input(self.message.as_deref(), self.hex)?;
+		let (round_pubkey, validator_set_id) = match &self.round_pubkey {
+			Some(round_pubkey) =>
+				(decode_hex(round_pubkey, "round public key")?, self.explicit_set_id()?),
+			None => {
+				let mut key = None;
+				cli.create_runner(self)?.sync_run(|config| {
+					key = Some(if self.from_state {
+						// The task manager has to outlive the runtime calls.
+						let partial = new_partial(&config, None)?;
+						let client = &partial.client;
+						let round_pubkey = client
+							.runtime_api()
+							.round_pubkey(client.info().best_hash)
+							.map_err(|e| Error::Application(Box::new(e)))?;
+						let set_id = match self.validator_set_id {
+							Some(set_id) => set_id,
+							None => authority_set_at(client, self.block).map_err(Error::Input)?,
+						};
+						(round_pubkey, set_id)
+					} else {
+						(genesis_of(&*config.chain_spec)?.round_pubkey, self.explicit_set_id()?)
+					});
+					Ok::<_, Error>(())
+				})?;
+				key.expect("set by the runner above; qed")
+			},
+		};
+
+		let ciphertext = timelock::encrypt(&round_pubkey, self.block, validator_set_id, &message)
+			.map_err(Error::Input)?;
+		println!("{}", array_bytes::bytes2hex("0x", &ciphertext));
+		Ok(())
+	}
+
+	/// The authority set given on the command line, which is required unless it can be read
+	/// from the node database.
+	fn explicit_set_id(&self) -> Result<u64> {
+		self.validator_set_id.ok_or_else(|| {
+			Error::Input(format!(
+				"The authority set that signs the pulse of block {} is not known, pass \
+				--validator-set-id",
+				self.block,
+			))
+		})
+	}
+}
+
+impl TimelockDecryptCmd {
+	/// Decrypt the ciphertext with the pulse of its block and print the plaintext.
+	pub fn run(&self, cli: &Cli) -> Result<()> {
+		let ciphertext = read_input(self.ciphertext.as_deref(), true)?;
+
+		let mut pulse = None;
+		cli.create_runner(self)?.sync_run(|config| {
+			// The task manager has to outlive the runtime calls.
+			let partial = new_partial(&config, None)?;
+			let client = &partial.client;
+			let set_id = match self.validator_set_id {
+				Some(set_id) => set_id,
+				None => authority_set_at(client, self.block).map_err(Error::Input)?,
+			};
+			pulse = Some(match (&self.signature, &self.blocks) {
+				(Some(signature), _) => (
+					genesis_of(&*config.chain_spec)?.round_pubkey,
+					decode_hex(signature, "signature")?,
+					set_id,
+				),
+				(None, Some(path)) => {
+					let keys = pulse_keys_at(client, set_id, self.block).map_err(Error::Input)?;
+					let signature = exported_pulse(&keys, path, self.binary, self.block)?;
+					(keys.round_pubkey, signature, set_id)
+				},
+				(None, None) => {
+					let (round_pubkey, signature) = stored_pulse(client, self.block)?;
+					(round_pubkey, signature, set_id)
+				},
+			});
+			Ok::<_, Error>(())
+		})?;
+		let (round_pubkey, signature, validator_set_id) =
+			pulse.expect("set by the runner above; qed");
+
+		let plaintext = timelock::decrypt(
+			&round_pubkey,
+			self.block,
+			validator_set_id,
+			&signature,
+			&ciphertext,
+		)
+		.map_err(Error::Input)?;
+		if self.hex {
+			println!("{}", array_bytes::bytes2hex("0x", &plaintext));
+		} else {
+			std::io::stdout().write_all(&plaintext)?;
+		}
+		Ok(())
+	}
+}
+
+impl CliConfiguration for BeaconVerifyCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+impl CliConfiguration for ExportPulsesCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+impl CliConfiguration for TimelockEncryptCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+impl CliConfiguration for TimelockDecryptCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+/// Read a command input from its argument, or from stdin if it was not given.
+fn read_input(arg: Option<&str>, hex: bool) -> Result<Vec<u8>> {
+	let input = match arg {
+		Some(arg) => arg.as_bytes().to_vec(),
+		None => {
+			let mut input = Vec::new();
+			std::io::stdin().lock().read_to_end(&mut input)?;
+			input
+		},
+	};
+	if hex {
+		decode_hex(String::from_utf8_lossy(&input).trim(), "input")
+	} else {
+		Ok(input)
+	}
+}
+
+fn decode_hex(hex: &str, what: &str) -> Result<Vec<u8>> {
+	array_bytes::hex2bytes(hex).map_err(|_| Error::Input(format!("Invalid hex encoded {}", what)))
+}
+
+/// The ETF genesis of the chain spec the command runs against.
+fn genesis_of(spec: &dyn ChainSpec) -> Result<EtfGenesis> {
+	chain_spec::read_etf_genesis(spec)
+		.map_err(Error::Input)?
+		.map(|(genesis, _)| genesis)
+		.ok_or_else(|| Error::Input("Raw chain specs do not carry the ETF genesis".into()))
+}
+
+/// The id of the BEEFY authority set that signs the pulse of `block`, from the authority set
+/// history in the best state of the node database.
+///
+/// Fails for blocks that are not imported yet: the authority set changes every session, so the
+/// set of a future block is only known once the block is reached.
+pub(crate) fn authority_set_at(
+	client: &FullClient,
+	block: BlockNumber,
+) -> std::result::Result<u64, String> {
+	let info = client.info();
+	if block > info.best_number {
+		return Err(format!(
+			"Block {} is not imported yet, the authority set that signs its pulse is not known",
+			block,
+		))
+	}
+	client
+		.runtime_api()
+		.authority_set_at(info.best_hash, block)
+		.map_err(|e| e.to_string())?
+		.ok_or_else(|| format!("The authority set of block {} is not known", block))
+}
+
+/// The keys the authority set `set_id` signs the pulse of `block` with, from the state of the
+/// block in the node database.
+///
+/// Fails if the state of the block was pruned, the keys of older pulses are only kept by
+/// archive nodes.
+fn pulse_keys_at(
+	client: &FullClient,
+	set_id: u64,
+	block: BlockNumber,
+) -> std::result::Result<PulseKeys, String> {
+	let unavailable = |e: &dyn std::fmt::Display| {
+		format!(
+			"The state of block {} is not available ({}), the keys of its pulse are only kept \
+			by archive nodes",
+			block, e,
+		)
+	};
+	let hash = client
+		.hash(block)
+		.map_err(|e| unavailable(&e))?
+		.ok_or_else(|| unavailable(&"the block is not imported"))?;
+	let api = client.runtime_api();
+	let (commitments, threshold) = api.share_commitments(hash).map_err(|e| unavailable(&e))?;
+	let round_pubkey = api.round_pubkey(hash).map_err(|e| unavailable(&e))?;
+	let commitments = commitments
+		.iter()
+		.map(|commitment| BeefyId::decode(&mut &commitment[..]))
+		.collect::<std::result::Result<Vec<_>, _>>()
+		.map_err(|e| format!("Invalid share commitment at block {}: {}", block, e))?;
+	Ok(PulseKeys {
+		validator_set_id: set_id,
+		round_pubkey,
+		commitments,
+		threshold: threshold as usize,
+	})
+}
+
+/// The round public key and the pulse signature for `block` in the node database, whether the
+/// pulse was published or backfilled.
+fn stored_pulse(client: &FullClient, block: BlockNumber) -> Result<(Vec<u8>, Vec<u8>)> {
+	let best = client.info().best_hash;
+	let api = client.runtime_api();
+	let api_error = |e: sp_api::ApiError| Error::Application(Box::new(e));
+	let status = api.pulse_status(best, block).map_err(api_error)?;
+	let round_pubkey = api.round_pubkey(best).map_err(api_error)?;
+	let signature = api.signature_at(best, block).map_err(api_error)?;
+	let signature = signature.ok_or_else(|| match status {
+		PulseStatus::Missing =>
+			Error::Input(format!("The pulse of block {} is permanently missing", block)),
+		_ => Error::Input(format!("No pulse has been published for block {}", block)),
+	})?;
+	Ok((round_pubkey, signature))
+}
+
+/// The pulse signature for `block`, recovered from the blocks exported to `path`.
+fn exported_pulse(
+	keys: &PulseKeys,
+	path: &Path,
+	binary: bool,
+	block: BlockNumber,
+) -> Result<Vec<u8>> {
+	let blocks = pulses::read_exported_blocks(path, binary).map_err(Error::Input)?;
+	let pulse = blocks
+		.iter()
+		.flat_map(|signed| pulses::submitted_pulses(&signed.block))
+		.find(|pulse| pulse.block_number == block)
+		.ok_or_else(|| {
+			Error::Input(format!("No pulse for block {} in {}", block, path.display()))
+		})?;
+	let checked = pulses::check_pulse(keys, &pulse).map_err(Error::Input)?;
+	checked.signature.ok_or_else(|| {
+		Error::Input(format!(
+			"The pulse for block {} has {} invalid signatures, fewer than {} remain valid",
+			block,
+			checked.invalid_signatures.len(),
+			keys.threshold,
+		))
+	})
+}
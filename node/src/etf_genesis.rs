@@ -0,0 +1,532 @@
+//! ETF genesis ceremony and resharing.
+//!
+//! Produces the `etf` genesis config, i.e. the round public key and one resharing per
+//! authority, from the BEEFY public keys of the initial authorities. No authority secrets are
+//! needed: the master secret is generated and split here, each share is encrypted to its
+//! recipient's public key, and the public commitment to each share is derived from the share
+//! itself rather than recovered with the recipient's key.
+//!
+//! The ceremony runs on BLS12-377, the curve of the BEEFY keys.
+//!
+//! When the authority set changes, the shares are handed over to the incoming authorities by
+//! `pallet_etf_resharing`. Every outgoing authority deals its share like the ceremony deals the
+//! master secret, see [`reshare`], and every incoming authority recovers its new share from the
+//! evaluations dealt to it, see [`recover_share`], and encrypts it to itself, see
+//! [`seal_share`]. Authorities run both sides of the resharing in the `etf_resharing` worker.
+
+use ark_ff::Field;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{UniformRand, Zero};
+use beefy_primitives::bls_crypto::AuthorityId as BeefyId;
+use etf_crypto_primitives::{dpss::acss::DoubleSecret, proofs::hashed_el_gamal_sigma::BatchPoK};
+use rand::{rngs::OsRng, CryptoRng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use sp_core::crypto::ByteArray;
+use sp_keystore::Keystore;
+use std::path::Path;
+use w3f_bls::{single::PublicKey, DoublePublicKey, EngineBLS, SerializableToBytes, TinyBLS377};
+
+/// A share encrypted to its recipient, along with the proof that it was encrypted correctly.
+type Resharing<E> = BatchPoK<<E as EngineBLS>::PublicKeyGroup>;
+
+/// The material placed in the `etf` genesis config.
+#[derive(Debug, Clone)]
+pub struct EtfGenesis {
+	/// The serialized round public key.
+	pub round_pubkey: Vec<u8>,
+	/// The number of shares needed to recover the round secret.
+	pub threshold: usize,
+	/// The commitment to each authority's share, along with its encrypted share.
+	pub resharings: Vec<(BeefyId, Vec<u8>)>,
+}
+
+/// A random polynomial over the scalar field of `E`, whose constant term is the shared secret.
+struct Polynomial<E: EngineBLS>(Vec<E::Scalar>);
+
+impl<E: EngineBLS> Polynomial<E> {
+	/// A random polynomial such that any `threshold` evaluations determine it.
+	fn random<R: RngCore + CryptoRng>(threshold: usize, rng: &mut R) -> Self {
+		Polynomial((0..threshold).map(|_| E::Scalar::rand(rng)).collect())
+	}
+
+	/// A random polynomial with the constant term `secret` such that any `threshold` evaluations
+	/// determine it.
+	fn with_secret<R: RngCore + CryptoRng>(
+		secret: E::Scalar,
+		threshold: usize,
+		rng: &mut R,
+	) -> Self {
+		let mut polynomial = Self::random(threshold, rng);
+		polynomial.0[0] = secret;
+		polynomial
+	}
+
+	fn secret(&self) -> E::Scalar {
+		self.0[0]
+	}
+
+	fn evaluate(&self, x: E::Scalar) -> E::Scalar {
+		self.0.iter().rev().fold(E::Scalar::zero(), |acc, coefficient| acc * x + coefficient)
+	}
+}
+
+/// The evaluation point of the share held by the authority at `index`.
+fn share_index<E: EngineBLS>(index: usize) -> E::Scalar {
+	E::Scalar::from((index + 1) as u64)
+}
+
+/// The public key of `secret` in both the signature and the public key group.
+fn double_public<E: EngineBLS>(secret: E::Scalar) -> DoublePublicKey<E> {
+	let secret = w3f_bls::SecretKeyVT::<E>(secret);
+	let keypair = w3f_bls::KeypairVT::<E> { public: secret.into_public(), secret };
+	DoublePublicKey(keypair.into_public_key_in_signature_group().0, keypair.public.0)
+}
+
+/// Decode the public key a share is encrypted to from an authority's key.
+fn recipient_key<E: EngineBLS>(authority: &BeefyId) -> Result<PublicKey<E>, String> {
+	DoublePublicKey::<E>::from_bytes(&authority.to_raw_vec())
+		.map(|key| PublicKey::<E>(key.1))
+		.map_err(|_| format!("{} is not a valid public key on the ETF curve", authority))
+}
+
+/// Decode a commitment to a share.
+pub(crate) fn commitment_key<E: EngineBLS>(
+	commitment: &BeefyId,
+) -> Result<DoublePublicKey<E>, String> {
+	DoublePublicKey::<E>::from_bytes(&commitment.to_raw_vec())
+		.map_err(|_| format!("Share commitment {} is not a valid public key", commitment))
+}
+
+/// The default resharing threshold, the same `pallet_etf_resharing` uses for later authority
+/// sets.
+pub fn default_threshold(authorities: usize) -> usize {
+	pallet_etf_resharing::default_threshold(authorities as u32) as usize
+}
+
+/// Check that `threshold` shares out of `authorities` can recover the round secret.
+pub fn validate_threshold(threshold: usize, authorities: usize) -> Result<(), String> {
+	if threshold == 0 || threshold > authorities {
+		return Err(format!(
+			"The ETF threshold must be between 1 and the number of authorities ({}), got {}",
+			authorities, threshold,
+		))
+	}
+	Ok(())
+}
+
+/// Derive a genesis seed from a human readable phrase.
+pub fn seed_from_phrase(phrase: &str) -> [u8; 32] {
+	sp_core::blake2_256(phrase.as_bytes())
+}
+
+/// Run the genesis ceremony for the given authorities.
+///
+/// Any `threshold` of the resulting shares recover the round secret. Without a `seed` the
+/// round secret is drawn from the OS randomness. With a `seed` the whole output is
+/// deterministic, which is only meant for development chains: anyone knowing the seed knows
+/// the round secret.
+pub fn etf_genesis(
+	authorities: &[BeefyId],
+	threshold: usize,
+	seed: Option<[u8; 32]>,
+) -> Result<EtfGenesis, String> {
+	match seed {
+		Some(seed) => run_ceremony::<TinyBLS377, _>(
+			authorities,
+			threshold,
+			&mut ChaCha20Rng::from_seed(seed),
+		),
+		None => run_ceremony::<TinyBLS377, _>(authorities, threshold, &mut OsRng),
+	}
+}
+
+fn run_ceremony<E: EngineBLS, R: RngCore + CryptoRng>(
+	authorities: &[BeefyId],
+	threshold: usize,
+	rng: &mut R,
+) -> Result<EtfGenesis, String> {
+	if authorities.is_empty() {
+		return Err("The ETF genesis needs at least one authority".into())
+	}
+	validate_threshold(threshold, authorities.len())?;
+
+	let msk = Polynomial::<E>::random(threshold, rng);
+	let msk_prime = Polynomial::<E>::random(threshold, rng);
+
+	let mut round_pubkey = Vec::new();
+	double_public::<E>(msk.secret())
+		.serialize_compressed(&mut round_pubkey)
+		.map_err(|e| format!("Failed to serialize the round public key: {}", e))?;
+
+	let resharings = deal(&msk, &msk_prime, authorities, rng)?;
+	let genesis = EtfGenesis { round_pubkey, threshold, resharings };
+	verify_commitments_on::<E>(&genesis)?;
+	Ok(genesis)
+}
+
+/// Deal the evaluations of `secret` and `blinding` to `authorities`.
+///
+/// Returns the commitment to the share of every authority, along with the share encrypted to
+/// it.
+fn deal<E: EngineBLS, R: RngCore + CryptoRng>(
+	secret: &Polynomial<E>,
+	blinding: &Polynomial<E>,
+	authorities: &[BeefyId],
+	rng: &mut R,
+) -> Result<Vec<(BeefyId, Vec<u8>)>, String> {
+	let recipients = authorities.iter().map(recipient_key::<E>).collect::<Result<Vec<_>, _>>()?;
+
+	let mut resharings = Vec::with_capacity(authorities.len());
+	for (idx, (authority, recipient)) in authorities.iter().zip(recipients).enumerate() {
+		let x = share_index::<E>(idx);
+		let share = DoubleSecret::<E>(secret.evaluate(x), blinding.evaluate(x));
+		let commitment = BeefyId::from_slice(&double_public::<E>(share.0).to_bytes())
+			.map_err(|_| "A share commitment does not fit a BEEFY public key".to_string())?;
+
+		let (addressee, resharing) = share
+			.reshare(&[recipient.clone()], 1, rng)
+			.map_err(|e| format!("Failed to encrypt the share of {}: {:?}", authority, e))?
+			.pop()
+			.ok_or_else(|| format!("No resharing was produced for {}", authority))?;
+		if addressee != recipient {
+			return Err(format!("The resharing for {} is addressed to another key", authority))
+		}
+
+		let mut bytes = Vec::new();
+		resharing
+			.serialize_compressed(&mut bytes)
+			.map_err(|e| format!("Failed to serialize the resharing of {}: {}", authority, e))?;
+		resharings.push((commitment, bytes));
+	}
+	Ok(resharings)
+}
+
+/// Deal the `share` of an outgoing authority to the `incoming` authorities, such that any
+/// `threshold` of their new shares recover the round secret.
+///
+/// Returns the commitment to the evaluation dealt to every incoming authority, along with the
+/// evaluation encrypted to it, i.e. the content of a `pallet_etf_resharing` dealing.
+pub fn reshare<E: EngineBLS, R: RngCore + CryptoRng>(
+	share: E::Scalar,
+	incoming: &[BeefyId],
+	threshold: usize,
+	rng: &mut R,
+) -> Result<Vec<(BeefyId, Vec<u8>)>, String> {
+	validate_threshold(threshold, incoming.len())?;
+	let secret = Polynomial::<E>::with_secret(share, threshold, rng);
+	let blinding = Polynomial::<E>::random(threshold, rng);
+	deal(&secret, &blinding, incoming, rng)
+}
+
+/// Interpolate at zero the evaluations dealt to an incoming authority, each paired with the
+/// index of its dealer in the outgoing authority set, into its new share.
+pub fn recover_share<E: EngineBLS>(dealt: &[(usize, E::Scalar)]) -> E::Scalar {
+	let indices = dealt.iter().map(|(dealer, _)| *dealer).collect::<Vec<_>>();
+	dealt.iter().fold(E::Scalar::zero(), |acc, (dealer, evaluation)| {
+		acc + *evaluation * lagrange_at_zero::<E>(*dealer, &indices)
+	})
+}
+
+/// Interpolate at zero the commitments dealt to an incoming authority, each paired with the
+/// index of its dealer in the outgoing authority set, into the commitment to its new share.
+pub fn recover_commitment<E: EngineBLS>(dealt: &[(usize, BeefyId)]) -> Result<BeefyId, String> {
+	let indices = dealt.iter().map(|(dealer, _)| *dealer).collect::<Vec<_>>();
+	let (mut signature_group, mut public_key_group) =
+		(E::SignatureGroup::zero(), E::PublicKeyGroup::zero());
+	for (dealer, commitment) in dealt {
+		let lambda = lagrange_at_zero::<E>(*dealer, &indices);
+		let commitment = commitment_key::<E>(commitment)?;
+		signature_group += commitment.0 * lambda;
+		public_key_group += commitment.1 * lambda;
+	}
+	BeefyId::from_slice(&DoublePublicKey::<E>(signature_group, public_key_group).to_bytes())
+		.map_err(|_| "A share commitment does not fit a BEEFY public key".to_string())
+}
+
+/// Encrypt the new `share` of an incoming authority to its own key, in the format of the
+/// genesis resharings.
+///
+/// Returns the commitment to the share along with the encrypted share.
+pub fn seal_share<E: EngineBLS, R: RngCore + CryptoRng>(
+	share: E::Scalar,
+	authority: &BeefyId,
+	rng: &mut R,
+) -> Result<(BeefyId, Vec<u8>), String> {
+	let blinding = Polynomial::<E>::random(1, rng);
+	deal(&Polynomial(vec![share]), &blinding, std::slice::from_ref(authority), rng)?
+		.pop()
+		.ok_or_else(|| format!("No resharing was produced for {}", authority))
+}
+
+/// Check that every resharing of `genesis` proves that it encrypts a share to the authority at
+/// the same index of `authorities`, and that the share commitments are consistent with the
+/// round public key.
+pub fn verify_genesis(genesis: &EtfGenesis, authorities: &[BeefyId]) -> Result<(), String> {
+	verify_resharings::<TinyBLS377>(genesis, authorities)?;
+	verify_commitments(genesis)
+}
+
+fn verify_resharings<E: EngineBLS>(
+	genesis: &EtfGenesis,
+	authorities: &[BeefyId],
+) -> Result<(), String> {
+	if genesis.resharings.len() != authorities.len() {
+		return Err(format!(
+			"There are {} resharings for {} authorities",
+			genesis.resharings.len(),
+			authorities.len(),
+		))
+	}
+
+	for (idx, ((commitment, bytes), authority)) in
+		genesis.resharings.iter().zip(authorities).enumerate()
+	{
+		let resharing = Resharing::<E>::deserialize_compressed(&mut &bytes[..]).map_err(|e| {
+			format!("Resharing {} (commitment {}) does not deserialize: {}", idx, commitment, e)
+		})?;
+		let recipient = recipient_key::<E>(authority)?;
+		if !resharing.verify(recipient.0) {
+			return Err(format!(
+				"The proof of resharing {} (commitment {}) does not hold for its recipient {}",
+				idx, commitment, authority,
+			))
+		}
+	}
+	Ok(())
+}
+
+/// Check that the share commitments of `genesis` interpolate to its round public key.
+///
+/// Any `genesis.threshold` commitments must recover the round public key. This catches shares
+/// that were not evaluated on the same polynomial as the round secret.
+pub fn verify_commitments(genesis: &EtfGenesis) -> Result<(), String> {
+	verify_commitments_on::<TinyBLS377>(genesis)
+}
+
+fn verify_commitments_on<E: EngineBLS>(genesis: &EtfGenesis) -> Result<(), String> {
+	let round_pubkey =
+		DoublePublicKey::<E>::deserialize_compressed(&mut &genesis.round_pubkey[..])
+			.map_err(|e| format!("The round public key does not deserialize: {}", e))?;
+
+	let threshold = genesis.threshold;
+	validate_threshold(threshold, genesis.resharings.len())?;
+
+	let commitments = genesis
+		.resharings
+		.iter()
+		.map(|(commitment, _)| commitment_key::<E>(commitment))
+		.collect::<Result<Vec<_>, _>>()?;
+
+	// Every window of `threshold` consecutive shares has to agree on the round public key.
+	for start in 0..=commitments.len() - threshold {
+		let indices = (start..start + threshold).collect::<Vec<_>>();
+		let (mut signature_group, mut public_key_group) =
+			(E::SignatureGroup::zero(), E::PublicKeyGroup::zero());
+		for &j in &indices {
+			let lambda = lagrange_at_zero::<E>(j, &indices);
+			signature_group += commitments[j].0 * lambda;
+			public_key_group += commitments[j].1 * lambda;
+		}
+
+		if signature_group != round_pubkey.0 || public_key_group != round_pubkey.1 {
+			return Err(format!(
+				"Share commitments {}..{} are inconsistent with the round public key",
+				start,
+				start + threshold,
+			))
+		}
+	}
+
+	Ok(())
+}
+
+/// The Lagrange coefficient of the share at `j` when interpolating `indices` at zero.
+pub(crate) fn lagrange_at_zero<E: EngineBLS>(j: usize, indices: &[usize]) -> E::Scalar {
+	let xj = share_index::<E>(j);
+	indices.iter().filter(|&&m| m != j).fold(E::Scalar::from(1u64), |acc, &m| {
+		let xm = share_index::<E>(m);
+		acc * xm * (xm - xj).inverse().expect("share indices are distinct; qed")
+	})
+}
+
+/// Read the BEEFY public keys of the authorities from a JSON file.
+///
+/// The file holds an array of SS58 encoded keys, the same encoding used for the `beefy` session
+/// keys of a chain spec.
+pub fn authorities_from_file(path: &Path) -> Result<Vec<BeefyId>, String> {
+	let bytes = std::fs::read(path)
+		.map_err(|e| format!("Failed to read authorities from {}: {}", path.display(), e))?;
+	serde_json::from_slice(&bytes)
+		.map_err(|e| format!("Failed to parse authorities from {}: {}", path.display(), e))
+}
+
+/// Collect the BEEFY public keys held by a keystore.
+pub fn authorities_from_keystore(keystore: &dyn Keystore) -> Vec<BeefyId> {
+	keystore
+		.bls377_public_keys(beefy_primitives::KEY_TYPE)
+		.into_iter()
+		.map(BeefyId::from)
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::chain_spec::{get_from_seed, get_pair_from_seed};
+
+	fn authorities(seeds: &[&str]) -> Vec<BeefyId> {
+		seeds.iter().map(|seed| get_from_seed::<BeefyId>(seed)).collect()
+	}
+
+	#[test]
+	fn resharings_are_recoverable_by_their_recipients() {
+		let seeds = ["Alice", "Bob", "Charlie", "Dave"];
+		let genesis = etf_genesis(&authorities(&seeds), 3, None).unwrap();
+
+		assert_eq!(genesis.resharings.len(), seeds.len());
+		for (seed, (commitment, resharing)) in seeds.iter().zip(&genesis.resharings) {
+			let pair = sp_core::bls::Pair::<TinyBLS377>::from(get_pair_from_seed::<BeefyId>(seed));
+			let recovered = pair.acss_recover(resharing, 1).expect("resharing is well formed");
+			assert_eq!(BeefyId::from(recovered.public()), *commitment);
+		}
+	}
+
+	#[test]
+	fn tampered_commitments_are_rejected() {
+		let mut genesis = etf_genesis(&authorities(&["Alice", "Bob"]), 2, None).unwrap();
+		genesis.resharings.swap(0, 1);
+
+		assert!(verify_commitments(&genesis).is_err());
+	}
+
+	#[test]
+	fn malformed_resharings_are_rejected() {
+		let authorities = authorities(&["Alice", "Bob"]);
+		let mut genesis = etf_genesis(&authorities, 2, None).unwrap();
+		assert!(verify_genesis(&genesis, &authorities).is_ok());
+
+		genesis.resharings[1].1.truncate(8);
+		assert!(verify_genesis(&genesis, &authorities).is_err());
+	}
+
+	#[test]
+	fn resharings_are_checked_against_their_recipients() {
+		let authorities = authorities(&["Alice", "Bob", "Charlie"]);
+		let genesis = etf_genesis(&authorities, 2, None).unwrap();
+		assert!(verify_genesis(&genesis, &authorities).is_ok());
+
+		// The shares of Alice and Bob are not encrypted to each other.
+		let mut swapped = authorities.clone();
+		swapped.swap(0, 1);
+		assert!(verify_genesis(&genesis, &swapped).is_err());
+		// Every authority needs a resharing.
+		assert!(verify_genesis(&genesis, &authorities[..2]).is_err());
+	}
+
+	#[test]
+	fn empty_authority_set_is_rejected() {
+		assert!(etf_genesis(&[], 1, None).is_err());
+	}
+
+	#[test]
+	fn seeded_genesis_is_deterministic() {
+		let authorities = authorities(&["Alice", "Bob"]);
+		let seed = Some(seed_from_phrase("dev"));
+
+		let first = etf_genesis(&authorities, 2, seed).unwrap();
+		let second = etf_genesis(&authorities, 2, seed).unwrap();
+		let unseeded = etf_genesis(&authorities, 2, None).unwrap();
+
+		assert_eq!(first.round_pubkey, second.round_pubkey);
+		assert_eq!(first.resharings, second.resharings);
+		assert_ne!(first.round_pubkey, unseeded.round_pubkey);
+	}
+
+	#[test]
+	fn resharing_keeps_the_round_public_key() {
+		let mut rng = ChaCha20Rng::from_seed([7; 32]);
+		// Any two of three outgoing authorities recover the round secret.
+		let secret = Polynomial::<TinyBLS377>::random(2, &mut rng);
+		let incoming = authorities(&["Alice", "Bob", "Charlie", "Dave"]);
+
+		// Two outgoing authorities deal to four incoming ones, any three of which recover the
+		// round secret afterwards.
+		let dealers = [0, 2];
+		let dealings = dealers.map(|dealer| {
+			let share = secret.evaluate(share_index::<TinyBLS377>(dealer));
+			reshare::<TinyBLS377, _>(share, &incoming, 3, &mut rng).unwrap()
+		});
+		let resharings = (0..incoming.len())
+			.map(|j| {
+				let dealt = dealers
+					.iter()
+					.zip(&dealings)
+					.map(|(&dealer, dealing)| (dealer, dealing[j].0.clone()))
+					.collect::<Vec<_>>();
+				(recover_commitment::<TinyBLS377>(&dealt).unwrap(), Vec::new())
+			})
+			.collect();
+
+		let mut round_pubkey = Vec::new();
+		double_public::<TinyBLS377>(secret.secret())
+			.serialize_compressed(&mut round_pubkey)
+			.unwrap();
+		let genesis = EtfGenesis { round_pubkey, threshold: 3, resharings };
+		assert!(verify_commitments(&genesis).is_ok());
+
+		// The dealt evaluations are encrypted to the incoming authorities.
+		let pair = sp_core::bls::Pair::<TinyBLS377>::from(get_pair_from_seed::<BeefyId>("Bob"));
+		let recovered = pair.acss_recover(&dealings[1][1].1, 1).expect("dealing is well formed");
+		assert_eq!(BeefyId::from(recovered.public()), dealings[1][1].0);
+	}
+
+	#[test]
+	fn recovered_shares_are_shares_of_the_round_secret() {
+		let mut rng = ChaCha20Rng::from_seed([7; 32]);
+		let secret = Polynomial::<TinyBLS377>::random(2, &mut rng);
+		let dealt = [0, 2].map(|dealer| {
+			let share = secret.evaluate(share_index::<TinyBLS377>(dealer));
+			(dealer, Polynomial::<TinyBLS377>::with_secret(share, 3, &mut rng))
+		});
+		let shares = (0..4)
+			.map(|j| {
+				let evaluations = dealt
+					.iter()
+					.map(|(dealer, polynomial)| {
+						(*dealer, polynomial.evaluate(share_index::<TinyBLS377>(j)))
+					})
+					.collect::<Vec<_>>();
+				recover_share::<TinyBLS377>(&evaluations)
+			})
+			.collect::<Vec<_>>();
+
+		let any_three = [(0, shares[0]), (1, shares[1]), (3, shares[3])];
+		assert_eq!(recover_share::<TinyBLS377>(&any_three), secret.secret());
+
+		let alice = get_from_seed::<BeefyId>("Alice");
+		let (commitment, sealed) =
+			seal_share::<TinyBLS377, _>(shares[1], &alice, &mut rng).unwrap();
+		assert_eq!(commitment.to_raw_vec(), double_public::<TinyBLS377>(shares[1]).to_bytes());
+		let pair = sp_core::bls::Pair::<TinyBLS377>::from(get_pair_from_seed::<BeefyId>("Alice"));
+		let recovered = pair.acss_recover(&sealed, 1).expect("sealed share is well formed");
+		assert_eq!(BeefyId::from(recovered.public()), commitment);
+	}
+
+	#[test]
+	fn default_threshold_tolerates_one_offline_authority() {
+		assert_eq!(default_threshold(1), 1);
+		assert_eq!(default_threshold(2), 1);
+		assert_eq!(default_threshold(3), 2);
+		assert_eq!(default_threshold(4), 3);
+		assert_eq!(default_threshold(10), 7);
+	}
+
+	#[test]
+	fn out_of_range_thresholds_are_rejected() {
+		let authorities = authorities(&["Alice", "Bob", "Charlie"]);
+
+		assert!(etf_genesis(&authorities, 0, None).is_err());
+		assert!(etf_genesis(&authorities, 4, None).is_err());
+		assert!(etf_genesis(&authorities, 2, None).is_ok());
+	}
+}
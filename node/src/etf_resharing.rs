@@ -0,0 +1,315 @@
+//! Authority side of the resharing of the ETF round secret.
+//!
+//! `pallet_etf_resharing` records the resharing rounds, the authorities taking part in them
+//! run them. On every finalized block the worker looks up the open round through the
+//! [`EtfResharingApi`] runtime API and acts for every local BEEFY key that takes part in it:
+//!
+//! - An outgoing authority decrypts its share and deals it to the incoming authorities, see
+//!   [`reshare`].
+//! - Once the round is qualified, an incoming authority decrypts the evaluations dealt to it
+//!   and recovers its new share from them, see [`recover_share`], which it submits encrypted to
+//!   itself, see [`seal_share`]. If an evaluation does not decrypt to the commitment to it, the
+//!   authority rejects that dealing instead.
+//!
+//! The submissions are unsigned transactions carrying the signature of the BEEFY key. A
+//! submission the round still needs is submitted again after [`RESUBMIT_AFTER`] blocks.
+
+use polkadot_sdk::*;
+
+use crate::etf_genesis::{authorities_from_keystore, recover_share, reshare, seal_share};
+use beefy_primitives::bls_crypto::{AuthorityId as BeefyId, Pair as BeefyPair};
+use codec::{Decode, Encode};
+use futures::StreamExt;
+use node_primitives::{Block, BlockNumber};
+use node_template_runtime::{
+	resharing_api::{EtfResharingApi, RoundStatus},
+	Runtime, RuntimeCall, UncheckedExtrinsic,
+};
+use pallet_etf_resharing::{Call as ResharingCall, Complaint, Dealing, NewShare, SetId};
+use rand::rngs::OsRng;
+use sc_client_api::BlockchainEvents;
+use sc_keystore::LocalKeystore;
+use sc_transaction_pool_api::LocalTransactionPool;
+use sp_api::ProvideRuntimeApi;
+use sp_core::{Get, Pair};
+use sp_runtime::{
+	traits::{Block as BlockT, Header as HeaderT},
+	BoundedVec,
+};
+use std::{collections::HashMap, sync::Arc};
+use w3f_bls::{EngineBLS, SerializableToBytes, TinyBLS377};
+
+const LOG_TARGET: &str = "etf-resharing";
+
+/// The number of blocks after which a submission the round still needs is submitted again.
+pub const RESUBMIT_AFTER: BlockNumber = 10;
+
+/// A share of the round secret, or an evaluation dealt to an incoming authority.
+type Scalar = <TinyBLS377 as EngineBLS>::Scalar;
+
+/// What a local authority has left to do in a round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Task {
+	/// Deal the share of the outgoing authority at the index.
+	Deal(u32),
+	/// Recover and submit the new share of the incoming authority at the index, or reject a
+	/// dealing to it.
+	Recover(u32),
+}
+
+/// Take part in the resharing rounds with the BEEFY keys of `keystore`.
+pub async fn run<C, P>(client: Arc<C>, pool: P, keystore: Arc<LocalKeystore>)
+where
+	C: ProvideRuntimeApi<Block> + BlockchainEvents<Block>,
+	C::Api: EtfResharingApi<Block, BeefyId>,
+	P: LocalTransactionPool<Block = Block>,
+{
+	let mut finality = client.finality_notification_stream();
+	// The block each task of the open round was last submitted at.
+	let mut submitted = HashMap::<(SetId, Task), BlockNumber>::new();
+	while let Some(notification) = finality.next().await {
+		let (at, number) = (notification.hash, *notification.header.number());
+		let round = match client.runtime_api().round(at) {
+			Ok(Some(round)) => round,
+			Ok(None) => {
+				submitted.clear();
+				continue
+			},
+			Err(e) => {
+				log::debug!(target: LOG_TARGET, "Failed to read the resharing round: {}", e);
+				continue
+			},
+		};
+		submitted.retain(|(set_id, _), _| *set_id == round.set_id);
+
+		for (task, authority) in tasks(&round, &authorities_from_keystore(&*keystore)) {
+			let due = submitted
+				.get(&(round.set_id, task))
+				.map_or(true, |last| number >= last + RESUBMIT_AFTER);
+			if !due {
+				continue
+			}
+			let pair = match keystore.key_pair::<BeefyPair>(&authority) {
+				Ok(Some(pair)) => pair,
+				_ => continue,
+			};
+
+			let call = match task {
+				Task::Deal(dealer) => deal(&*client, at, &round, dealer, &pair),
+				Task::Recover(index) => recover(&*client, at, &round, index, &pair),
+			};
+			match call.and_then(|call| submit(&pool, at, call)) {
+				Ok(()) => {
+					log::info!(
+						target: LOG_TARGET,
+						"Submitted {:?} for authority set #{}",
+						task,
+						round.set_id,
+					);
+					submitted.insert((round.set_id, task), number);
+				},
+				Err(e) => log::warn!(
+					target: LOG_TARGET,
+					"Failed to submit {:?} for authority set #{}: {}",
+					task,
+					round.set_id,
+					e,
+				),
+			}
+		}
+	}
+}
+
+/// The tasks left to the `local` authorities in `round`, each with the key of its authority.
+fn tasks(round: &RoundStatus<BeefyId>, local: &[BeefyId]) -> Vec<(Task, BeefyId)> {
+	let mut tasks = Vec::new();
+	if !round.qualified {
+		for (dealer, authority) in round.outgoing.iter().enumerate() {
+			let dealer = dealer as u32;
+			if local.contains(authority) &&
+				!round.dealt.contains(&dealer) &&
+				!round.rejected.contains(&dealer)
+			{
+				tasks.push((Task::Deal(dealer), authority.clone()));
+			}
+		}
+	} else {
+		for (index, authority) in round.incoming.iter().enumerate() {
+			let index = index as u32;
+			if local.contains(authority) && !round.submitted.contains(&index) {
+				tasks.push((Task::Recover(index), authority.clone()));
+			}
+		}
+	}
+	tasks
+}
+
+/// Deal the share of the outgoing authority at `dealer`, held by `pair`, to the incoming
+/// authorities.
+fn deal<C>(
+	client: &C,
+	at: <Block as BlockT>::Hash,
+	round: &RoundStatus<BeefyId>,
+	dealer: u32,
+	pair: &BeefyPair,
+) -> Result<RuntimeCall, String>
+where
+	C: ProvideRuntimeApi<Block>,
+	C::Api: EtfResharingApi<Block, BeefyId>,
+{
+	let (commitment, encrypted) = client
+		.runtime_api()
+		.share(at, dealer)
+		.map_err(|e| format!("Failed to read the share: {}", e))?
+		.ok_or("The authority holds no share")?;
+	let share = decrypt(pair, &encrypted, &commitment)
+		.ok_or("The share does not decrypt to the commitment to it")?;
+
+	let dealt =
+		reshare::<TinyBLS377, _>(share, &round.incoming, round.threshold as usize, &mut OsRng)?;
+	let (commitments, shares): (Vec<_>, Vec<_>) = dealt.into_iter().unzip();
+	let dealing = Dealing::<Runtime> {
+		set_id: round.set_id,
+		dealer,
+		commitments: bounded(commitments)?,
+		shares: bounded(shares.into_iter().map(bounded).collect::<Result<_, _>>()?)?,
+	};
+	let signature = pair.sign(&dealing.encode());
+	Ok(ResharingCall::<Runtime>::submit_dealing { dealing, signature }.into())
+}
+
+/// Recover the new share of the incoming authority at `index`, held by `pair`, or reject the
+/// first dealing whose evaluation dealt to it does not decrypt to the commitment to it.
+fn recover<C>(
+	client: &C,
+	at: <Block as BlockT>::Hash,
+	round: &RoundStatus<BeefyId>,
+	index: u32,
+	pair: &BeefyPair,
+) -> Result<RuntimeCall, String>
+where
+	C: ProvideRuntimeApi<Block>,
+	C::Api: EtfResharingApi<Block, BeefyId>,
+{
+	let dealt_shares = client
+		.runtime_api()
+		.dealt_shares(at, index)
+		.map_err(|e| format!("Failed to read the dealt shares: {}", e))?;
+
+	let mut dealt = Vec::with_capacity(dealt_shares.len());
+	for (dealer, commitment, encrypted) in dealt_shares {
+		match decrypt(pair, &encrypted, &commitment) {
+			Some(evaluation) => dealt.push((dealer as usize, evaluation)),
+			None if round.complained.contains(&index) =>
+				return Err(format!(
+					"The evaluation dealt by #{} does not decrypt to the commitment to it, and \
+					 a dealing was already rejected in this round",
+					dealer,
+				)),
+			None => {
+				let complaint = Complaint { set_id: round.set_id, authority: index, dealer };
+				let signature = pair.sign(&complaint.encode());
+				let call = ResharingCall::<Runtime>::submit_complaint { complaint, signature };
+				return Ok(call.into())
+			},
+		}
+	}
+
+	let authority = &round.incoming[index as usize];
+	let (_, sealed) = seal_share::<TinyBLS377, _>(recover_share(&dealt), authority, &mut OsRng)?;
+	let share =
+		NewShare::<Runtime> { set_id: round.set_id, authority: index, share: bounded(sealed)? };
+	let signature = pair.sign(&share.encode());
+	Ok(ResharingCall::<Runtime>::submit_share { share, signature }.into())
+}
+
+/// Decrypt a share or an evaluation encrypted to `pair`, if it is the one committed to by
+/// `commitment`.
+fn decrypt(pair: &BeefyPair, encrypted: &[u8], commitment: &BeefyId) -> Option<Scalar> {
+	let pair = sp_core::bls::Pair::<TinyBLS377>::from(pair.clone());
+	let recovered = pair.acss_recover(encrypted, 1).ok()?;
+	if BeefyId::from(recovered.public()) != *commitment {
+		return None
+	}
+	let secret = w3f_bls::SecretKey::<TinyBLS377>::from_bytes(&recovered.to_raw_vec()).ok()?;
+	Some(secret.into_vartime().0)
+}
+
+fn bounded<T, S: Get<u32>>(items: Vec<T>) -> Result<BoundedVec<T, S>, String> {
+	items.try_into().map_err(|_| "The submission exceeds the bounds of the runtime".into())
+}
+
+/// Submit `call` as an unsigned transaction to the local pool.
+fn submit<P: LocalTransactionPool<Block = Block>>(
+	pool: &P,
+	at: <Block as BlockT>::Hash,
+	call: RuntimeCall,
+) -> Result<(), String> {
+	let xt = UncheckedExtrinsic::new_unsigned(call);
+	let xt = Decode::decode(&mut &xt.encode()[..]).map_err(|e| format!("{}", e))?;
+	pool.submit_local(at, xt).map(drop).map_err(|e| format!("{:?}", e))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::chain_spec::{get_from_seed, get_pair_from_seed};
+	use rand::SeedableRng;
+	use rand_chacha::ChaCha20Rng;
+
+	fn round(outgoing: &[&str], incoming: &[&str]) -> RoundStatus<BeefyId> {
+		let keys = |seeds: &[&str]| -> Vec<BeefyId> {
+			seeds.iter().map(|seed| get_from_seed::<BeefyId>(seed)).collect()
+		};
+		RoundStatus {
+			set_id: 1,
+			outgoing: keys(outgoing),
+			incoming: keys(incoming),
+			threshold: 2,
+			dealt: vec![],
+			rejected: vec![],
+			qualified: false,
+			submitted: vec![],
+			complained: vec![],
+		}
+	}
+
+	#[test]
+	fn outgoing_authorities_deal_until_the_round_is_qualified() {
+		let mut round = round(&["Alice", "Bob", "Charlie"], &["Bob", "Dave"]);
+		let local = [get_from_seed::<BeefyId>("Alice"), get_from_seed::<BeefyId>("Bob")];
+		let tasks = |round: &RoundStatus<BeefyId>| {
+			super::tasks(round, &local).into_iter().map(|(task, _)| task).collect::<Vec<_>>()
+		};
+
+		assert_eq!(tasks(&round), vec![Task::Deal(0), Task::Deal(1)]);
+		round.dealt = vec![0];
+		round.rejected = vec![1];
+		assert_eq!(tasks(&round), vec![]);
+
+		// Bob is also the first incoming authority.
+		round.qualified = true;
+		assert_eq!(tasks(&round), vec![Task::Recover(0)]);
+		round.submitted = vec![0];
+		assert_eq!(tasks(&round), vec![]);
+	}
+
+	#[test]
+	fn dealt_evaluations_decrypt_to_their_commitment() {
+		let mut rng = ChaCha20Rng::from_seed([7; 32]);
+		let incoming = [get_from_seed::<BeefyId>("Alice"), get_from_seed::<BeefyId>("Bob")];
+		let dealt = reshare::<TinyBLS377, _>(Scalar::from(5u64), &incoming, 2, &mut rng).unwrap();
+		let bob = get_pair_from_seed::<BeefyId>("Bob");
+
+		let (commitment, encrypted) = &dealt[1];
+		let evaluation = decrypt(&bob, encrypted, commitment).expect("dealt to Bob");
+		let (resealed, _) =
+			seal_share::<TinyBLS377, _>(evaluation, &incoming[1], &mut rng).unwrap();
+		assert_eq!(resealed, *commitment);
+
+		// The evaluation dealt to Alice is not Bob's, and the commitment to Alice's evaluation
+		// is not the commitment to Bob's.
+		assert_eq!(decrypt(&bob, &dealt[0].1, &dealt[0].0), None);
+		assert_eq!(decrypt(&bob, encrypted, &dealt[0].0), None);
+	}
+}
@@ -0,0 +1,374 @@
+//! An `inspect` printer that decodes the ETF specific parts of blocks.
+//!
+//! BEEFY digest items, randomness beacon pulses and calls sealed with timelock encryption are
+//! rendered with their fields decoded. Anything else is printed like the default printer does.
+
+use crate::{etf_cmd::authority_set_at, service::new_partial, timelock};
+use beefy_primitives::{bls_crypto::AuthorityId as BeefyId, ConsensusLog, BEEFY_ENGINE_ID};
+use codec::{Decode, Encode};
+use frame_support::traits::GetCallMetadata;
+use node_inspect::{DebugPrinter, PrettyPrinter};
+use node_primitives::{Block, BlockNumber};
+use node_template_runtime::{
+	beacon_api::RandomnessBeaconApi, Runtime, RuntimeCall, UncheckedExtrinsic,
+};
+use sc_cli::Error;
+use sc_client_api::StorageProvider;
+use sc_service::Configuration;
+use serde::Serialize;
+use serde_json::json;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::storage::StorageKey;
+use sp_runtime::{
+	traits::{Block as _, Header as _},
+	DigestItem, OpaqueExtrinsic,
+};
+use std::fmt;
+
+/// The number of agenda blocks `inspect timelocked` lists by default.
+const DEFAULT_AGENDA_RANGE: BlockNumber = 100;
+
+/// Prints blocks and extrinsics with the ETF data decoded, as text or JSON.
+#[derive(Default)]
+pub struct EtfPrinter {
+	json: bool,
+}
+
+impl EtfPrinter {
+	/// Create a printer, rendering JSON if `json` is set.
+	pub fn new(json: bool) -> Self {
+		EtfPrinter { json }
+	}
+}
+
+/// The decoded ETF data of a digest item or an extrinsic.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum EtfItem {
+	/// The MMR root deposited by `pallet_beefy_mmr_etf`.
+	BeefyMmrRoot {
+		/// The hex encoded root.
+		root: String,
+	},
+	/// A new BEEFY-ETF authority set.
+	BeefyAuthoritiesChange {
+		/// The id of the new set.
+		set_id: u64,
+		/// The number of authorities in the set.
+		authorities: usize,
+	},
+	/// A BEEFY-ETF authority was disabled.
+	BeefyAuthorityDisabled {
+		/// The index of the authority in the current set.
+		index: u32,
+	},
+	/// A randomness beacon pulse submission.
+	Pulse {
+		/// The block the pulse was produced for.
+		block_number: BlockNumber,
+		/// The hex encoded signatures of the authorities.
+		signatures: Vec<String>,
+	},
+	/// A call sealed with timelock encryption.
+	SealedCall {
+		/// The pallet the call is submitted to.
+		pallet: String,
+		/// The block whose pulse decrypts the call, if the call specifies it.
+		target: Option<BlockNumber>,
+		/// The size of the ciphertext in bytes.
+		ciphertext_size: usize,
+	},
+}
+
+impl fmt::Display for EtfItem {
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			EtfItem::BeefyMmrRoot { root } => write!(fmt, "BEEFY MMR root {}", root),
+			EtfItem::BeefyAuthoritiesChange { set_id, authorities } =>
+				write!(fmt, "BEEFY authority set {} with {} authorities", set_id, authorities),
+			EtfItem::BeefyAuthorityDisabled { index } =>
+				write!(fmt, "BEEFY authority {} disabled", index),
+			EtfItem::Pulse { block_number, signatures } => {
+				let count = signatures.len();
+				write!(fmt, "Pulse for block {} with {} signatures", block_number, count)?;
+				for (idx, signature) in signatures.iter().enumerate() {
+					write!(fmt, "\n   {}: {}", idx, signature)?;
+				}
+				Ok(())
+			},
+			EtfItem::SealedCall { pallet, target: Some(target), ciphertext_size } => write!(
+				fmt,
+				"Call sealed to {} for block {}, {} bytes of ciphertext",
+				pallet, target, ciphertext_size
+			),
+			EtfItem::SealedCall { pallet, target: None, ciphertext_size } =>
+				write!(fmt, "Call sealed to {}, {} bytes of ciphertext", pallet, ciphertext_size),
+		}
+	}
+}
+
+/// Decode the BEEFY-ETF consensus log of a digest item.
+pub fn decode_digest_item(item: &DigestItem) -> Option<EtfItem> {
+	let log = item.consensus_try_to::<ConsensusLog<BeefyId>>(&BEEFY_ENGINE_ID)?;
+	Some(match log {
+		ConsensusLog::MmrRoot(root) =>
+			EtfItem::BeefyMmrRoot { root: array_bytes::bytes2hex("0x", root.as_ref()) },
+		ConsensusLog::AuthoritiesChange(set) =>
+			EtfItem::BeefyAuthoritiesChange { set_id: set.id(), authorities: set.len() },
+		ConsensusLog::OnDisabled(index) => EtfItem::BeefyAuthorityDisabled { index },
+	})
+}
+
+/// Decode the ETF data of the call of an extrinsic.
+pub fn decode_call(call: &RuntimeCall) -> Option<EtfItem> {
+	let sealed = |pallet: &str, target, ciphertext: &[u8]| EtfItem::SealedCall {
+		pallet: pallet.into(),
+		target,
+		ciphertext_size: ciphertext.len(),
+	};
+
+	match call {
+		RuntimeCall::RandomnessBeacon(pallet_randomness_beacon::Call::write_pulse {
+			signatures,
+			block_number,
+		}) => Some(EtfItem::Pulse {
+			block_number: *block_number,
+			signatures: signatures.iter().map(|s| array_bytes::bytes2hex("0x", s)).collect(),
+		}),
+		RuntimeCall::Scheduler(pallet_scheduler::Call::schedule_sealed {
+			when, ciphertext, ..
+		}) => Some(sealed("Scheduler", Some(*when), ciphertext)),
+		RuntimeCall::Murmur(pallet_murmur::Call::proxy { ciphertext, .. }) =>
+			Some(sealed("Murmur", None, ciphertext)),
+		_ => None,
+	}
+}
+
+fn decode_extrinsic(extrinsic: &OpaqueExtrinsic) -> Option<UncheckedExtrinsic> {
+	UncheckedExtrinsic::decode(&mut &extrinsic.encode()[..]).ok()
+}
+
+fn extrinsic_json(extrinsic: &OpaqueExtrinsic) -> serde_json::Value {
+	let decoded = decode_extrinsic(extrinsic);
+	let call = decoded.as_ref().map(|xt| {
+		let metadata = xt.function.get_call_metadata();
+		format!("{}::{}", metadata.pallet_name, metadata.function_name)
+	});
+	json!({
+		"call": call,
+		"signed": decoded.as_ref().map(|xt| xt.signature.is_some()),
+		"etf": decoded.as_ref().and_then(|xt| decode_call(&xt.function)),
+		"bytes": array_bytes::bytes2hex("0x", extrinsic.encode()),
+	})
+}
+
+impl PrettyPrinter<Block> for EtfPrinter {
+	fn fmt_block(&self, fmt: &mut fmt::Formatter, block: &Block) -> fmt::Result {
+		let digest = block.header().digest().logs();
+		if self.json {
+			let block = json!({
+				"number": block.header().number(),
+				"hash": block.header().hash(),
+				"parentHash": block.header().parent_hash(),
+				"digest": digest.iter().map(|item| json!({
+					"etf": decode_digest_item(item),
+					"bytes": array_bytes::bytes2hex("0x", item.encode()),
+				})).collect::<Vec<_>>(),
+				"extrinsics": block.extrinsics().iter().map(extrinsic_json).collect::<Vec<_>>(),
+			});
+			return writeln!(fmt, "{:#}", block)
+		}
+
+		writeln!(fmt, "Header:")?;
+		writeln!(fmt, "{:?}", block.header())?;
+		let items = digest.iter().filter_map(decode_digest_item).collect::<Vec<_>>();
+		if !items.is_empty() {
+			writeln!(fmt, "ETF digest items ({})", items.len())?;
+			for item in items {
+				writeln!(fmt, "- {}", item)?;
+			}
+		}
+		writeln!(fmt, "Extrinsics ({})", block.extrinsics().len())?;
+		for (idx, ex) in block.extrinsics().iter().enumerate() {
+			writeln!(fmt, "- {}:", idx)?;
+			self.fmt_extrinsic(fmt, ex)?;
+		}
+		Ok(())
+	}
+
+	fn fmt_extrinsic(&self, fmt: &mut fmt::Formatter, extrinsic: &OpaqueExtrinsic) -> fmt::Result {
+		if self.json {
+			return writeln!(fmt, "{:#}", extrinsic_json(extrinsic))
+		}
+
+		match decode_extrinsic(extrinsic) {
+			Some(xt) => {
+				if let Some(item) = decode_call(&xt.function) {
+					writeln!(fmt, " {}", item)?;
+				}
+				writeln!(fmt, " {:#?}", xt)?;
+				writeln!(fmt, " Bytes: {}", array_bytes::bytes2hex("0x", extrinsic.encode()))
+			},
+			None => <DebugPrinter as PrettyPrinter<Block>>::fmt_extrinsic(
+				&DebugPrinter,
+				fmt,
+				extrinsic,
+			),
+		}
+	}
+}
+
+/// A timelock encrypted call in the scheduler agenda.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TimelockedCall {
+	target: BlockNumber,
+	index: usize,
+	ciphertext_size: usize,
+	#[serde(flatten)]
+	status: TimelockStatus,
+}
+
+/// Whether a timelocked call could be opened with the pulse of its target block.
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+enum TimelockStatus {
+	/// The pulse has not been published yet.
+	Sealed,
+	/// The call was decrypted and decoded.
+	Decrypted { call: String },
+	/// The pulse is published, but the call cannot be opened with it.
+	Undecryptable { error: String },
+}
+
+impl fmt::Display for TimelockedCall {
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			fmt,
+			"Block {}, #{}: {} bytes of ciphertext, ",
+			self.target, self.index, self.ciphertext_size
+		)?;
+		match &self.status {
+			TimelockStatus::Sealed => write!(fmt, "sealed until the pulse is published"),
+			TimelockStatus::Decrypted { call } => write!(fmt, "decrypted to\n{}", call),
+			TimelockStatus::Undecryptable { error } =>
+				write!(fmt, "cannot be decrypted: {}", error),
+		}
+	}
+}
+
+/// List the timelock encrypted calls in the scheduler agenda of blocks `from..=to`, as of the
+/// best block, and decrypt those whose pulse is on chain.
+pub fn timelocked(
+	config: Configuration,
+	from: Option<BlockNumber>,
+	to: Option<BlockNumber>,
+	json: bool,
+) -> sc_cli::Result<()> {
+	// The task manager has to outlive the runtime calls.
+	let partial = new_partial(&config, None)?;
+	let client = &partial.client;
+	let best = client.info();
+	let from = from.unwrap_or(best.best_number);
+	let to = to.unwrap_or_else(|| from.saturating_add(DEFAULT_AGENDA_RANGE));
+
+	let api = client.runtime_api();
+	let api_error = |e: sp_api::ApiError| Error::Application(Box::new(e));
+	let round_pubkey = api.round_pubkey(best.best_hash).map_err(api_error)?;
+
+	let mut calls = Vec::new();
+	for target in from..=to {
+		let key = StorageKey(pallet_scheduler::Agenda::<Runtime>::hashed_key_for(target));
+		let Some(agenda) = client.storage(best.best_hash, &key)? else { continue };
+		let agenda =
+			Vec::<Option<pallet_scheduler::ScheduledOf<Runtime>>>::decode(&mut &agenda.0[..])
+				.map_err(|e| {
+					Error::Input(format!("Failed to decode the agenda of block {}: {}", target, e))
+				})?;
+
+		let sealed = agenda
+			.into_iter()
+			.enumerate()
+			.filter_map(|(index, scheduled)| Some((index, scheduled?.maybe_ciphertext?)))
+			.collect::<Vec<_>>();
+		if sealed.is_empty() {
+			continue
+		}
+
+		let pulse = match api.signature_at(best.best_hash, target).map_err(api_error)? {
+			Some(signature) =>
+				Some((signature, authority_set_at(client, target).map_err(Error::Input)?)),
+			None => None,
+		};
+		for (index, ciphertext) in sealed {
+			let status = match &pulse {
+				None => TimelockStatus::Sealed,
+				Some((signature, set_id)) => {
+					let call =
+						timelock::decrypt(&round_pubkey, target, *set_id, signature, &ciphertext)
+							.and_then(|plaintext| {
+								RuntimeCall::decode(&mut &plaintext[..])
+									.map_err(|e| format!("Failed to decode the call: {}", e))
+							});
+					match call {
+						Ok(call) => TimelockStatus::Decrypted { call: format!("{:#?}", call) },
+						Err(error) => TimelockStatus::Undecryptable { error },
+					}
+				},
+			};
+			calls.push(TimelockedCall { target, index, ciphertext_size: ciphertext.len(), status });
+		}
+	}
+
+	if json {
+		let calls = serde_json::to_string_pretty(&calls).map_err(|e| Error::Input(e.to_string()))?;
+		println!("{}", calls);
+	} else if calls.is_empty() {
+		println!("No timelocked calls are scheduled for blocks {} to {}", from, to);
+	} else {
+		for call in calls {
+			println!("{}", call);
+		}
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use beefy_primitives::ValidatorSet;
+	use sp_runtime::traits::Zero;
+
+	#[test]
+	fn pulse_submissions_are_decoded() {
+		let call = RuntimeCall::RandomnessBeacon(pallet_randomness_beacon::Call::write_pulse {
+			signatures: vec![vec![1, 2], vec![3]],
+			block_number: 7,
+		});
+
+		assert_eq!(
+			decode_call(&call),
+			Some(EtfItem::Pulse {
+				block_number: 7,
+				signatures: vec!["0x0102".into(), "0x03".into()],
+			})
+		);
+	}
+
+	#[test]
+	fn beefy_digest_items_are_decoded() {
+		let authorities = vec![crate::chain_spec::get_from_seed::<BeefyId>("Alice")];
+		let set = ValidatorSet::new(authorities, Zero::zero()).unwrap();
+		let item = DigestItem::Consensus(
+			BEEFY_ENGINE_ID,
+			ConsensusLog::<BeefyId>::AuthoritiesChange(set).encode(),
+		);
+
+		assert_eq!(
+			decode_digest_item(&item),
+			Some(EtfItem::BeefyAuthoritiesChange { set_id: 0, authorities: 1 })
+		);
+		assert_eq!(decode_digest_item(&DigestItem::Other(vec![1])), None);
+	}
+}
@@ -30,14 +30,26 @@
 
 #![warn(missing_docs)]
 
+mod beacon_metrics;
+mod beacon_watchdog;
 #[cfg(feature = "cli")]
 mod benchmarking;
 pub mod chain_spec;
+pub mod etf_genesis;
+mod etf_resharing;
 #[cfg(feature = "cli")]
 mod cli;
 #[cfg(feature = "cli")]
 mod command;
+#[cfg(feature = "cli")]
+mod etf_cmd;
+#[cfg(feature = "cli")]
+mod inspect;
+#[cfg(feature = "cli")]
+mod pulses;
 pub mod service;
+#[cfg(feature = "cli")]
+mod timelock;
 
 #[cfg(feature = "cli")]
 pub use cli::*;
@@ -0,0 +1,362 @@
+//! Randomness beacon pulses recorded in exported blocks.
+//!
+//! Pulses are submitted to `pallet_randomness_beacon` as `write_pulse` extrinsics carrying the
+//! signature of each ETF authority, and pulses the beacon skipped are backfilled with the
+//! aggregated signature by `BeaconBackfill::backfill` extrinsics. These helpers read blocks
+//! written by `export-blocks` and recover the pulses from them, so that the beacon can be
+//! checked against [`PulseKeys`] from any source, like the chain spec or the state of a node
+//! database.
+
+use crate::etf_genesis::{commitment_key, lagrange_at_zero};
+use ark_std::Zero;
+use beefy_primitives::{bls_crypto::AuthorityId as BeefyId, ValidatorSetId};
+use codec::{Decode, Encode};
+use node_primitives::{Block, BlockNumber};
+use node_template_runtime::{beacon::pulse_message, RuntimeCall, UncheckedExtrinsic};
+use sp_runtime::generic::SignedBlock;
+use std::{collections::BTreeMap, fs::File, io::BufReader, ops::RangeInclusive, path::Path};
+use w3f_bls::{
+	single::PublicKey, DoublePublicKey, EngineBLS, SerializableToBytes, Signature, TinyBLS377,
+};
+
+/// The signatures submitted for a pulse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubmittedPulse {
+	/// The block number the pulse was produced for.
+	pub block_number: BlockNumber,
+	/// The signatures the pulse is recovered from.
+	pub signatures: PulseSignatures,
+}
+
+/// How the signature of a pulse was submitted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PulseSignatures {
+	/// The signatures of the authorities, indexed like their share commitments, published with
+	/// `write_pulse`.
+	Shares(Vec<Vec<u8>>),
+	/// The aggregated signature of a skipped pulse, backfilled with `backfill`.
+	Aggregated(Vec<u8>),
+}
+
+/// The keys a pulse is checked against.
+///
+/// The authorities sign the pulse of a block under the id of the authority set active at the
+/// block, with the shares that set holds, and resharing changes the shares with the set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PulseKeys {
+	/// The id of the authority set that signs the pulse.
+	pub validator_set_id: ValidatorSetId,
+	/// The serialized round public key.
+	pub round_pubkey: Vec<u8>,
+	/// The commitments to the shares of the authorities, indexed like the authority set.
+	pub commitments: Vec<BeefyId>,
+	/// The number of valid signatures the pulse signature is recovered from.
+	pub threshold: usize,
+}
+
+/// The outcome of checking a submitted pulse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckedPulse {
+	/// The indices of the signatures that do not verify against their share commitment.
+	pub invalid_signatures: Vec<usize>,
+	/// The pulse signature recovered from the valid signatures, if there were enough of them.
+	pub signature: Option<Vec<u8>>,
+}
+
+/// The findings of a beacon audit.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Audit {
+	/// The number of pulses found.
+	pub pulses: usize,
+	/// The blocks between the first and the last pulse that have no pulse.
+	pub gaps: Vec<RangeInclusive<BlockNumber>>,
+	/// The blocks that have more than one pulse.
+	pub duplicates: Vec<BlockNumber>,
+	/// The blocks with a pulse whose signature cannot be recovered from valid signatures.
+	pub invalid: Vec<BlockNumber>,
+	/// The indices of the invalid signatures of each pulse, for pulses that had any.
+	pub invalid_signatures: Vec<(BlockNumber, Vec<usize>)>,
+}
+
+impl Audit {
+	/// Whether the beacon published exactly one valid pulse for each block.
+	pub fn is_ok(&self) -> bool {
+		self.gaps.is_empty() && self.duplicates.is_empty() && self.invalid.is_empty()
+	}
+}
+
+/// Read blocks written by `export-blocks`, in binary or JSON format.
+pub fn read_exported_blocks(path: &Path, binary: bool) -> Result<Vec<SignedBlock<Block>>, String> {
+	let file = File::open(path)
+		.map_err(|e| format!("Failed to open exported blocks {}: {}", path.display(), e))?;
+	let reader = BufReader::new(file);
+	let invalid = |e: &dyn std::fmt::Display| {
+		format!("Failed to decode exported blocks {}: {}", path.display(), e)
+	};
+
+	if binary {
+		// Binary exports start with the number of blocks.
+		let mut input = codec::IoReader(reader);
+		let count = u64::decode(&mut input).map_err(|e| invalid(&e))?;
+		(0..count)
+			.map(|_| SignedBlock::<Block>::decode(&mut input).map_err(|e| invalid(&e)))
+			.collect()
+	} else {
+		serde_json::Deserializer::from_reader(reader)
+			.into_iter::<SignedBlock<Block>>()
+			.map(|block| block.map_err(|e| invalid(&e)))
+			.collect()
+	}
+}
+
+/// Returns the pulses submitted in `block`.
+pub fn submitted_pulses(block: &Block) -> Vec<SubmittedPulse> {
+	block
+		.extrinsics
+		.iter()
+		.filter_map(|xt| {
+			let xt = UncheckedExtrinsic::decode(&mut &xt.encode()[..]).ok()?;
+			match xt.function {
+				RuntimeCall::RandomnessBeacon(pallet_randomness_beacon::Call::write_pulse {
+					signatures,
+					block_number,
+				}) => Some(SubmittedPulse {
+					block_number,
+					signatures: PulseSignatures::Shares(signatures),
+				}),
+				RuntimeCall::BeaconBackfill(pallet_beacon_backfill::Call::backfill {
+					block,
+					signature,
+				}) => Some(SubmittedPulse {
+					block_number: block,
+					signatures: PulseSignatures::Aggregated(signature.into_inner()),
+				}),
+				_ => None,
+			}
+		})
+		.collect()
+}
+
+/// Check every pulse against the keys `keys_at` returns for its block, and look for missing
+/// and duplicate pulses.
+///
+/// The audit fails if the keys of a block are not known.
+pub fn audit(
+	mut keys_at: impl FnMut(BlockNumber) -> Result<PulseKeys, String>,
+	pulses: impl IntoIterator<Item = SubmittedPulse>,
+) -> Result<Audit, String> {
+	let mut audit = Audit::default();
+	let mut counts = BTreeMap::<BlockNumber, usize>::new();
+	for pulse in pulses {
+		let checked = check_pulse(&keys_at(pulse.block_number)?, &pulse)?;
+		if checked.signature.is_none() {
+			audit.invalid.push(pulse.block_number);
+		}
+		if !checked.invalid_signatures.is_empty() {
+			audit.invalid_signatures.push((pulse.block_number, checked.invalid_signatures));
+		}
+		*counts.entry(pulse.block_number).or_default() += 1;
+		audit.pulses += 1;
+	}
+
+	audit.duplicates = counts.iter().filter(|(_, count)| **count > 1).map(|(n, _)| *n).collect();
+	audit.gaps = counts
+		.keys()
+		.zip(counts.keys().skip(1))
+		.filter(|(prev, next)| *next - *prev > 1)
+		.map(|(prev, next)| prev + 1..=next - 1)
+		.collect();
+	Ok(audit)
+}
+
+/// Check every signature of `pulse` against the share commitments of `keys`, and recover the
+/// pulse signature if at least `keys.threshold` of them are valid.
+///
+/// The aggregated signature of a backfilled pulse is checked against the round public key.
+pub fn check_pulse(keys: &PulseKeys, pulse: &SubmittedPulse) -> Result<CheckedPulse, String> {
+	check_pulse_on::<TinyBLS377>(keys, pulse)
+}
+
+fn check_pulse_on<E: EngineBLS>(
+	keys: &PulseKeys,
+	pulse: &SubmittedPulse,
+) -> Result<CheckedPulse, String> {
+	use ark_serialize::CanonicalDeserialize;
+
+	let message = pulse_message(pulse.block_number, keys.validator_set_id);
+	let verify = |signature: &Signature<E>| {
+		DoublePublicKey::<E>::deserialize_compressed(&keys.round_pubkey[..])
+			.map_or(false, |public| signature.verify(&message, &PublicKey(public.1)))
+	};
+	let signatures = match &pulse.signatures {
+		PulseSignatures::Shares(signatures) => signatures,
+		PulseSignatures::Aggregated(signature) => {
+			let signature = Signature::<E>::from_bytes(signature)
+				.ok()
+				.filter(|signature| verify(signature))
+				.map(|signature| signature.to_bytes());
+			return Ok(CheckedPulse { invalid_signatures: Vec::new(), signature })
+		},
+	};
+
+	let mut valid = Vec::new();
+	let mut invalid_signatures = Vec::new();
+	for (idx, bytes) in signatures.iter().enumerate() {
+		let share = keys.commitments.get(idx).map(commitment_key::<E>).transpose()?;
+		let signature = Signature::<E>::from_bytes(bytes).ok();
+		match share.zip(signature) {
+			Some((share, signature)) if signature.verify(&message, &PublicKey(share.1)) =>
+				valid.push((idx, signature)),
+			_ => invalid_signatures.push(idx),
+		}
+	}
+
+	let signature = (valid.len() >= keys.threshold)
+		.then(|| {
+			let valid = &valid[..keys.threshold];
+			let indices = valid.iter().map(|(idx, _)| *idx).collect::<Vec<_>>();
+			let point = valid.iter().fold(E::SignatureGroup::zero(), |acc, (idx, signature)| {
+				acc + signature.0 * lagrange_at_zero::<E>(*idx, &indices)
+			});
+			Signature::<E>(point)
+		})
+		.filter(|signature| verify(signature))
+		.map(|signature| signature.to_bytes());
+
+	Ok(CheckedPulse { invalid_signatures, signature })
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		chain_spec::get_from_seed,
+		etf_genesis::{etf_genesis, EtfGenesis},
+	};
+	use node_primitives::Header;
+	use sp_runtime::{traits::Header as _, OpaqueExtrinsic};
+
+	impl PulseKeys {
+		/// The keys of the genesis authority set.
+		fn genesis(genesis: &EtfGenesis) -> Self {
+			Self {
+				validator_set_id: 0,
+				round_pubkey: genesis.round_pubkey.clone(),
+				commitments: genesis
+					.resharings
+					.iter()
+					.map(|(commitment, _)| commitment.clone())
+					.collect(),
+				threshold: genesis.threshold,
+			}
+		}
+	}
+
+	fn pulse(block_number: BlockNumber, signatures: Vec<Vec<u8>>) -> SubmittedPulse {
+		SubmittedPulse { block_number, signatures: PulseSignatures::Shares(signatures) }
+	}
+
+	#[test]
+	fn audit_reports_gaps_duplicates_and_invalid_pulses() {
+		let authorities = ["Alice", "Bob"].map(get_from_seed::<BeefyId>);
+		let genesis = etf_genesis(&authorities, 2, None).unwrap();
+		let pulses = vec![
+			pulse(1, vec![]),
+			pulse(2, vec![vec![0; 48]]),
+			pulse(2, vec![]),
+			pulse(5, vec![]),
+		];
+
+		let audit = audit(|_| Ok(PulseKeys::genesis(&genesis)), pulses).unwrap();
+
+		assert_eq!(audit.pulses, 4);
+		assert_eq!(audit.gaps, vec![3..=4]);
+		assert_eq!(audit.duplicates, vec![2]);
+		assert_eq!(audit.invalid, vec![1, 2, 2, 5]);
+		assert_eq!(audit.invalid_signatures, vec![(2, vec![0])]);
+		assert!(!audit.is_ok());
+	}
+
+	#[test]
+	fn backfilled_pulses_are_submitted_pulses() {
+		let header = Header::new(
+			1,
+			Default::default(),
+			Default::default(),
+			Default::default(),
+			Default::default(),
+		);
+		let extrinsic = |call: RuntimeCall| {
+			OpaqueExtrinsic::decode(&mut &UncheckedExtrinsic::new_unsigned(call).encode()[..])
+				.unwrap()
+		};
+		let block = Block::new(
+			header,
+			vec![
+				extrinsic(RuntimeCall::RandomnessBeacon(
+					pallet_randomness_beacon::Call::write_pulse {
+						signatures: vec![vec![1]],
+						block_number: 2,
+					},
+				)),
+				extrinsic(RuntimeCall::BeaconBackfill(pallet_beacon_backfill::Call::backfill {
+					block: 1,
+					signature: vec![2].try_into().unwrap(),
+				})),
+			],
+		);
+
+		assert_eq!(
+			submitted_pulses(&block),
+			vec![
+				pulse(2, vec![vec![1]]),
+				SubmittedPulse {
+					block_number: 1,
+					signatures: PulseSignatures::Aggregated(vec![2]),
+				},
+			]
+		);
+	}
+
+	#[test]
+	fn backfilled_pulses_are_checked_against_the_round_public_key() {
+		let authorities = ["Alice", "Bob"].map(get_from_seed::<BeefyId>);
+		let genesis = etf_genesis(&authorities, 2, None).unwrap();
+		let backfilled = SubmittedPulse {
+			block_number: 2,
+			signatures: PulseSignatures::Aggregated(vec![0; 48]),
+		};
+
+		let pulses = vec![pulse(1, vec![]), backfilled, pulse(3, vec![])];
+
+		let audit = audit(|_| Ok(PulseKeys::genesis(&genesis)), pulses).unwrap();
+
+		assert!(audit.gaps.is_empty());
+		assert_eq!(audit.invalid, vec![1, 2, 3]);
+		assert!(audit.invalid_signatures.is_empty());
+	}
+
+	#[test]
+	fn empty_audit_is_ok() {
+		let authorities = [get_from_seed::<BeefyId>("Alice")];
+		let genesis = etf_genesis(&authorities, 1, None).unwrap();
+
+		assert!(audit(|_| Ok(PulseKeys::genesis(&genesis)), vec![]).unwrap().is_ok());
+	}
+
+	#[test]
+	fn pulses_are_checked_against_the_keys_of_their_block() {
+		let authorities = ["Alice", "Bob"].map(get_from_seed::<BeefyId>);
+		let genesis = etf_genesis(&authorities, 2, None).unwrap();
+		let keys_at = |block: BlockNumber| match block {
+			1 => Ok(PulseKeys::genesis(&genesis)),
+			_ => Err(format!("The keys of block {} are not known", block)),
+		};
+
+		assert!(audit(keys_at, vec![pulse(1, vec![])]).is_ok());
+		assert_eq!(
+			audit(keys_at, vec![pulse(1, vec![]), pulse(2, vec![])]),
+			Err("The keys of block 2 are not known".into())
+		);
+	}
+}
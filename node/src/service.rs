@@ -25,7 +25,7 @@ use polkadot_sdk::{
 	*,
 };
 
-use crate::Cli;
+use crate::{beacon_metrics, beacon_watchdog, etf_resharing, Cli};
 use codec::Encode;
 use frame_benchmarking_cli::SUBSTRATE_REFERENCE_HARDWARE;
 use frame_system_rpc_runtime_api::AccountNonceApi;
@@ -347,8 +347,9 @@ pub fn new_partial(
 						beefy_best_block_stream: beefy_rpc_links
 							.from_voter_best_beefy_stream
 							.clone(),
-						subscription_executor,
+						subscription_executor: subscription_executor.clone(),
 					},
+					beacon: node_rpc::BeaconDeps { subscription_executor },
 					statement_store: rpc_statement_store.clone(),
 					backend: rpc_backend.clone(),
 					mixnet_api: mixnet_api.as_ref().cloned(),
@@ -672,6 +673,44 @@ pub fn new_full_base<N: NetworkBackend<Block, <Block as BlockT>::Hash>>(
 	// need a keystore, regardless of which protocol we use below.
 	let keystore = if role.is_authority() { Some(keystore_container.keystore()) } else { None };
 
+	task_manager.spawn_handle().spawn(
+		"etf-beacon-watchdog",
+		None,
+		beacon_watchdog::run(client.clone(), keystore_container.keystore()),
+	);
+	// Authorities hand the shares of the round secret over to every new authority set.
+	if role.is_authority() {
+		task_manager.spawn_handle().spawn(
+			"etf-resharing",
+			None,
+			etf_resharing::run(
+				client.clone(),
+				transaction_pool.clone(),
+				keystore_container.local_keystore(),
+			),
+		);
+	}
+	let beacon_pulse_metrics = prometheus_registry.as_ref().and_then(|registry| {
+		beacon_metrics::Metrics::register(registry)
+			.map_err(|e| log::warn!("Failed to register the beacon metrics: {}", e))
+			.ok()
+	});
+	// The pulses of the local authority are submitted by the BEEFY-ETF gadget.
+	let beefy_tx_pool_factory = match beacon_pulse_metrics {
+		Some(metrics) => {
+			task_manager.spawn_handle().spawn(
+				"etf-beacon-metrics",
+				None,
+				beacon_metrics::run_height_metrics(client.clone(), metrics.clone()),
+			);
+			OffchainTransactionPoolFactory::new(beacon_metrics::MeteredPool::new(
+				transaction_pool.clone(),
+				metrics,
+			))
+		},
+		None => OffchainTransactionPoolFactory::new(transaction_pool.clone()),
+	};
+
 	// beefy is enabled if its notification service exists
 	let network_params = beefy::BeefyNetworkParams {
 		network: Arc::new(network.clone()),
@@ -692,7 +731,7 @@ pub fn new_full_base<N: NetworkBackend<Block, <Block as BlockT>::Hash>>(
 		prometheus_registry: prometheus_registry.clone(),
 		links: beefy_links,
 		on_demand_justifications_handler: beefy_on_demand_justifications_handler,
-		offchain_tx_pool_factory: OffchainTransactionPoolFactory::new(transaction_pool.clone()),
+		offchain_tx_pool_factory: beefy_tx_pool_factory,
 	};
 
 	let beefy_gadget = beefy::start_beefy_gadget::<_, _, _, _, _, _, _>(beefy_params);
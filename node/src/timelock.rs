@@ -0,0 +1,113 @@
+//! Timelock encryption against the randomness beacon.
+//!
+//! A message encrypted for block `n` can only be decrypted with the pulse the ETF authorities
+//! publish for `n`. As the authorities sign the BEEFY commitment of `n`, the message is also
+//! bound to the id of the authority set expected to sign it. Ciphertexts are the compressed
+//! serialization of a [`TLECiphertext`], which is what `pallet_scheduler` and `pallet_murmur`
+//! expect.
+
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use beefy_primitives::ValidatorSetId;
+use etf_crypto_primitives::encryption::tlock::{tle, TLECiphertext};
+use node_primitives::BlockNumber;
+use node_template_runtime::beacon::{pulse_identity, pulse_message, BeaconEngine};
+use rand::{rngs::OsRng, RngCore};
+use w3f_bls::{single::PublicKey, DoublePublicKey, SerializableToBytes, Signature};
+
+fn decode_round_pubkey(round_pubkey: &[u8]) -> Result<DoublePublicKey<BeaconEngine>, String> {
+	DoublePublicKey::<BeaconEngine>::deserialize_compressed(round_pubkey)
+		.map_err(|e| format!("Invalid round public key: {}", e))
+}
+
+/// Encrypt `message` so that it can be decrypted with the pulse the authority set
+/// `validator_set_id` signs for `block`.
+pub fn encrypt(
+	round_pubkey: &[u8],
+	block: BlockNumber,
+	validator_set_id: ValidatorSetId,
+	message: &[u8],
+) -> Result<Vec<u8>, String> {
+	let public = decode_round_pubkey(round_pubkey)?;
+
+	let mut key = [0u8; 32];
+	OsRng.fill_bytes(&mut key);
+	let identity = pulse_identity(block, validator_set_id);
+	let ciphertext = tle::<BeaconEngine, _>(public.1, key, message, identity, OsRng)
+		.map_err(|e| format!("Failed to encrypt: {:?}", e))?;
+
+	let mut bytes = Vec::new();
+	ciphertext
+		.serialize_compressed(&mut bytes)
+		.map_err(|e| format!("Failed to serialize the ciphertext: {}", e))?;
+	Ok(bytes)
+}
+
+/// Decrypt `ciphertext` with the pulse `signature` the authority set `validator_set_id`
+/// signed for `block`.
+///
+/// The signature is checked against the round public key first, so that a wrong pulse is
+/// reported as such rather than as a decryption failure.
+pub fn decrypt(
+	round_pubkey: &[u8],
+	block: BlockNumber,
+	validator_set_id: ValidatorSetId,
+	signature: &[u8],
+	ciphertext: &[u8],
+) -> Result<Vec<u8>, String> {
+	let public = decode_round_pubkey(round_pubkey)?;
+	let signature = Signature::<BeaconEngine>::from_bytes(signature)
+		.map_err(|_| "Invalid pulse signature".to_string())?;
+	if !signature.verify(&pulse_message(block, validator_set_id), &PublicKey(public.1)) {
+		return Err(format!(
+			"The signature is not the pulse of authority set #{} for block {}",
+			validator_set_id, block,
+		))
+	}
+
+	let ciphertext = TLECiphertext::<BeaconEngine>::deserialize_compressed(ciphertext)
+		.map_err(|e| format!("Invalid ciphertext: {}", e))?;
+	ciphertext.tld(signature.0).map_err(|e| format!("Failed to decrypt: {:?}", e))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use w3f_bls::TinyBLS377;
+
+	fn round_key() -> (w3f_bls::KeypairVT<TinyBLS377>, Vec<u8>) {
+		let keypair = w3f_bls::KeypairVT::<TinyBLS377>::generate(&mut OsRng);
+		let public =
+			DoublePublicKey(keypair.into_public_key_in_signature_group().0, keypair.public.0);
+		let mut bytes = Vec::new();
+		public.serialize_compressed(&mut bytes).unwrap();
+		(keypair, bytes)
+	}
+
+	#[test]
+	fn pulse_decrypts_what_was_encrypted_for_its_block() {
+		let (keypair, round_pubkey) = round_key();
+		let ciphertext = encrypt(&round_pubkey, 42, 3, b"sealed call").unwrap();
+		let pulse = keypair.sign(&pulse_message(42, 3)).to_bytes();
+
+		assert_eq!(decrypt(&round_pubkey, 42, 3, &pulse, &ciphertext).unwrap(), b"sealed call");
+	}
+
+	#[test]
+	fn pulse_for_another_block_is_rejected() {
+		let (keypair, round_pubkey) = round_key();
+		let ciphertext = encrypt(&round_pubkey, 42, 3, b"sealed call").unwrap();
+		let pulse = keypair.sign(&pulse_message(41, 3)).to_bytes();
+
+		assert!(decrypt(&round_pubkey, 42, 3, &pulse, &ciphertext).is_err());
+	}
+
+	#[test]
+	fn pulse_of_another_authority_set_is_rejected() {
+		let (keypair, round_pubkey) = round_key();
+		let ciphertext = encrypt(&round_pubkey, 42, 3, b"sealed call").unwrap();
+		let pulse = keypair.sign(&pulse_message(42, 4)).to_bytes();
+
+		assert!(decrypt(&round_pubkey, 42, 4, &pulse, &ciphertext).is_err());
+		assert!(decrypt(&round_pubkey, 42, 3, &pulse, &ciphertext).is_err());
+	}
+}
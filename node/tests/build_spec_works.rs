@@ -37,3 +37,53 @@ fn build_spec_works() {
 
 	let _value: serde_json::Value = serde_json::from_slice(output.stdout.as_slice()).unwrap();
 }
+
+#[test]
+fn build_spec_with_etf_seed_is_reproducible() {
+	let build_spec = || {
+		let output = Command::new(cargo_bin("substrate-node"))
+			.args(&["build-spec", "--dev", "--etf-seed", "integration"])
+			.output()
+			.unwrap();
+		assert!(output.status.success());
+		output.stdout
+	};
+
+	assert_eq!(build_spec(), build_spec());
+}
+
+#[test]
+fn build_spec_refuses_etf_seed_for_staging() {
+	let output = Command::new(cargo_bin("substrate-node"))
+		.args(&["build-spec", "--chain", "staging", "--etf-seed", "integration"])
+		.output()
+		.unwrap();
+	assert!(!output.status.success());
+}
+
+#[test]
+fn build_spec_rejects_malformed_etf_genesis() {
+	let base_path = tempdir().expect("could not create a temp dir");
+	let spec_path = base_path.path().join("spec.json");
+
+	let output = Command::new(cargo_bin("substrate-node"))
+		.args(&["build-spec", "--chain", "local"])
+		.output()
+		.unwrap();
+	assert!(output.status.success());
+
+	let mut spec: serde_json::Value = serde_json::from_slice(output.stdout.as_slice()).unwrap();
+	spec["genesis"]["runtimeGenesis"]["patch"]["etf"]["genesisResharing"]
+		.as_array_mut()
+		.unwrap()
+		.pop();
+	std::fs::write(&spec_path, serde_json::to_vec(&spec).unwrap()).unwrap();
+
+	let output = Command::new(cargo_bin("substrate-node"))
+		.args(&["build-spec", "--chain"])
+		.arg(&spec_path)
+		.output()
+		.unwrap();
+	assert!(!output.status.success());
+	assert!(String::from_utf8_lossy(&output.stderr).contains("resharings"));
+}
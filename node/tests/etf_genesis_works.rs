@@ -0,0 +1,63 @@
+use assert_cmd::cargo::cargo_bin;
+use node::chain_spec::authority_keys_from_seed;
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn etf_genesis_works() {
+	let dir = tempdir().expect("could not create a temp dir");
+	let authorities = dir.path().join("authorities.json");
+	let patch = dir.path().join("etf.json");
+	let spec = dir.path().join("spec.json");
+
+	let keys = ["Alice", "Bob", "Charlie"]
+		.iter()
+		.map(|seed| authority_keys_from_seed(seed).7)
+		.collect::<Vec<_>>();
+	std::fs::write(&authorities, serde_json::to_vec(&keys).unwrap()).unwrap();
+
+	let output = Command::new(cargo_bin("substrate-node"))
+		.args(&["build-spec", "--chain", "local"])
+		.output()
+		.unwrap();
+	assert!(output.status.success());
+	std::fs::write(&spec, output.stdout).unwrap();
+
+	let status = Command::new(cargo_bin("substrate-node"))
+		.args(&["etf-genesis", "--threshold", "2", "--authorities"])
+		.arg(&authorities)
+		.arg("--output")
+		.arg(&patch)
+		.arg("--merge-into")
+		.arg(&spec)
+		.status()
+		.unwrap();
+	assert!(status.success());
+
+	let patch: serde_json::Value =
+		serde_json::from_slice(&std::fs::read(&patch).unwrap()).unwrap();
+	assert_eq!(patch["etf"]["genesisResharing"].as_array().unwrap().len(), 3);
+	assert_eq!(patch["etfResharing"]["threshold"], 2);
+
+	let spec: serde_json::Value = serde_json::from_slice(&std::fs::read(&spec).unwrap()).unwrap();
+	let genesis = &spec["genesis"]["runtimeGenesis"]["patch"];
+	assert_eq!(genesis["etf"], patch["etf"]);
+	assert_eq!(genesis["etfResharing"], patch["etfResharing"]);
+}
+
+#[test]
+fn etf_genesis_rejects_threshold_above_authority_count() {
+	let dir = tempdir().expect("could not create a temp dir");
+	let authorities = dir.path().join("authorities.json");
+	let keys = vec![authority_keys_from_seed("Alice").7];
+	std::fs::write(&authorities, serde_json::to_vec(&keys).unwrap()).unwrap();
+
+	let status = Command::new(cargo_bin("substrate-node"))
+		.args(&["etf-genesis", "--threshold", "2", "--authorities"])
+		.arg(&authorities)
+		.arg("--output")
+		.arg(dir.path().join("etf.json"))
+		.status()
+		.unwrap();
+	assert!(!status.success());
+}
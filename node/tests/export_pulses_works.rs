@@ -0,0 +1,22 @@
+use assert_cmd::cargo::cargo_bin;
+use std::process::Command;
+use tempfile::tempdir;
+
+#[test]
+fn export_pulses_works() {
+	let base_path = tempdir().expect("could not create a temp dir");
+	let output = base_path.path().join("pulses.csv");
+
+	let status = Command::new(cargo_bin("substrate-node"))
+		.args(&["export-pulses", "--dev", "--format", "csv"])
+		.arg("-d")
+		.arg(base_path.path())
+		.arg(&output)
+		.status()
+		.unwrap();
+	assert!(status.success());
+
+	// No pulses are published at genesis.
+	let csv = std::fs::read_to_string(&output).unwrap();
+	assert_eq!(csv, "blockNumber,signature,randomness,authoritySetId\n");
+}
@@ -38,3 +38,17 @@ async fn inspect_works() {
 		.unwrap();
 	assert!(status.success());
 }
+
+#[test]
+fn inspect_timelocked_works() {
+	let base_path = tempdir().expect("could not create a temp dir");
+
+	let output = Command::new(cargo_bin("substrate-node"))
+		.args(&["inspect", "--dev", "-d"])
+		.arg(base_path.path())
+		.args(&["timelocked", "--from", "0", "--to", "10"])
+		.output()
+		.unwrap();
+	assert!(output.status.success());
+	assert!(String::from_utf8_lossy(&output.stdout).contains("No timelocked calls"));
+}
@@ -0,0 +1,55 @@
+use assert_cmd::cargo::cargo_bin;
+use std::process::Command;
+use tempfile::tempdir;
+
+fn encrypt(base_path: &std::path::Path, block: &str) -> String {
+	let output = Command::new(cargo_bin("substrate-node"))
+		.args(&["timelock", "encrypt", "--chain", "dev", "--block", block, "--message", "hello"])
+		.args(&["--validator-set-id", "0"])
+		.arg("-d")
+		.arg(base_path)
+		.output()
+		.unwrap();
+	assert!(output.status.success());
+	String::from_utf8(output.stdout).unwrap().trim().to_string()
+}
+
+#[test]
+fn timelock_encrypt_works() {
+	let base_path = tempdir().expect("could not create a temp dir");
+	let ciphertext = encrypt(base_path.path(), "10");
+
+	assert!(ciphertext.starts_with("0x"));
+	assert!(array_bytes::hex2bytes(&ciphertext).is_ok());
+}
+
+#[test]
+fn timelock_encrypt_requires_the_authority_set_of_future_blocks() {
+	let base_path = tempdir().expect("could not create a temp dir");
+
+	for from_state in [&[][..], &["--from-state"][..]] {
+		let status = Command::new(cargo_bin("substrate-node"))
+			.args(&["timelock", "encrypt", "--chain", "dev", "--block", "10", "--message", "hello"])
+			.args(from_state)
+			.arg("-d")
+			.arg(base_path.path())
+			.status()
+			.unwrap();
+		assert!(!status.success());
+	}
+}
+
+#[test]
+fn timelock_decrypt_rejects_invalid_signature() {
+	let base_path = tempdir().expect("could not create a temp dir");
+	let ciphertext = encrypt(base_path.path(), "10");
+
+	let status = Command::new(cargo_bin("substrate-node"))
+		.args(&["timelock", "decrypt", "--chain", "dev", "--block", "10"])
+		.args(&["--ciphertext", &ciphertext, "--signature", "0x00", "--validator-set-id", "0"])
+		.arg("-d")
+		.arg(base_path.path())
+		.status()
+		.unwrap();
+	assert!(!status.success());
+}
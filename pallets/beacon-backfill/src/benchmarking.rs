@@ -0,0 +1,49 @@
+//! Benchmarks for the beacon backfill pallet.
+
+use super::*;
+use crate::Pallet as BeaconBackfill;
+
+use frame_benchmarking::v2::*;
+use frame_support::traits::Hooks;
+use frame_system::{pallet_prelude::BlockNumberFor, RawOrigin};
+use sp_runtime::traits::Zero;
+
+#[benchmarks]
+mod benchmarks {
+	use super::*;
+
+	#[benchmark]
+	fn backfill() -> Result<(), BenchmarkError> {
+		// The beacon skipped block 1 and went on with block 2.
+		let block: BlockNumberFor<T> = 1u32.into();
+		T::BenchmarkHelper::publish(2u32.into());
+		let set_id = BeaconBackfill::<T>::authority_set_at(block)
+			.ok_or(BenchmarkError::Stop("No authority set is active at the skipped block"))?;
+		let signature = T::BenchmarkHelper::sign(block, set_id)
+			.try_into()
+			.map_err(|_| BenchmarkError::Stop("The signature exceeds `MaxSignatureLen`"))?;
+
+		#[extrinsic_call]
+		_(RawOrigin::None, block, signature);
+
+		assert_eq!(BeaconBackfill::<T>::status(block), PulseStatus::Backfilled);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn on_initialize(n: Linear<0, { T::MaxClosedPerBlock::get() }>) {
+		// The windows of the blocks `1..=n` closed without a pulse.
+		ClosedUntil::<T>::put(BlockNumberFor::<T>::zero());
+		T::BenchmarkHelper::publish(T::BackfillWindow::get().saturating_add(n.into()));
+		let now = frame_system::Pallet::<T>::block_number();
+
+		#[block]
+		{
+			BeaconBackfill::<T>::on_initialize(now);
+		}
+
+		assert_eq!(ClosedUntil::<T>::get(), Some(n.into()));
+	}
+
+	impl_benchmark_test_suite!(BeaconBackfill, crate::mock::new_test_ext(), crate::mock::Test);
+}
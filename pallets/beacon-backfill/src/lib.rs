@@ -0,0 +1,342 @@
+//! # Beacon Backfill Pallet
+//!
+//! Fills the gaps left in the randomness beacon by skipped BEEFY-ETF rounds.
+//!
+//! When the ETF authorities skip a round, the beacon moves on and the skipped block never gets
+//! a pulse. Until [`Config::BackfillWindow`] blocks after the beacon has passed it, anyone can
+//! submit the aggregated signature of a skipped block with the unsigned [`Call::backfill`].
+//! The signature authenticates itself: it is only accepted if it verifies under the round public
+//! key, which takes a threshold of the authorities to produce.
+//!
+//! Once the window has closed without a pulse, the pulse of the block is permanently missing.
+//! [`Config::OnPulseMissing`] is told exactly once per such block, so that whatever waits for
+//! the pulse, like timelocked calls, can be abandoned instead of waiting forever. The status of
+//! the pulse of any block is given by [`Pallet::status`].
+//!
+//! The authorities sign the pulse of a block under the id of the BEEFY-ETF authority set active
+//! at that block, so a skipped block of an earlier session is only backfilled with a signature
+//! of the set that was active then. The runtime notes every new set with
+//! [`Pallet::note_authority_set`], and [`Pallet::authority_set_at`] resolves the set of any
+//! block from these notes. When the pallet is added to a running chain,
+//! [`migrations::NoteCurrentAuthoritySet`] notes the set active at the upgrade.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+pub mod migrations;
+pub mod weights;
+pub use weights::WeightInfo;
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::weights::Weight;
+use scale_info::TypeInfo;
+use sp_runtime::{traits::Saturating, RuntimeDebug, SaturatedConversion};
+use sp_std::prelude::*;
+
+/// The identifier of a BEEFY authority set.
+pub type SetId = u64;
+
+/// The pulses published by the beacon.
+pub trait PulseSource<BlockNumber> {
+	/// The latest block with a published pulse.
+	fn latest() -> BlockNumber;
+
+	/// Whether the beacon published a pulse for `block`.
+	fn is_published(block: BlockNumber) -> bool;
+
+	/// Whether `signature` is a valid aggregated signature for `block` of the authority set
+	/// `set_id` under the round public key.
+	fn verify(block: BlockNumber, set_id: SetId, signature: &[u8]) -> bool;
+}
+
+/// Handles pulses that are permanently missing.
+pub trait OnPulseMissing<BlockNumber> {
+	/// The pulse of `block` will never be published nor backfilled.
+	///
+	/// Returns the weight consumed.
+	fn on_pulse_missing(block: BlockNumber) -> Weight;
+}
+
+impl<BlockNumber> OnPulseMissing<BlockNumber> for () {
+	fn on_pulse_missing(_: BlockNumber) -> Weight {
+		Weight::zero()
+	}
+}
+
+/// Sets up the beacon for the benchmarks of this pallet.
+#[cfg(feature = "runtime-benchmarks")]
+pub trait BenchmarkHelper<BlockNumber> {
+	/// Publish the pulse of `block`, and of no other block.
+	fn publish(block: BlockNumber);
+
+	/// A valid aggregated signature of the pulse of `block` by the authority set `set_id`.
+	fn sign(block: BlockNumber, set_id: SetId) -> Vec<u8>;
+}
+
+/// The status of the pulse of a block.
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode, MaxEncodedLen, TypeInfo, RuntimeDebug)]
+pub enum PulseStatus<BlockNumber> {
+	/// The beacon published the pulse.
+	Published,
+	/// The pulse was skipped by the beacon and backfilled.
+	Backfilled,
+	/// There is no pulse yet, but it can still be published or backfilled until the beacon
+	/// reaches `deadline`.
+	Awaiting { deadline: BlockNumber },
+	/// The pulse is permanently missing.
+	Missing,
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The beacon whose gaps are filled.
+		type Beacon: PulseSource<BlockNumberFor<Self>>;
+
+		/// What to do with pulses that are permanently missing.
+		type OnPulseMissing: OnPulseMissing<BlockNumberFor<Self>>;
+
+		/// How many blocks after the beacon has passed a block its pulse can be backfilled.
+		#[pallet::constant]
+		type BackfillWindow: Get<BlockNumberFor<Self>>;
+
+		/// The maximum number of blocks whose window closes that are handled per block.
+		///
+		/// Windows that close while more are pending are handled in later blocks.
+		#[pallet::constant]
+		type MaxClosedPerBlock: Get<u32>;
+
+		/// The maximum length of an aggregated signature.
+		#[pallet::constant]
+		type MaxSignatureLen: Get<u32>;
+
+		/// The priority of backfill transactions.
+		#[pallet::constant]
+		type UnsignedPriority: Get<TransactionPriority>;
+
+		/// Weight information for extrinsics in this pallet.
+		type WeightInfo: WeightInfo;
+
+		/// Sets up the beacon for the benchmarks.
+		#[cfg(feature = "runtime-benchmarks")]
+		type BenchmarkHelper: BenchmarkHelper<BlockNumberFor<Self>>;
+	}
+
+	/// The aggregated signatures of backfilled blocks.
+	#[pallet::storage]
+	pub type Backfilled<T: Config> =
+		StorageMap<_, Twox64Concat, BlockNumberFor<T>, BoundedVec<u8, T::MaxSignatureLen>>;
+
+	/// The last block whose backfill window has been handled.
+	///
+	/// Empty until the first block after the pallet is added, which starts handling at the
+	/// window closing then.
+	#[pallet::storage]
+	pub type ClosedUntil<T: Config> = StorageValue<_, BlockNumberFor<T>>;
+
+	/// The authority sets noted, in the order they took over, each with the block it took over
+	/// at.
+	#[pallet::storage]
+	pub type AuthoritySetStarts<T: Config> =
+		StorageMap<_, Twox64Concat, u32, (SetId, BlockNumberFor<T>)>;
+
+	/// The number of authority sets noted.
+	#[pallet::storage]
+	pub type AuthoritySetCount<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+	/// The id of the latest authority set noted.
+	#[pallet::storage]
+	pub type LatestAuthoritySet<T: Config> = StorageValue<_, SetId, ValueQuery>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The pulse of a skipped block was backfilled.
+		Backfilled { block: BlockNumberFor<T> },
+		/// The backfill window of a block closed without a pulse.
+		PulseMissing { block: BlockNumberFor<T> },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The block already has a pulse.
+		AlreadyPublished,
+		/// The beacon has not passed the block yet, its pulse is published as usual.
+		NotSkipped,
+		/// The backfill window of the block has closed.
+		WindowClosed,
+		/// The signature is not valid for the block under the round public key, or not signed by
+		/// the authority set that was active at the block.
+		BadSignature,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(_n: BlockNumberFor<T>) -> Weight {
+			let closing = T::Beacon::latest().saturating_sub(T::BackfillWindow::get());
+			let Some(mut closed) = ClosedUntil::<T>::get() else {
+				ClosedUntil::<T>::put(closing);
+				return T::DbWeight::get().reads_writes(2, 1)
+			};
+
+			let mut weight = Weight::zero();
+			let mut handled = 0;
+			while closed < closing && handled < T::MaxClosedPerBlock::get() {
+				closed.saturating_inc();
+				handled += 1;
+				if !T::Beacon::is_published(closed) && !Backfilled::<T>::contains_key(closed) {
+					weight.saturating_accrue(T::OnPulseMissing::on_pulse_missing(closed));
+					Self::deposit_event(Event::PulseMissing { block: closed });
+				}
+			}
+			if handled > 0 {
+				ClosedUntil::<T>::put(closed);
+			}
+			weight.saturating_add(T::WeightInfo::on_initialize(handled))
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Backfill the pulse of a skipped `block` with its aggregated `signature`.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::WeightInfo::backfill())]
+		pub fn backfill(
+			origin: OriginFor<T>,
+			block: BlockNumberFor<T>,
+			signature: BoundedVec<u8, T::MaxSignatureLen>,
+		) -> DispatchResult {
+			ensure_none(origin)?;
+			Self::check_backfill(block, &signature)?;
+
+			Backfilled::<T>::insert(block, signature);
+			Self::deposit_event(Event::Backfilled { block });
+			Ok(())
+		}
+	}
+
+	#[pallet::validate_unsigned]
+	impl<T: Config> ValidateUnsigned for Pallet<T> {
+		type Call = Call<T>;
+
+		fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+			let Call::backfill { block, signature } = call else {
+				return InvalidTransaction::Call.into()
+			};
+			Self::check_backfill(*block, signature).map_err(|e| match e {
+				Error::<T>::NotSkipped => InvalidTransaction::Future,
+				Error::<T>::BadSignature => InvalidTransaction::BadProof,
+				_ => InvalidTransaction::Stale,
+			})?;
+
+			ValidTransaction::with_tag_prefix("BeaconBackfill")
+				.priority(T::UnsignedPriority::get())
+				.and_provides(block)
+				.longevity(T::BackfillWindow::get().saturated_into::<u64>().max(1))
+				.propagate(true)
+				.build()
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// The status of the pulse of `block`.
+		pub fn status(block: BlockNumberFor<T>) -> PulseStatus<BlockNumberFor<T>> {
+			if T::Beacon::is_published(block) {
+				return PulseStatus::Published
+			}
+			if Backfilled::<T>::contains_key(block) {
+				return PulseStatus::Backfilled
+			}
+			let deadline = block.saturating_add(T::BackfillWindow::get());
+			if deadline <= T::Beacon::latest() {
+				PulseStatus::Missing
+			} else {
+				PulseStatus::Awaiting { deadline }
+			}
+		}
+
+		/// The aggregated signature backfilled for `block`, if any.
+		pub fn backfilled(block: BlockNumberFor<T>) -> Option<Vec<u8>> {
+			Backfilled::<T>::get(block).map(BoundedVec::into_inner)
+		}
+
+		/// Note that the authority set `set_id` signs the pulses from the current block on.
+		///
+		/// Sets that are not newer than the latest one noted are ignored.
+		pub fn note_authority_set(set_id: SetId) {
+			let count = AuthoritySetCount::<T>::get();
+			if count > 0 && set_id <= LatestAuthoritySet::<T>::get() {
+				return
+			}
+			let now = frame_system::Pallet::<T>::block_number();
+			AuthoritySetStarts::<T>::insert(count, (set_id, now));
+			AuthoritySetCount::<T>::put(count.saturating_add(1));
+			LatestAuthoritySet::<T>::put(set_id);
+		}
+
+		/// The id of the authority set that signs the pulse of `block`, i.e. the set active at
+		/// `block`.
+		///
+		/// The genesis set is active from genesis even if it was not noted. Blocks after the
+		/// current one resolve to the current set, which is only final once they are imported.
+		/// Sets that were never noted are skipped, the blocks they were active at resolve to the
+		/// set noted before them. Returns `None` for the blocks before the first set noted if
+		/// that set does not follow the genesis one, as the sets active then are unknown.
+		pub fn authority_set_at(block: BlockNumberFor<T>) -> Option<SetId> {
+			// The sets are noted in the order they took over, so the set of `block` is the
+			// last one noted at or before it.
+			let (mut low, mut high) = (0, AuthoritySetCount::<T>::get());
+			while low < high {
+				let mid = low + (high - low) / 2;
+				let (_, start) = AuthoritySetStarts::<T>::get(mid)?;
+				if start <= block {
+					low = mid + 1;
+				} else {
+					high = mid;
+				}
+			}
+
+			match low.checked_sub(1) {
+				Some(index) => AuthoritySetStarts::<T>::get(index).map(|(set_id, _)| set_id),
+				None => match AuthoritySetStarts::<T>::get(0) {
+					Some((first, _)) if first > 1 => None,
+					_ => Some(0),
+				},
+			}
+		}
+
+		fn check_backfill(block: BlockNumberFor<T>, signature: &[u8]) -> Result<(), Error<T>> {
+			ensure!(block < T::Beacon::latest(), Error::<T>::NotSkipped);
+			ensure!(
+				!T::Beacon::is_published(block) && !Backfilled::<T>::contains_key(block),
+				Error::<T>::AlreadyPublished
+			);
+			ensure!(
+				matches!(Self::status(block), PulseStatus::Awaiting { .. }),
+				Error::<T>::WindowClosed
+			);
+			let set_id = Self::authority_set_at(block).ok_or(Error::<T>::BadSignature)?;
+			ensure!(T::Beacon::verify(block, set_id, signature), Error::<T>::BadSignature);
+			Ok(())
+		}
+	}
+}
@@ -0,0 +1,44 @@
+//! Migrations of the beacon backfill pallet.
+
+use super::*;
+use frame_support::{
+	pallet_prelude::*,
+	traits::{Get, OnRuntimeUpgrade},
+};
+use sp_std::marker::PhantomData;
+
+#[cfg(feature = "try-runtime")]
+use sp_runtime::TryRuntimeError;
+
+/// Notes the authority set `CurrentSetId` as the one signing the pulses from the upgrade on,
+/// when the pallet is added to a running chain.
+///
+/// The sets that were active before are unknown, so the pulses of the blocks before the upgrade
+/// resolve to no set, see [`Pallet::authority_set_at`]. Does nothing once a set has been noted.
+pub struct NoteCurrentAuthoritySet<T, CurrentSetId>(PhantomData<(T, CurrentSetId)>);
+
+impl<T: Config, CurrentSetId: Get<SetId>> OnRuntimeUpgrade
+	for NoteCurrentAuthoritySet<T, CurrentSetId>
+{
+	fn on_runtime_upgrade() -> Weight {
+		if AuthoritySetCount::<T>::get() > 0 {
+			return T::DbWeight::get().reads(1)
+		}
+		Pallet::<T>::note_authority_set(CurrentSetId::get());
+		T::DbWeight::get().reads_writes(3, 3)
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn post_upgrade(_state: Vec<u8>) -> Result<(), TryRuntimeError> {
+		ensure!(
+			LatestAuthoritySet::<T>::get() >= CurrentSetId::get(),
+			"the current authority set is not noted"
+		);
+		ensure!(
+			Pallet::<T>::authority_set_at(frame_system::Pallet::<T>::block_number()) ==
+				Some(LatestAuthoritySet::<T>::get()),
+			"the current block does not resolve to the latest authority set"
+		);
+		Ok(())
+	}
+}
@@ -0,0 +1,124 @@
+//! Test environment for the beacon backfill pallet.
+
+use super::*;
+use crate as pallet_beacon_backfill;
+
+use frame_support::{derive_impl, parameter_types, traits::Hooks};
+use sp_runtime::BuildStorage;
+use std::{cell::RefCell, collections::BTreeSet};
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test {
+		System: frame_system,
+		BeaconBackfill: pallet_beacon_backfill,
+	}
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+impl frame_system::Config for Test {
+	type Block = Block;
+}
+
+thread_local! {
+	static PUBLISHED: RefCell<BTreeSet<u64>> = RefCell::new(BTreeSet::new());
+	static MISSING: RefCell<Vec<u64>> = RefCell::new(Vec::new());
+}
+
+/// Publish the pulse of `block`.
+pub fn publish(block: u64) {
+	PUBLISHED.with(|published| published.borrow_mut().insert(block));
+}
+
+/// The blocks reported to [`OnPulseMissing`], in order.
+pub fn missing() -> Vec<u64> {
+	MISSING.with(|missing| missing.borrow().clone())
+}
+
+/// The signature of the pulse of `block` by the authority set active at it.
+pub fn signature(block: u64) -> BoundedVec<u8, <Test as Config>::MaxSignatureLen> {
+	signature_by(block, BeaconBackfill::authority_set_at(block).unwrap())
+}
+
+pub fn signature_by(
+	block: u64,
+	set_id: SetId,
+) -> BoundedVec<u8, <Test as Config>::MaxSignatureLen> {
+	(block, set_id).encode().try_into().unwrap()
+}
+
+pub struct MockBeacon;
+
+impl PulseSource<u64> for MockBeacon {
+	fn latest() -> u64 {
+		PUBLISHED.with(|published| published.borrow().last().copied().unwrap_or_default())
+	}
+
+	fn is_published(block: u64) -> bool {
+		PUBLISHED.with(|published| published.borrow().contains(&block))
+	}
+
+	fn verify(block: u64, set_id: SetId, signature: &[u8]) -> bool {
+		signature == (block, set_id).encode()
+	}
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+impl BenchmarkHelper<u64> for MockBeacon {
+	fn publish(block: u64) {
+		publish(block)
+	}
+
+	fn sign(block: u64, set_id: SetId) -> Vec<u8> {
+		signature_by(block, set_id).into_inner()
+	}
+}
+
+pub struct RecordMissing;
+
+impl OnPulseMissing<u64> for RecordMissing {
+	fn on_pulse_missing(block: u64) -> Weight {
+		MISSING.with(|missing| missing.borrow_mut().push(block));
+		Weight::zero()
+	}
+}
+
+parameter_types! {
+	pub const BackfillWindow: u64 = 3;
+}
+
+impl Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type Beacon = MockBeacon;
+	type OnPulseMissing = RecordMissing;
+	type BackfillWindow = BackfillWindow;
+	type MaxClosedPerBlock = frame_support::traits::ConstU32<2>;
+	type MaxSignatureLen = frame_support::traits::ConstU32<16>;
+	type UnsignedPriority = frame_support::traits::ConstU64<100>;
+	type WeightInfo = ();
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = MockBeacon;
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let t = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+	PUBLISHED.with(|published| published.borrow_mut().clear());
+	MISSING.with(|missing| missing.borrow_mut().clear());
+
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		BeaconBackfill::on_initialize(1);
+	});
+	ext
+}
+
+/// Advance to block `n`, running the hooks of the pallet.
+pub fn run_to_block(n: u64) {
+	while System::block_number() < n {
+		let next = System::block_number() + 1;
+		System::set_block_number(next);
+		BeaconBackfill::on_initialize(next);
+	}
+}
@@ -0,0 +1,206 @@
+//! Tests for the beacon backfill pallet.
+
+use super::*;
+use crate::mock::*;
+
+use frame_support::{assert_noop, assert_ok, pallet_prelude::*};
+
+#[test]
+fn skipped_pulses_can_be_backfilled() {
+	new_test_ext().execute_with(|| {
+		publish(1);
+		publish(2);
+		publish(4);
+		assert_eq!(BeaconBackfill::status(2), PulseStatus::Published);
+		assert_eq!(BeaconBackfill::status(3), PulseStatus::Awaiting { deadline: 6 });
+
+		assert_ok!(BeaconBackfill::backfill(RuntimeOrigin::none(), 3, signature(3)));
+		assert_eq!(BeaconBackfill::status(3), PulseStatus::Backfilled);
+		assert_eq!(BeaconBackfill::backfilled(3), Some((3u64, 0u64).encode()));
+		System::assert_last_event(Event::Backfilled { block: 3 }.into());
+	});
+}
+
+#[test]
+fn backfills_are_checked() {
+	new_test_ext().execute_with(|| {
+		publish(2);
+		publish(4);
+		assert_noop!(
+			BeaconBackfill::backfill(RuntimeOrigin::signed(1), 3, signature(3)),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			BeaconBackfill::backfill(RuntimeOrigin::none(), 4, signature(4)),
+			Error::<Test>::NotSkipped
+		);
+		assert_noop!(
+			BeaconBackfill::backfill(RuntimeOrigin::none(), 2, signature(2)),
+			Error::<Test>::AlreadyPublished
+		);
+		assert_noop!(
+			BeaconBackfill::backfill(RuntimeOrigin::none(), 3, signature(4)),
+			Error::<Test>::BadSignature
+		);
+
+		publish(7);
+		assert_eq!(BeaconBackfill::status(3), PulseStatus::Missing);
+		assert_noop!(
+			BeaconBackfill::backfill(RuntimeOrigin::none(), 3, signature(3)),
+			Error::<Test>::WindowClosed
+		);
+	});
+}
+
+#[test]
+fn backfills_are_validated_in_the_pool() {
+	new_test_ext().execute_with(|| {
+		publish(2);
+		publish(4);
+		let validate = |block, signature| {
+			BeaconBackfill::validate_unsigned(
+				TransactionSource::External,
+				&Call::backfill { block, signature },
+			)
+		};
+
+		let valid = validate(3, signature(3)).unwrap();
+		assert_eq!(valid.priority, 100);
+		assert_eq!(valid.longevity, 3);
+		assert_eq!(valid.provides, vec![("BeaconBackfill", 3u64).encode()]);
+
+		assert_eq!(validate(3, signature(4)), InvalidTransaction::BadProof.into());
+		assert_eq!(validate(5, signature(5)), InvalidTransaction::Future.into());
+		assert_eq!(validate(2, signature(2)), InvalidTransaction::Stale.into());
+	});
+}
+
+#[test]
+fn missing_pulses_are_reported_once() {
+	new_test_ext().execute_with(|| {
+		publish(1);
+		publish(2);
+		publish(4);
+		publish(8);
+
+		// At most two closed windows are handled per block.
+		run_to_block(2);
+		assert!(missing().is_empty());
+		run_to_block(3);
+		assert_eq!(missing(), vec![3]);
+		run_to_block(4);
+		assert_eq!(missing(), vec![3, 5]);
+		System::assert_last_event(Event::PulseMissing { block: 5 }.into());
+		assert_eq!(ClosedUntil::<Test>::get(), Some(5));
+
+		assert_ok!(BeaconBackfill::backfill(RuntimeOrigin::none(), 6, signature(6)));
+		publish(10);
+		run_to_block(6);
+		assert_eq!(missing(), vec![3, 5, 7]);
+		assert_eq!(ClosedUntil::<Test>::get(), Some(7));
+	});
+}
+
+#[test]
+fn authority_sets_are_resolved_by_block() {
+	new_test_ext().execute_with(|| {
+		// The genesis set is active from genesis without being noted.
+		assert_eq!(BeaconBackfill::authority_set_at(0), Some(0));
+		assert_eq!(BeaconBackfill::authority_set_at(1), Some(0));
+		// Future blocks resolve to the current set.
+		assert_eq!(BeaconBackfill::authority_set_at(2), Some(0));
+
+		for (block, set_id) in [(4, 1), (5, 2), (9, 3)] {
+			run_to_block(block);
+			BeaconBackfill::note_authority_set(set_id);
+		}
+		run_to_block(12);
+
+		let sets = (0..=13).map(BeaconBackfill::authority_set_at).collect::<Vec<_>>();
+		let expected = [0, 0, 0, 0, 1, 2, 2, 2, 2, 3, 3, 3, 3, 3].map(Some);
+		assert_eq!(sets, expected);
+	});
+}
+
+#[test]
+fn authority_sets_that_were_not_noted_are_skipped() {
+	new_test_ext().execute_with(|| {
+		run_to_block(3);
+		BeaconBackfill::note_authority_set(1);
+		run_to_block(6);
+		// The sets 2 to 4 were never noted.
+		BeaconBackfill::note_authority_set(5);
+		// Sets that are not newer than the latest one are ignored.
+		BeaconBackfill::note_authority_set(5);
+		BeaconBackfill::note_authority_set(4);
+		run_to_block(8);
+
+		let sets = (0..=8).map(BeaconBackfill::authority_set_at).collect::<Vec<_>>();
+		let expected = [0, 0, 0, 1, 1, 1, 5, 5, 5].map(Some);
+		assert_eq!(sets, expected);
+		assert_eq!(AuthoritySetCount::<Test>::get(), 2);
+	});
+}
+
+#[test]
+fn authority_sets_before_the_first_one_noted_are_unknown() {
+	new_test_ext().execute_with(|| {
+		run_to_block(3);
+		// The sets 0 and 1 were never noted.
+		BeaconBackfill::note_authority_set(2);
+
+		assert_eq!(BeaconBackfill::authority_set_at(1), None);
+		assert_eq!(BeaconBackfill::authority_set_at(2), None);
+		assert_eq!(BeaconBackfill::authority_set_at(3), Some(2));
+		assert_eq!(BeaconBackfill::authority_set_at(4), Some(2));
+	});
+}
+
+#[test]
+fn the_current_authority_set_is_noted_on_upgrade() {
+	use frame_support::traits::OnRuntimeUpgrade;
+
+	parameter_types! {
+		pub const CurrentSetId: SetId = 7;
+	}
+	type Migration = migrations::NoteCurrentAuthoritySet<Test, CurrentSetId>;
+
+	new_test_ext().execute_with(|| {
+		run_to_block(5);
+		Migration::on_runtime_upgrade();
+		assert_eq!(LatestAuthoritySet::<Test>::get(), 7);
+		assert_eq!(BeaconBackfill::authority_set_at(4), None);
+		assert_eq!(BeaconBackfill::authority_set_at(5), Some(7));
+
+		// Pulses of the current set are verified against it, and the next rotation follows it.
+		publish(4);
+		publish(7);
+		assert_ok!(BeaconBackfill::backfill(RuntimeOrigin::none(), 5, signature_by(5, 7)));
+		run_to_block(6);
+		BeaconBackfill::note_authority_set(8);
+		assert_eq!(BeaconBackfill::authority_set_at(5), Some(7));
+		assert_eq!(BeaconBackfill::authority_set_at(6), Some(8));
+
+		// Running the migration again does nothing.
+		Migration::on_runtime_upgrade();
+		assert_eq!(LatestAuthoritySet::<Test>::get(), 8);
+	});
+}
+
+#[test]
+fn skipped_pulses_are_backfilled_with_the_set_active_at_the_block() {
+	new_test_ext().execute_with(|| {
+		publish(2);
+		run_to_block(4);
+		BeaconBackfill::note_authority_set(1);
+		publish(5);
+
+		// Block 3 was skipped in the session of the set 0.
+		assert_noop!(
+			BeaconBackfill::backfill(RuntimeOrigin::none(), 3, signature_by(3, 1)),
+			Error::<Test>::BadSignature
+		);
+		assert_ok!(BeaconBackfill::backfill(RuntimeOrigin::none(), 3, signature_by(3, 0)));
+		assert_ok!(BeaconBackfill::backfill(RuntimeOrigin::none(), 4, signature_by(4, 1)));
+	});
+}
@@ -0,0 +1,62 @@
+//! Weights for `pallet_beacon_backfill`.
+//!
+//! The benchmarks of the pallet are in `benchmarking.rs`. These values are estimated from the
+//! storage accesses of the benchmarks, and are to be replaced by the output of
+//! `benchmark pallet --pallet pallet_beacon_backfill` on reference hardware, see the README.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+#![allow(missing_docs)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use core::marker::PhantomData;
+
+/// Weight functions needed for `pallet_beacon_backfill`.
+pub trait WeightInfo {
+	fn backfill() -> Weight;
+	fn on_initialize(n: u32, ) -> Weight;
+}
+
+/// Weights for `pallet_beacon_backfill` using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: `RandomnessBeacon::Height` (r:1 w:0)
+	/// Storage: `RandomnessBeacon::Pulses` (r:1 w:0)
+	/// Storage: `BeaconBackfill::Backfilled` (r:1 w:1)
+	/// Storage: `Etf::RoundPublic` (r:1 w:0)
+	fn backfill() -> Weight {
+		// One verification of the aggregated signature, two pairings.
+		Weight::from_parts(600_000_000, 0)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `RandomnessBeacon::Height` (r:1 w:0)
+	/// Storage: `BeaconBackfill::ClosedUntil` (r:1 w:1)
+	/// Storage: `RandomnessBeacon::Pulses` (r:n w:0)
+	/// Storage: `BeaconBackfill::Backfilled` (r:n w:0)
+	/// The range of component `n` is `[0, 16]`, the number of closed backfill windows.
+	fn on_initialize(n: u32, ) -> Weight {
+		Weight::from_parts(3_000_000, 0)
+			.saturating_add(Weight::from_parts(2_000_000, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().reads((2_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+}
+
+// For backwards compatibility and tests.
+impl WeightInfo for () {
+	fn backfill() -> Weight {
+		Weight::from_parts(600_000_000, 0)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	fn on_initialize(n: u32, ) -> Weight {
+		Weight::from_parts(3_000_000, 0)
+			.saturating_add(Weight::from_parts(2_000_000, 0).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().reads((2_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+}
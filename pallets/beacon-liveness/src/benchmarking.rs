@@ -0,0 +1,64 @@
+//! Benchmarks for the beacon liveness pallet.
+
+use super::*;
+use crate::Pallet as BeaconLiveness;
+
+use frame_benchmarking::v2::*;
+use frame_support::traits::{Get, Hooks};
+
+#[benchmarks]
+mod benchmarks {
+	use super::*;
+
+	#[benchmark]
+	fn on_initialize(p: Linear<0, { T::MaxPulsesPerBlock::get() }>) {
+		// `p` pulses were published, each signed by every authority.
+		T::BenchmarkHelper::set_validators(T::MaxAuthorities::get());
+		for block in 1..=p {
+			T::BenchmarkHelper::publish(block.into());
+			BeaconLiveness::<T>::note_pulse(block.into(), 0..T::MaxAuthorities::get());
+		}
+		let now = frame_system::Pallet::<T>::block_number();
+
+		#[block]
+		{
+			BeaconLiveness::<T>::on_initialize(now);
+		}
+
+		assert!(Noted::<T>::get().is_empty());
+		assert_eq!(SessionPulses::<T>::get(), p);
+	}
+
+	#[benchmark]
+	fn end_session(n: Linear<1, { T::MaxAuthorities::get() }>) {
+		// Every validator withheld its shares of the pulses of the session.
+		T::BenchmarkHelper::set_validators(n);
+		SessionPulses::<T>::put(1);
+
+		#[block]
+		{
+			BeaconLiveness::<T>::end_session(0);
+		}
+
+		assert_eq!(PreviousValidators::<T>::get().len() as u32, n);
+	}
+
+	#[benchmark]
+	fn verify_pulse(n: Linear<1, { T::MaxAuthorities::get() }>) -> Result<(), BenchmarkError> {
+		// Every one of `n` authorities signed the pulse of the current block.
+		let block = frame_system::Pallet::<T>::block_number();
+		let signatures = T::BenchmarkHelper::sign_pulse(block, n);
+		let signers;
+
+		#[block]
+		{
+			signers = T::PulseVerifier::verify_pulse(block, &signatures);
+		}
+
+		let signers = signers.map_err(|_| BenchmarkError::Stop("The pulse does not verify"))?;
+		assert_eq!(signers.len() as u32, n);
+		Ok(())
+	}
+
+	impl_benchmark_test_suite!(BeaconLiveness, crate::mock::new_test_ext(), crate::mock::Test);
+}
@@ -0,0 +1,387 @@
+//! # Beacon Liveness Pallet
+//!
+//! Reports the ETF authorities that withhold their shares of the randomness beacon.
+//!
+//! Every pulse submitted to the beacon carries one signature per authority, left empty for the
+//! authorities whose share is missing. The runtime verifies a submitted pulse and tells the
+//! pallet which authorities signed it with [`Pallet::verify_and_note_pulse`] before the
+//! submission is dispatched, and the pallet credits them in the next block, only if the
+//! submission actually published the pulse. The verification is charged to the block with
+//! the benchmarked [`WeightInfo::verify_pulse`], as the beacon fixes the weight of submissions.
+//! The contributors of every published pulse are rewarded through
+//! [`Config::Reward`], so that operating the ETF gadget reliably pays off.
+//!
+//! At the end of each session, [`Pallet::end_session`] reports the authorities that contributed
+//! to less than [`Config::MinParticipation`] of the pulses published in the session to
+//! [`Config::ReportUnresponsiveness`] as a [`WithholdingOffence`], slashed like the
+//! unresponsiveness offence of `pallet_im_online`. Sessions without any published pulse are
+//! not reported, as a stalled beacon cannot be blamed on any single authority.
+//!
+//! Authorities are identified by their index in the BEEFY authority set that signed the pulse,
+//! which is the index of the validator in the session of the pulse's block. Pulses of blocks
+//! of the previous session are credited to its validators, but are not counted towards the
+//! participation in the current session, and older pulses are not credited at all.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+pub mod weights;
+pub use weights::WeightInfo;
+
+use codec::{Decode, Encode};
+use frame_support::traits::{ValidatorSet, ValidatorSetWithIdentification};
+use scale_info::TypeInfo;
+use sp_runtime::{
+	traits::{Convert, Saturating},
+	PerThing, Perbill, RuntimeDebug,
+};
+use sp_staking::{
+	offence::{Kind, Offence, ReportOffence},
+	SessionIndex,
+};
+use sp_std::prelude::*;
+
+/// The pulses published by the beacon.
+pub trait PulseSource<BlockNumber> {
+	/// Whether the beacon published a pulse for `block`.
+	fn is_published(block: BlockNumber) -> bool;
+}
+
+/// Verifies the signatures submitted for a pulse.
+pub trait VerifyPulse<BlockNumber> {
+	/// Why a submission is rejected.
+	type Error;
+
+	/// Verify the `signatures` submitted for the pulse of `block`, one per authority, and
+	/// return the indices of the authorities whose signature is valid.
+	fn verify_pulse(block: BlockNumber, signatures: &[Vec<u8>]) -> Result<Vec<u32>, Self::Error>;
+}
+
+/// Rewards the authorities whose shares are included in published pulses.
+pub trait RewardContributors<ValidatorId> {
+	/// Reward `contributors` for their shares of a published pulse.
+	fn reward_contributors(contributors: Vec<ValidatorId>);
+}
+
+impl<ValidatorId> RewardContributors<ValidatorId> for () {
+	fn reward_contributors(_: Vec<ValidatorId>) {}
+}
+
+/// Sets up the beacon and the validators for the benchmarks of this pallet.
+#[cfg(feature = "runtime-benchmarks")]
+pub trait BenchmarkHelper<BlockNumber> {
+	/// Publish the pulse of `block`.
+	fn publish(block: BlockNumber);
+
+	/// Make `count` validators the validators of the session, each with a full identification.
+	fn set_validators(count: u32);
+
+	/// The signatures of the pulse of `block` by `signers` authorities, each valid under
+	/// [`Config::PulseVerifier`] and enough to recover the pulse.
+	fn sign_pulse(block: BlockNumber, signers: u32) -> Vec<Vec<u8>>;
+}
+
+/// A type for representing the validator id in a session.
+pub type ValidatorId<T> = <<T as Config>::ValidatorSet as ValidatorSet<
+	<T as frame_system::Config>::AccountId,
+>>::ValidatorId;
+
+/// A tuple of (ValidatorId, Identification) where `Identification` is the full identification
+/// of `ValidatorId`.
+pub type IdentificationTuple<T> = (
+	ValidatorId<T>,
+	<<T as Config>::ValidatorSet as ValidatorSetWithIdentification<
+		<T as frame_system::Config>::AccountId,
+	>>::Identification,
+);
+
+/// The conversion of a validator id into its full identification.
+type IdentificationOf<T> = <<T as Config>::ValidatorSet as ValidatorSetWithIdentification<
+	<T as frame_system::Config>::AccountId,
+>>::IdentificationOf;
+
+/// An offence committed by the authorities that withheld their beacon shares in a session.
+#[derive(RuntimeDebug, TypeInfo, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct WithholdingOffence<Offender> {
+	/// The session in which the shares were withheld.
+	pub session_index: SessionIndex,
+	/// The size of the validator set in the session.
+	pub validator_set_count: u32,
+	/// The authorities that withheld their shares.
+	pub offenders: Vec<Offender>,
+}
+
+impl<Offender: Clone> Offence<Offender> for WithholdingOffence<Offender> {
+	const ID: Kind = *b"beacon:unrespons";
+	type TimeSlot = SessionIndex;
+
+	fn offenders(&self) -> Vec<Offender> {
+		self.offenders.clone()
+	}
+
+	fn session_index(&self) -> SessionIndex {
+		self.session_index
+	}
+
+	fn validator_set_count(&self) -> u32 {
+		self.validator_set_count
+	}
+
+	fn time_slot(&self) -> Self::TimeSlot {
+		self.session_index
+	}
+
+	fn slash_fraction(&self, offenders_count: u32) -> Perbill {
+		// The same curve as for unresponsive validators: nothing while at most a tenth of the
+		// set withholds, then `3 * (k - (n / 10 + 1)) / n * 7%`.
+		if let Some(threshold) = offenders_count.checked_sub(self.validator_set_count / 10 + 1) {
+			let x = Perbill::from_rational(3 * threshold, self.validator_set_count);
+			x.saturating_mul(Perbill::from_percent(7))
+		} else {
+			Perbill::default()
+		}
+	}
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The validators of the session, in the order of the BEEFY authority set.
+		type ValidatorSet: ValidatorSetWithIdentification<Self::AccountId>;
+
+		/// Where the authorities that withheld their shares are reported.
+		type ReportUnresponsiveness: ReportOffence<
+			Self::AccountId,
+			IdentificationTuple<Self>,
+			WithholdingOffence<IdentificationTuple<Self>>,
+		>;
+
+		/// The beacon whose pulses are credited.
+		type Beacon: PulseSource<BlockNumberFor<Self>>;
+
+		/// Verifies the signatures of submitted pulses.
+		type PulseVerifier: VerifyPulse<BlockNumberFor<Self>>;
+
+		/// How the contributors of published pulses are rewarded.
+		type Reward: RewardContributors<ValidatorId<Self>>;
+
+		/// The maximum number of authorities.
+		#[pallet::constant]
+		type MaxAuthorities: Get<u32>;
+
+		/// The maximum number of pulses noted per block.
+		#[pallet::constant]
+		type MaxPulsesPerBlock: Get<u32>;
+
+		/// The share of the pulses published in a session an authority has to contribute to.
+		#[pallet::constant]
+		type MinParticipation: Get<Perbill>;
+
+		/// Weight information for the operations of this pallet.
+		type WeightInfo: WeightInfo;
+
+		/// Sets up the beacon and the validators for the benchmarks.
+		#[cfg(feature = "runtime-benchmarks")]
+		type BenchmarkHelper: BenchmarkHelper<BlockNumberFor<Self>>;
+	}
+
+	/// The pulses submitted in the current block with the indices of the authorities that
+	/// signed them, at most one per pulse, credited in the next block if they were published.
+	#[pallet::storage]
+	pub type Noted<T: Config> = StorageValue<
+		_,
+		BoundedVec<(BlockNumberFor<T>, BoundedVec<u32, T::MaxAuthorities>), T::MaxPulsesPerBlock>,
+		ValueQuery,
+	>;
+
+	/// The block at which the current session started.
+	#[pallet::storage]
+	pub type SessionStart<T: Config> = StorageValue<_, BlockNumberFor<T>, ValueQuery>;
+
+	/// The block at which the previous session started.
+	#[pallet::storage]
+	pub type PreviousSessionStart<T: Config> = StorageValue<_, BlockNumberFor<T>, ValueQuery>;
+
+	/// The validators of the previous session, in the order of its BEEFY authority set.
+	#[pallet::storage]
+	pub type PreviousValidators<T: Config> =
+		StorageValue<_, BoundedVec<ValidatorId<T>, T::MaxAuthorities>, ValueQuery>;
+
+	/// The number of pulses published in the current session.
+	#[pallet::storage]
+	pub type SessionPulses<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+	/// The number of pulses published in the current session each authority contributed to,
+	/// by authority index.
+	#[pallet::storage]
+	pub type Contributions<T: Config> =
+		StorageValue<_, BoundedVec<u32, T::MaxAuthorities>, ValueQuery>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// Every authority contributed to enough pulses in the session.
+		AllContributed { session_index: SessionIndex },
+		/// Some authorities withheld their shares in the session.
+		SomeWithheld { session_index: SessionIndex, withheld: Vec<IdentificationTuple<T>> },
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(_n: BlockNumberFor<T>) -> Weight {
+			T::WeightInfo::on_initialize(Self::credit_noted())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Note the verified pulse submitted for `block`, signed by the authorities at `signers`
+		/// of the authority set active at `block`, before the submission is dispatched.
+		///
+		/// A pulse can only be submitted while it is not published, so a later submission for
+		/// the same block means that the dispatch of the earlier one failed, and it replaces the
+		/// earlier one. The signers are only credited if the pulse was published by the end of
+		/// the block, i.e. by the last submission noted for it. Only the first
+		/// [`Config::MaxPulsesPerBlock`] pulses of a block are noted.
+		pub fn note_pulse(block: BlockNumberFor<T>, signers: impl IntoIterator<Item = u32>) {
+			let signers = BoundedVec::truncate_from(signers.into_iter().collect());
+			Noted::<T>::mutate(|noted| {
+				match noted.iter_mut().find(|(noted, _)| *noted == block) {
+					Some(entry) => entry.1 = signers,
+					None => {
+						let _ = noted.try_push((block, signers));
+					},
+				}
+			});
+		}
+
+		/// Verify the `signatures` submitted for the pulse of `block` with
+		/// [`Config::PulseVerifier`] and note the authorities whose signature is valid, see
+		/// [`Pallet::note_pulse`].
+		///
+		/// The weight of the verification is registered with the current block.
+		pub fn verify_and_note_pulse(
+			block: BlockNumberFor<T>,
+			signatures: &[Vec<u8>],
+		) -> Result<(), <T::PulseVerifier as VerifyPulse<BlockNumberFor<T>>>::Error> {
+			let signers = T::PulseVerifier::verify_pulse(block, signatures)?;
+			frame_system::Pallet::<T>::register_extra_weight_unchecked(
+				T::WeightInfo::verify_pulse(signatures.len() as u32),
+				DispatchClass::Mandatory,
+			);
+			Self::note_pulse(block, signers);
+			Ok(())
+		}
+
+		/// Report the authorities that withheld their shares in the ending session.
+		pub fn end_session(session_index: SessionIndex) {
+			Self::credit_noted();
+			let pulses = SessionPulses::<T>::take();
+			let contributions = Contributions::<T>::take();
+			let validators = T::ValidatorSet::validators();
+			// The validators of the next session sign the pulses from the current block on.
+			PreviousSessionStart::<T>::put(SessionStart::<T>::get());
+			SessionStart::<T>::put(frame_system::Pallet::<T>::block_number());
+			PreviousValidators::<T>::put(BoundedVec::truncate_from(validators.clone()));
+			if pulses == 0 {
+				return
+			}
+
+			let required = T::MinParticipation::get().mul_ceil(pulses);
+			let validator_set_count = validators.len() as u32;
+			let withheld = validators
+				.into_iter()
+				.enumerate()
+				.filter(|(index, _)| {
+					contributions.get(*index).copied().unwrap_or_default() < required
+				})
+				.filter_map(|(_, id)| {
+					IdentificationOf::<T>::convert(id.clone()).map(|full_id| (id, full_id))
+				})
+				.collect::<Vec<IdentificationTuple<T>>>();
+			frame_system::Pallet::<T>::register_extra_weight_unchecked(
+				T::WeightInfo::end_session(validator_set_count),
+				DispatchClass::Mandatory,
+			);
+
+			if withheld.is_empty() {
+				Self::deposit_event(Event::AllContributed { session_index });
+				return
+			}
+			Self::deposit_event(Event::SomeWithheld { session_index, withheld: withheld.clone() });
+			let offence =
+				WithholdingOffence { session_index, validator_set_count, offenders: withheld };
+			if let Err(e) = T::ReportUnresponsiveness::report_offence(vec![], offence) {
+				sp_runtime::print(e);
+			}
+		}
+
+		/// Credit and reward the authorities that signed the noted pulses that were published.
+		///
+		/// Returns the number of noted pulses.
+		fn credit_noted() -> u32 {
+			let noted = Noted::<T>::take();
+			if noted.is_empty() {
+				return 0
+			}
+			let session_start = SessionStart::<T>::get();
+			let previous_session_start = PreviousSessionStart::<T>::get();
+			let validators = T::ValidatorSet::validators();
+			let previous_validators = PreviousValidators::<T>::get();
+			let mut published = 0u32;
+			Contributions::<T>::mutate(|contributions| {
+				for (block, signers) in noted.iter() {
+					if !T::Beacon::is_published(*block) {
+						continue
+					}
+					let signed_by = if *block >= session_start {
+						&validators[..]
+					} else if *block >= previous_session_start {
+						&previous_validators[..]
+					} else {
+						continue
+					};
+					T::Reward::reward_contributors(
+						signers
+							.iter()
+							.filter_map(|signer| signed_by.get(*signer as usize).cloned())
+							.collect(),
+					);
+					// The participation in the previous session has already been reported.
+					if *block < session_start {
+						continue
+					}
+					published.saturating_inc();
+					for signer in signers.iter().map(|signer| *signer as usize) {
+						if contributions.len() <= signer {
+							contributions.bounded_resize(signer + 1, 0);
+						}
+						if let Some(count) = contributions.get_mut(signer) {
+							count.saturating_inc();
+						}
+					}
+				}
+			});
+			SessionPulses::<T>::mutate(|pulses| pulses.saturating_accrue(published));
+			noted.len() as u32
+		}
+	}
+}
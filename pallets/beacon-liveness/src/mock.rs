@@ -0,0 +1,173 @@
+//! Test environment for the beacon liveness pallet.
+
+use super::*;
+use crate as pallet_beacon_liveness;
+
+use frame_support::{
+	derive_impl, parameter_types,
+	traits::{ConstU32, Hooks},
+};
+use sp_runtime::{traits::ConvertInto, BuildStorage};
+use sp_staking::offence::OffenceError;
+use std::{cell::RefCell, collections::BTreeSet};
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test {
+		System: frame_system,
+		BeaconLiveness: pallet_beacon_liveness,
+	}
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+impl frame_system::Config for Test {
+	type Block = Block;
+}
+
+type Offence = WithholdingOffence<(u64, u64)>;
+
+thread_local! {
+	static VALIDATORS: RefCell<Vec<u64>> = RefCell::new(vec![1, 2, 3, 4]);
+	static PUBLISHED: RefCell<BTreeSet<u64>> = RefCell::new(BTreeSet::new());
+	static OFFENCES: RefCell<Vec<Offence>> = RefCell::new(Vec::new());
+	static REWARDED: RefCell<Vec<Vec<u64>>> = RefCell::new(Vec::new());
+}
+
+/// Set the validators of the session.
+pub fn set_validators(validators: Vec<u64>) {
+	VALIDATORS.with(|stored| *stored.borrow_mut() = validators);
+}
+
+/// Publish the pulse of `block`.
+pub fn publish(block: u64) {
+	PUBLISHED.with(|published| published.borrow_mut().insert(block));
+}
+
+/// The offences reported so far.
+pub fn offences() -> Vec<Offence> {
+	OFFENCES.with(|offences| offences.borrow().clone())
+}
+
+/// The contributors rewarded so far, per pulse.
+pub fn rewarded() -> Vec<Vec<u64>> {
+	REWARDED.with(|rewarded| rewarded.borrow().clone())
+}
+
+pub struct MockValidatorSet;
+
+impl ValidatorSet<u64> for MockValidatorSet {
+	type ValidatorId = u64;
+	type ValidatorIdOf = ConvertInto;
+
+	fn session_index() -> SessionIndex {
+		0
+	}
+
+	fn validators() -> Vec<u64> {
+		VALIDATORS.with(|validators| validators.borrow().clone())
+	}
+}
+
+impl ValidatorSetWithIdentification<u64> for MockValidatorSet {
+	type Identification = u64;
+	type IdentificationOf = ConvertInto;
+}
+
+pub struct RecordOffences;
+
+impl ReportOffence<u64, (u64, u64), Offence> for RecordOffences {
+	fn report_offence(_reporters: Vec<u64>, offence: Offence) -> Result<(), OffenceError> {
+		OFFENCES.with(|offences| offences.borrow_mut().push(offence));
+		Ok(())
+	}
+
+	fn is_known_offence(_offenders: &[(u64, u64)], _time_slot: &SessionIndex) -> bool {
+		false
+	}
+}
+
+pub struct MockBeacon;
+
+impl PulseSource<u64> for MockBeacon {
+	fn is_published(block: u64) -> bool {
+		PUBLISHED.with(|published| published.borrow().contains(&block))
+	}
+}
+
+/// Accepts the non-empty signatures of pulses that are not published yet.
+impl VerifyPulse<u64> for MockBeacon {
+	type Error = ();
+
+	fn verify_pulse(block: u64, signatures: &[Vec<u8>]) -> Result<Vec<u32>, ()> {
+		let signers = (0..signatures.len() as u32)
+			.filter(|&index| !signatures[index as usize].is_empty())
+			.collect::<Vec<_>>();
+		match Self::is_published(block) || signers.is_empty() {
+			true => Err(()),
+			false => Ok(signers),
+		}
+	}
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+impl BenchmarkHelper<u64> for MockBeacon {
+	fn publish(block: u64) {
+		publish(block)
+	}
+
+	fn set_validators(count: u32) {
+		set_validators((1..=count as u64).collect())
+	}
+
+	fn sign_pulse(_block: u64, signers: u32) -> Vec<Vec<u8>> {
+		vec![vec![1]; signers as usize]
+	}
+}
+
+pub struct RecordRewards;
+
+impl RewardContributors<u64> for RecordRewards {
+	fn reward_contributors(contributors: Vec<u64>) {
+		REWARDED.with(|rewarded| rewarded.borrow_mut().push(contributors));
+	}
+}
+
+parameter_types! {
+	pub const MinParticipation: Perbill = Perbill::from_percent(50);
+}
+
+impl Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type ValidatorSet = MockValidatorSet;
+	type ReportUnresponsiveness = RecordOffences;
+	type Beacon = MockBeacon;
+	type PulseVerifier = MockBeacon;
+	type Reward = RecordRewards;
+	type MaxAuthorities = ConstU32<8>;
+	type MaxPulsesPerBlock = ConstU32<4>;
+	type MinParticipation = MinParticipation;
+	type WeightInfo = ();
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = MockBeacon;
+}
+
+/// Note and publish a pulse for `block` signed by `signers`, and move on to the next block.
+pub fn pulse(block: u64, signers: &[u32]) {
+	BeaconLiveness::note_pulse(block, signers.iter().copied());
+	publish(block);
+	next_block();
+}
+
+pub fn next_block() {
+	let next = System::block_number() + 1;
+	System::set_block_number(next);
+	BeaconLiveness::on_initialize(next);
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let t = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
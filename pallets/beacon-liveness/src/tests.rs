@@ -0,0 +1,178 @@
+//! Tests for the beacon liveness pallet.
+
+use super::*;
+use crate::mock::*;
+use frame_support::dispatch::DispatchClass;
+
+#[test]
+fn only_published_pulses_are_credited() {
+	new_test_ext().execute_with(|| {
+		BeaconLiveness::note_pulse(1, [0, 2]);
+		publish(1);
+		// Submitted, but never published.
+		BeaconLiveness::note_pulse(2, [0, 1]);
+		assert_eq!(SessionPulses::<Test>::get(), 0);
+
+		next_block();
+		assert!(Noted::<Test>::get().is_empty());
+		assert_eq!(SessionPulses::<Test>::get(), 1);
+		assert_eq!(Contributions::<Test>::get().into_inner(), vec![1, 0, 1]);
+	});
+}
+
+#[test]
+fn verified_pulses_are_noted_and_charged() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(BeaconLiveness::verify_and_note_pulse(1, &[vec![1], vec![], vec![1]]), Ok(()));
+		let charged = *System::block_weight().get(DispatchClass::Mandatory);
+		assert_eq!(charged, <() as WeightInfo>::verify_pulse(3));
+		// Rejected submissions are neither noted nor charged.
+		assert_eq!(BeaconLiveness::verify_and_note_pulse(2, &[vec![], vec![]]), Err(()));
+		assert_eq!(*System::block_weight().get(DispatchClass::Mandatory), charged);
+		publish(1);
+
+		next_block();
+		assert_eq!(Contributions::<Test>::get().into_inner(), vec![1, 0, 1]);
+	});
+}
+
+#[test]
+fn contributors_of_published_pulses_are_rewarded() {
+	new_test_ext().execute_with(|| {
+		BeaconLiveness::note_pulse(1, [0, 2, 3]);
+		publish(1);
+		BeaconLiveness::note_pulse(2, [1, 2]);
+		// Signers beyond the validator set are not rewarded.
+		BeaconLiveness::note_pulse(3, [1, 7]);
+		publish(3);
+
+		next_block();
+		assert_eq!(rewarded(), vec![vec![1, 3, 4], vec![2]]);
+	});
+}
+
+#[test]
+fn contributors_are_rewarded_once_per_pulse() {
+	new_test_ext().execute_with(|| {
+		// Competing submissions for the same pulse.
+		BeaconLiveness::note_pulse(1, [0, 1]);
+		BeaconLiveness::note_pulse(1, [0, 1, 2]);
+		publish(1);
+
+		next_block();
+		next_block();
+		assert_eq!(rewarded(), vec![vec![1, 2, 3]]);
+	});
+}
+
+#[test]
+fn withholding_authorities_are_reported() {
+	new_test_ext().execute_with(|| {
+		pulse(1, &[0, 1, 2]);
+		pulse(2, &[0, 1]);
+		pulse(3, &[0, 1, 3]);
+		// Noted in the last block of the session, credited before reporting.
+		BeaconLiveness::note_pulse(4, [0, 1]);
+		publish(4);
+
+		BeaconLiveness::end_session(7);
+		// Two of the four pulses are required: the validators at 2 and 3 contributed to one.
+		let withheld = vec![(3, 3), (4, 4)];
+		System::assert_last_event(
+			Event::SomeWithheld { session_index: 7, withheld: withheld.clone() }.into(),
+		);
+		let offence =
+			WithholdingOffence { session_index: 7, validator_set_count: 4, offenders: withheld };
+		assert_eq!(offences(), vec![offence]);
+		assert_eq!(SessionPulses::<Test>::get(), 0);
+		assert!(Contributions::<Test>::get().is_empty());
+	});
+}
+
+#[test]
+fn contributing_authorities_are_not_reported() {
+	new_test_ext().execute_with(|| {
+		pulse(1, &[0, 1, 2, 3]);
+		pulse(2, &[1, 2, 3]);
+		pulse(3, &[0, 3]);
+
+		BeaconLiveness::end_session(1);
+		System::assert_last_event(Event::AllContributed { session_index: 1 }.into());
+		assert!(offences().is_empty());
+	});
+}
+
+#[test]
+fn sessions_without_pulses_are_not_reported() {
+	new_test_ext().execute_with(|| {
+		BeaconLiveness::note_pulse(1, [0]);
+		next_block();
+
+		BeaconLiveness::end_session(1);
+		assert!(System::events().is_empty());
+		assert!(offences().is_empty());
+
+		// The next session starts from scratch.
+		set_validators(vec![5, 6]);
+		pulse(2, &[1]);
+		BeaconLiveness::end_session(2);
+		assert_eq!(offences()[0].offenders, vec![(5, 5)]);
+	});
+}
+
+#[test]
+fn only_the_submission_that_published_a_pulse_is_credited() {
+	new_test_ext().execute_with(|| {
+		// The dispatch of the first submission failed, the second one published the pulse.
+		BeaconLiveness::note_pulse(1, [0, 1]);
+		BeaconLiveness::note_pulse(1, [2, 3]);
+		publish(1);
+
+		next_block();
+		assert_eq!(SessionPulses::<Test>::get(), 1);
+		assert_eq!(Contributions::<Test>::get().into_inner(), vec![0, 0, 1, 1]);
+		assert_eq!(rewarded(), vec![vec![3, 4]]);
+	});
+}
+
+#[test]
+fn pulses_are_credited_to_the_set_that_signed_them() {
+	new_test_ext().execute_with(|| {
+		next_block();
+		BeaconLiveness::end_session(1);
+		set_validators(vec![5, 6, 7, 8]);
+
+		// The pulse of the last block of the previous session is published late, it is
+		// rewarded to the previous validators but not counted in the current session.
+		BeaconLiveness::note_pulse(1, [0, 1]);
+		publish(1);
+		pulse(2, &[2, 3]);
+		assert_eq!(rewarded(), vec![vec![1, 2], vec![7, 8]]);
+		assert_eq!(SessionPulses::<Test>::get(), 1);
+		assert_eq!(Contributions::<Test>::get().into_inner(), vec![0, 0, 1, 1]);
+
+		BeaconLiveness::end_session(2);
+		assert_eq!(offences()[0].offenders, vec![(5, 5), (6, 6)]);
+
+		// Pulses older than the previous session are not credited.
+		set_validators(vec![9, 10]);
+		BeaconLiveness::note_pulse(1, [0, 1]);
+		next_block();
+		assert_eq!(rewarded().len(), 2);
+		assert_eq!(SessionPulses::<Test>::get(), 0);
+	});
+}
+
+#[test]
+fn slash_fraction_follows_im_online() {
+	let offence = |offenders: u64| WithholdingOffence {
+		session_index: 0,
+		validator_set_count: 50,
+		offenders: (0..offenders).collect::<Vec<_>>(),
+	};
+
+	// At most a tenth of the set withholding is not slashed.
+	assert_eq!(offence(6).slash_fraction(6), Perbill::zero());
+	// 3 * (10 - 6) / 50 * 7%
+	assert_eq!(offence(10).slash_fraction(10), Perbill::from_parts(16_800_000));
+}
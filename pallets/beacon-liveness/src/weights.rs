@@ -0,0 +1,101 @@
+//! Weights for `pallet_beacon_liveness`.
+//!
+//! The benchmarks of the pallet are in `benchmarking.rs`. These values are estimated from the
+//! storage accesses of the benchmarks, and are to be replaced by the output of
+//! `benchmark pallet --pallet pallet_beacon_liveness` on reference hardware, see the README.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+#![allow(missing_docs)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use core::marker::PhantomData;
+
+/// Weight functions needed for `pallet_beacon_liveness`.
+pub trait WeightInfo {
+	fn on_initialize(p: u32, ) -> Weight;
+	fn end_session(n: u32, ) -> Weight;
+	fn verify_pulse(n: u32, ) -> Weight;
+}
+
+/// Weights for `pallet_beacon_liveness` using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: `BeaconLiveness::Noted` (r:1 w:1)
+	/// Storage: `BeaconLiveness::SessionStart` (r:1 w:0)
+	/// Storage: `BeaconLiveness::PreviousSessionStart` (r:1 w:0)
+	/// Storage: `Session::Validators` (r:1 w:0)
+	/// Storage: `BeaconLiveness::PreviousValidators` (r:1 w:0)
+	/// Storage: `RandomnessBeacon::Pulses` (r:p w:0)
+	/// Storage: `Staking::ActiveEra` (r:p w:0)
+	/// Storage: `Staking::ErasRewardPoints` (r:p w:p)
+	/// Storage: `BeaconLiveness::Contributions` (r:1 w:1)
+	/// Storage: `BeaconLiveness::SessionPulses` (r:1 w:1)
+	/// The range of component `p` is `[0, 16]`, the number of noted pulses.
+	fn on_initialize(p: u32, ) -> Weight {
+		Weight::from_parts(3_000_000, 0)
+			.saturating_add(Weight::from_parts(2_000_000, 0).saturating_mul(p.into()))
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().reads((3_u64).saturating_mul(p.into())))
+			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(p.into())))
+			.saturating_add(T::DbWeight::get().reads_writes(2_u64, 3_u64))
+	}
+	/// Storage: `BeaconLiveness::SessionPulses` (r:1 w:1)
+	/// Storage: `BeaconLiveness::Contributions` (r:1 w:1)
+	/// Storage: `Session::Validators` (r:1 w:0)
+	/// Storage: `BeaconLiveness::SessionStart` (r:1 w:1)
+	/// Storage: `BeaconLiveness::PreviousSessionStart` (r:0 w:1)
+	/// Storage: `BeaconLiveness::PreviousValidators` (r:0 w:1)
+	/// Storage: `Staking::ErasStakers` (r:n w:0)
+	/// Storage: `Offences::Reports` (r:n w:n)
+	/// The range of component `n` is `[1, 100]`, the number of validators.
+	fn end_session(n: u32, ) -> Weight {
+		Weight::from_parts(20_000_000, 0)
+			.saturating_add(Weight::from_parts(5_000_000, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().reads((2_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(5_u64))
+			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(n.into())))
+	}
+	/// Storage: `RandomnessBeacon::Height` (r:1 w:0)
+	/// Storage: `RandomnessBeacon::Pulses` (r:1 w:0)
+	/// Storage: `BeefyEtf::Authorities` (r:1 w:0)
+	/// Storage: `BeaconBackfill::AuthoritySetCount` (r:1 w:0)
+	/// Storage: `BeaconBackfill::AuthoritySetStarts` (r:2 w:0)
+	/// Storage: `Etf::Commitments` (r:1 w:0)
+	/// Storage: `EtfResharing::Threshold` (r:1 w:0)
+	/// Storage: `Etf::RoundPublic` (r:1 w:0)
+	/// The range of component `n` is `[1, 100]`, the number of signatures.
+	fn verify_pulse(n: u32, ) -> Weight {
+		// Two pairings per signature, and two for the recovered pulse signature.
+		Weight::from_parts(600_000_000, 0)
+			.saturating_add(Weight::from_parts(600_000_000, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(9_u64))
+	}
+}
+
+// For backwards compatibility and tests.
+impl WeightInfo for () {
+	fn on_initialize(p: u32, ) -> Weight {
+		Weight::from_parts(3_000_000, 0)
+			.saturating_add(Weight::from_parts(2_000_000, 0).saturating_mul(p.into()))
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().reads((3_u64).saturating_mul(p.into())))
+			.saturating_add(RocksDbWeight::get().writes((1_u64).saturating_mul(p.into())))
+			.saturating_add(RocksDbWeight::get().reads_writes(2_u64, 3_u64))
+	}
+	fn end_session(n: u32, ) -> Weight {
+		Weight::from_parts(20_000_000, 0)
+			.saturating_add(Weight::from_parts(5_000_000, 0).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().reads((2_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(5_u64))
+			.saturating_add(RocksDbWeight::get().writes((1_u64).saturating_mul(n.into())))
+	}
+	fn verify_pulse(n: u32, ) -> Weight {
+		Weight::from_parts(600_000_000, 0)
+			.saturating_add(Weight::from_parts(600_000_000, 0).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(9_u64))
+	}
+}
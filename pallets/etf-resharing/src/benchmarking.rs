@@ -0,0 +1,146 @@
+//! Benchmarks for the ETF resharing pallet.
+
+use super::*;
+use crate::Pallet as EtfResharing;
+
+use frame_benchmarking::v2::*;
+use frame_support::traits::Get;
+use frame_system::RawOrigin;
+
+/// `count` new authorities.
+fn authorities<T: Config>(count: u32) -> Vec<T::AuthorityId> {
+	(0..count).map(|_| T::AuthorityId::generate_pair(None)).collect()
+}
+
+/// The encrypted shares of a dealing.
+fn encrypted<T: Config>(shares: Vec<Vec<u8>>) -> Vec<BoundedVec<u8, T::MaxShareLen>> {
+	shares.into_iter().map(BoundedVec::truncate_from).collect()
+}
+
+fn assert_last_event<T: Config>(event: <T as Config>::RuntimeEvent) {
+	frame_system::Pallet::<T>::assert_last_event(event.into());
+}
+
+#[benchmarks]
+mod benchmarks {
+	use super::*;
+
+	#[benchmark]
+	fn submit_dealing(n: Linear<1, { T::MaxAuthorities::get() }>) -> Result<(), BenchmarkError> {
+		// A single outgoing authority deals its share to `n` incoming ones.
+		let dealer = T::AuthorityId::generate_pair(None);
+		let incoming = authorities::<T>(n);
+		EtfResharing::<T>::on_new_validator_set(&[dealer.clone()], &incoming, 1);
+		let threshold = EtfResharing::<T>::threshold_for(n);
+		let (share, commitments, shares) = T::BenchmarkHelper::dealing(&incoming, threshold);
+		T::Shares::set_shares(vec![share], vec![vec![]]);
+
+		let dealing = Dealing::<T> {
+			set_id: 1,
+			dealer: 0,
+			commitments: BoundedVec::truncate_from(commitments),
+			shares: BoundedVec::truncate_from(encrypted::<T>(shares)),
+		};
+		let signature = dealer
+			.sign(&dealing.encode())
+			.ok_or(BenchmarkError::Stop("The dealer cannot sign"))?;
+
+		#[extrinsic_call]
+		_(RawOrigin::None, dealing, signature);
+
+		assert!(Dealings::<T>::contains_key(0));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn submit_share(t: Linear<1, { T::MaxAuthorities::get() }>) -> Result<(), BenchmarkError> {
+		// The commitment to the new share is interpolated from the dealings of `t` dealers,
+		// which all dealt the same evaluation.
+		let authority = T::AuthorityId::generate_pair(None);
+		EtfResharing::<T>::on_new_validator_set(&[], &[authority.clone()], 1);
+		let (commitment, _, mut shares) = T::BenchmarkHelper::dealing(&[authority.clone()], 1);
+		T::Shares::set_shares(vec![commitment.clone(); t as usize], vec![vec![]; t as usize]);
+		Threshold::<T>::put(t);
+		for dealer in 0..t {
+			Dealings::<T>::insert(dealer, BoundedVec::truncate_from(vec![commitment.clone()]));
+		}
+
+		let share = NewShare::<T> {
+			set_id: 1,
+			authority: 0,
+			share: BoundedVec::truncate_from(shares.pop().unwrap_or_default()),
+		};
+		let signature = authority
+			.sign(&share.encode())
+			.ok_or(BenchmarkError::Stop("The authority cannot sign"))?;
+
+		#[extrinsic_call]
+		_(RawOrigin::None, share, signature);
+
+		assert!(NewShares::<T>::contains_key(0));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn rotate(
+		n: Linear<1, { T::MaxAuthorities::get() }>,
+		m: Linear<0, { T::MaxAuthorities::get() }>,
+	) {
+		// `n` incoming authorities take over from as many outgoing ones, a threshold of which
+		// dealt, and `m` of the incoming ones did not submit their new share.
+		let m = m.min(n);
+		let incoming = authorities::<T>(n);
+		let threshold = EtfResharing::<T>::threshold_for(n);
+		let (_, commitments, shares) = T::BenchmarkHelper::dealing(&incoming, threshold);
+		T::Shares::set_shares(commitments.clone(), vec![vec![]; n as usize]);
+		EtfResharing::<T>::on_new_validator_set(&[], &incoming, 1);
+		for dealer in 0..threshold {
+			Dealings::<T>::insert(dealer, BoundedVec::truncate_from(commitments.clone()));
+		}
+		let shares = encrypted::<T>(shares);
+		for authority in m..n {
+			let commitment = commitments[authority as usize].clone();
+			NewShares::<T>::insert(authority, (commitment, shares[authority as usize].clone()));
+		}
+
+		#[block]
+		{
+			EtfResharing::<T>::on_new_validator_set(&incoming, &incoming, 2);
+		}
+
+		assert_last_event::<T>(Event::SharesRotated { set_id: 1, submitted: n - m }.into());
+	}
+
+	#[benchmark]
+	fn submit_complaint(
+		n: Linear<1, { T::MaxAuthorities::get() }>,
+	) -> Result<(), BenchmarkError> {
+		// An incoming authority rejects a dealing to `n` incoming authorities, all others of
+		// which already submitted their new share.
+		let incoming = authorities::<T>(n);
+		EtfResharing::<T>::on_new_validator_set(&[], &incoming, 1);
+		let (_, commitments, shares) = T::BenchmarkHelper::dealing(&incoming, 1);
+		let shares = encrypted::<T>(shares);
+		for (authority, share) in shares.iter().enumerate() {
+			DealtShares::<T>::insert(authority as u32, 0, share.clone());
+		}
+		Dealings::<T>::insert(0, BoundedVec::truncate_from(commitments.clone()));
+		for authority in 1..n {
+			let commitment = commitments[authority as usize].clone();
+			NewShares::<T>::insert(authority, (commitment, shares[authority as usize].clone()));
+		}
+
+		let complaint = Complaint { set_id: 1, authority: 0, dealer: 0 };
+		let signature = incoming[0]
+			.sign(&complaint.encode())
+			.ok_or(BenchmarkError::Stop("The authority cannot sign"))?;
+
+		#[extrinsic_call]
+		_(RawOrigin::None, complaint, signature);
+
+		assert!(Rejected::<T>::contains_key(0));
+		Ok(())
+	}
+
+	impl_benchmark_test_suite!(EtfResharing, crate::mock::new_test_ext(), crate::mock::Test);
+}
@@ -0,0 +1,713 @@
+//! # ETF Resharing Pallet
+//!
+//! Hands the shares of the ETF round secret over from one BEEFY-ETF authority set to the next
+//! without changing the round public key.
+//!
+//! The genesis ceremony deals the shares once, while the authority set changes with every
+//! election. Whenever the authority set queued by BEEFY differs from the current one, a
+//! resharing round opens, see [`Pallet::on_new_validator_set`]:
+//!
+//! 1. The outgoing authorities deal their shares to the incoming set with
+//!    [`Call::submit_dealing`]. A dealer picks a random polynomial whose constant term is its
+//!    share and publishes its evaluation for every incoming authority, encrypted to that
+//!    authority, along with a commitment to each evaluation. The commitments are checked
+//!    against the commitment to the dealer's share, so a dealer can only deal the share it
+//!    holds, and every encrypted evaluation has to prove that it is addressed to its recipient
+//!    and matches its commitment.
+//! 2. Once a threshold of the outgoing authorities have dealt, the round is qualified. Every
+//!    incoming authority decrypts the evaluations dealt to it, see [`Pallet::dealt_shares`],
+//!    and interpolates them into its new share. It encrypts the new share to itself, in the
+//!    format of the genesis resharings, and submits it with [`Call::submit_share`]. The
+//!    encrypted share has to match the commitment interpolated from the dealings.
+//!
+//!    An incoming authority whose evaluation does not decrypt to its commitment rejects the
+//!    dealing with [`Call::submit_complaint`] instead. The dealer cannot deal again in the
+//!    round and another outgoing authority takes its place. The chain cannot tell whether the
+//!    dealer or the complaining authority is dishonest, so every incoming authority can only
+//!    reject a single dealing per round.
+//! 3. When the incoming set becomes the current one, the new shares and the commitments to
+//!    them replace the old ones, see [`Config::Shares`].
+//!
+//! The dealt polynomials interpolate at zero to shares of the round secret, so the new shares
+//! are shares of the same secret and the round public key stays constant. A round that is not
+//! qualified by the time the incoming set becomes current, or in which fewer than a threshold
+//! of the incoming authorities submitted their new shares, fails and leaves the shares as they
+//! are.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+pub mod weights;
+pub use weights::WeightInfo;
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::{BoundedVec, CloneNoBound, EqNoBound, PartialEqNoBound, RuntimeDebugNoBound};
+use scale_info::TypeInfo;
+use sp_runtime::{RuntimeAppPublic, RuntimeDebug};
+use sp_std::prelude::*;
+
+/// The identifier of a BEEFY authority set.
+pub type SetId = u64;
+
+/// The signature of an authority.
+pub type AuthoritySignature<T> = <<T as Config>::AuthorityId as RuntimeAppPublic>::Signature;
+
+/// The shares of the round secret held by the current authorities.
+pub trait EtfShares<AuthorityId> {
+	/// The commitments to the shares, indexed like the current authority set.
+	fn commitments() -> Vec<AuthorityId>;
+
+	/// The shares, each encrypted to its authority and indexed like the current authority set.
+	fn shares() -> Vec<Vec<u8>>;
+
+	/// Replace the shares and the commitments to them, both indexed like the new authority set.
+	fn set_shares(commitments: Vec<AuthorityId>, shares: Vec<Vec<u8>>);
+}
+
+/// The cryptography of the resharing.
+///
+/// A commitment to a share is the public key of the share. The share of the authority at index
+/// `i` is the evaluation of the shared polynomial at `i + 1`.
+pub trait ResharingCrypto<AuthorityId> {
+	/// Whether `commitments` commit to the evaluations of a polynomial of degree less than
+	/// `threshold` at the indices of the incoming authorities, whose constant term is the share
+	/// committed to by `dealer`.
+	fn verify_dealing(dealer: &AuthorityId, commitments: &[AuthorityId], threshold: u32) -> bool;
+
+	/// Whether `share` proves that it encrypts to `recipient` the share committed to by
+	/// `commitment`.
+	fn verify_share(recipient: &AuthorityId, commitment: &AuthorityId, share: &[u8]) -> bool;
+
+	/// Interpolate at zero the commitments dealt to an authority, each paired with the index of
+	/// its dealer in the outgoing authority set.
+	fn interpolate(dealt: &[(u32, AuthorityId)]) -> Option<AuthorityId>;
+}
+
+/// Produces valid dealings for the benchmarks of this pallet.
+#[cfg(feature = "runtime-benchmarks")]
+pub trait BenchmarkHelper<AuthorityId> {
+	/// The commitment to a share, along with the commitments to the evaluations of a polynomial
+	/// of degree less than `threshold` whose constant term is that share, at the indices of the
+	/// `incoming` authorities, and the evaluations encrypted to them.
+	fn dealing(
+		incoming: &[AuthorityId],
+		threshold: u32,
+	) -> (AuthorityId, Vec<AuthorityId>, Vec<Vec<u8>>);
+}
+
+/// The default number of shares needed to recover the round secret, i.e. a two thirds majority
+/// `⌊2n/3⌋ + 1` of the `n` authorities, capped to `n - 1` so that the beacon keeps going while
+/// one of them is offline.
+///
+/// The cap only lowers the threshold of sets of up to three authorities, like those of the
+/// development testnets; a single authority needs its own share. The genesis ceremony of the
+/// node and every resharing round use this same default.
+pub fn default_threshold(authorities: u32) -> u32 {
+	(authorities * 2 / 3 + 1).min(authorities.saturating_sub(1)).max(1)
+}
+
+/// An open resharing round.
+#[derive(
+	CloneNoBound,
+	PartialEqNoBound,
+	EqNoBound,
+	Encode,
+	Decode,
+	MaxEncodedLen,
+	TypeInfo,
+	RuntimeDebugNoBound,
+)]
+#[scale_info(skip_type_params(T))]
+#[codec(mel_bound())]
+pub struct ResharingRound<T: Config> {
+	/// The id of the incoming authority set.
+	pub set_id: SetId,
+	/// The authorities dealing their shares, indexed like their shares.
+	pub outgoing: BoundedVec<T::AuthorityId, T::MaxAuthorities>,
+	/// The authorities the shares are dealt to.
+	pub incoming: BoundedVec<T::AuthorityId, T::MaxAuthorities>,
+	/// The number of new shares needed to recover the round secret.
+	pub threshold: u32,
+}
+
+/// The share of an outgoing authority, dealt to the incoming authorities.
+#[derive(CloneNoBound, PartialEqNoBound, EqNoBound, Encode, Decode, TypeInfo, RuntimeDebugNoBound)]
+#[scale_info(skip_type_params(T))]
+pub struct Dealing<T: Config> {
+	/// The id of the incoming authority set.
+	pub set_id: SetId,
+	/// The index of the dealer in the outgoing authority set.
+	pub dealer: u32,
+	/// The commitment to the evaluation dealt to each incoming authority.
+	pub commitments: BoundedVec<T::AuthorityId, T::MaxAuthorities>,
+	/// The evaluation dealt to each incoming authority, encrypted to it.
+	pub shares: BoundedVec<BoundedVec<u8, T::MaxShareLen>, T::MaxAuthorities>,
+}
+
+/// The new share of an incoming authority, encrypted to itself.
+#[derive(CloneNoBound, PartialEqNoBound, EqNoBound, Encode, Decode, TypeInfo, RuntimeDebugNoBound)]
+#[scale_info(skip_type_params(T))]
+pub struct NewShare<T: Config> {
+	/// The id of the incoming authority set.
+	pub set_id: SetId,
+	/// The index of the authority in the incoming authority set.
+	pub authority: u32,
+	/// The encrypted share.
+	pub share: BoundedVec<u8, T::MaxShareLen>,
+}
+
+/// The complaint of an incoming authority about the evaluation a dealer dealt to it.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, MaxEncodedLen, TypeInfo, RuntimeDebug)]
+pub struct Complaint {
+	/// The id of the incoming authority set.
+	pub set_id: SetId,
+	/// The index of the complaining authority in the incoming authority set.
+	pub authority: u32,
+	/// The index of the dealer in the outgoing authority set.
+	pub dealer: u32,
+}
+
+/// The state of an open resharing round, as followed by the authorities taking part in it.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, TypeInfo, RuntimeDebug)]
+pub struct RoundStatus<AuthorityId> {
+	/// The id of the incoming authority set.
+	pub set_id: SetId,
+	/// The authorities dealing their shares, indexed like their shares.
+	pub outgoing: Vec<AuthorityId>,
+	/// The authorities the shares are dealt to.
+	pub incoming: Vec<AuthorityId>,
+	/// The number of new shares needed to recover the round secret.
+	pub threshold: u32,
+	/// The outgoing authorities whose dealing was accepted.
+	pub dealt: Vec<u32>,
+	/// The outgoing authorities whose dealing was rejected.
+	pub rejected: Vec<u32>,
+	/// Whether enough shares were dealt for the incoming authorities to recover their new
+	/// shares.
+	pub qualified: bool,
+	/// The incoming authorities that submitted their new share.
+	pub submitted: Vec<u32>,
+	/// The incoming authorities that rejected a dealing.
+	pub complained: Vec<u32>,
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The public key of an authority, which is also the format of a commitment to a share.
+		type AuthorityId: Member
+			+ Parameter
+			+ RuntimeAppPublic
+			+ MaybeSerializeDeserialize
+			+ MaxEncodedLen;
+
+		/// The shares of the round secret.
+		type Shares: EtfShares<Self::AuthorityId>;
+
+		/// The cryptography of the resharing.
+		type Crypto: ResharingCrypto<Self::AuthorityId>;
+
+		/// The maximum number of authorities in a set.
+		#[pallet::constant]
+		type MaxAuthorities: Get<u32>;
+
+		/// The maximum length of an encrypted share.
+		#[pallet::constant]
+		type MaxShareLen: Get<u32>;
+
+		/// The priority of dealings and new shares in the transaction pool.
+		#[pallet::constant]
+		type UnsignedPriority: Get<TransactionPriority>;
+
+		/// Weight information for extrinsics in this pallet.
+		type WeightInfo: WeightInfo;
+
+		/// Produces valid commitments for the benchmarks.
+		#[cfg(feature = "runtime-benchmarks")]
+		type BenchmarkHelper: BenchmarkHelper<Self::AuthorityId>;
+	}
+
+	/// The configured number of shares needed to recover the round secret, carried over to
+	/// every authority set.
+	///
+	/// Zero means the [`default_threshold`] for the size of each set, see
+	/// [`Pallet::threshold`].
+	#[pallet::storage]
+	pub type Threshold<T> = StorageValue<_, u32, ValueQuery>;
+
+	/// The open resharing round.
+	#[pallet::storage]
+	pub type Round<T: Config> = StorageValue<_, ResharingRound<T>>;
+
+	/// The commitments dealt by each outgoing authority in the open round.
+	#[pallet::storage]
+	pub type Dealings<T: Config> =
+		CountedStorageMap<_, Twox64Concat, u32, BoundedVec<T::AuthorityId, T::MaxAuthorities>>;
+
+	/// The encrypted evaluations dealt in the open round, by incoming authority and dealer.
+	#[pallet::storage]
+	pub type DealtShares<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		u32,
+		Twox64Concat,
+		u32,
+		BoundedVec<u8, T::MaxShareLen>,
+	>;
+
+	/// The new shares submitted in the open round, along with the commitments to them.
+	#[pallet::storage]
+	pub type NewShares<T: Config> =
+		StorageMap<_, Twox64Concat, u32, (T::AuthorityId, BoundedVec<u8, T::MaxShareLen>)>;
+
+	/// The dealer each incoming authority rejected in the open round.
+	#[pallet::storage]
+	pub type Complaints<T> = StorageMap<_, Twox64Concat, u32, u32>;
+
+	/// The outgoing authorities whose dealing was rejected in the open round.
+	#[pallet::storage]
+	pub type Rejected<T> = StorageMap<_, Twox64Concat, u32, ()>;
+
+	#[pallet::genesis_config]
+	#[derive(frame_support::DefaultNoBound)]
+	pub struct GenesisConfig<T: Config> {
+		/// The number of shares needed to recover the round secret, for the genesis authorities
+		/// and every later set. Zero means the [`default_threshold`] for the size of each set.
+		pub threshold: u32,
+		#[serde(skip)]
+		pub _config: sp_std::marker::PhantomData<T>,
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> BuildGenesisConfig for GenesisConfig<T> {
+		fn build(&self) {
+			Threshold::<T>::put(self.threshold);
+		}
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The shares are being dealt to the authority set `set_id`.
+		ResharingStarted { set_id: SetId },
+		/// An outgoing authority dealt its share.
+		Dealt { set_id: SetId, dealer: u32 },
+		/// Enough shares were dealt for the incoming authorities to recover their new shares.
+		Qualified { set_id: SetId },
+		/// An incoming authority submitted its new share.
+		ShareSubmitted { set_id: SetId, authority: u32 },
+		/// An incoming authority rejected the dealing of `dealer`.
+		DealingRejected { set_id: SetId, dealer: u32, authority: u32 },
+		/// The authority set `set_id` took over the shares, `submitted` of which were submitted.
+		SharesRotated { set_id: SetId, submitted: u32 },
+		/// The round was not qualified in time, or fewer than a threshold of the incoming
+		/// authorities submitted their new shares, so the shares were left as they are.
+		ResharingFailed { set_id: SetId },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// There is no open resharing round for the authority set.
+		NoRound,
+		/// There is no authority at the index.
+		UnknownAuthority,
+		/// The signature is not the authority's.
+		BadSignature,
+		/// The authority already dealt its share.
+		AlreadyDealt,
+		/// Enough shares were already dealt.
+		AlreadyQualified,
+		/// The dealing does not have one share for every incoming authority.
+		WrongLength,
+		/// The commitments are inconsistent with each other or with the dealer's share.
+		InvalidDealing,
+		/// An encrypted share does not prove that it matches its commitment and recipient.
+		InvalidShare,
+		/// Not enough shares were dealt yet to recover the new shares.
+		NotQualified,
+		/// The authority already submitted its new share.
+		AlreadySubmitted,
+		/// The dealing of the authority was rejected.
+		Rejected,
+		/// The authority did not deal its share.
+		NotDealt,
+		/// The authority already rejected a dealing.
+		AlreadyComplained,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		#[cfg(feature = "try-runtime")]
+		fn try_state(_n: BlockNumberFor<T>) -> Result<(), sp_runtime::TryRuntimeError> {
+			ensure!(
+				T::Shares::shares().len() == T::Shares::commitments().len(),
+				"the shares and the commitments to them differ in number"
+			);
+			Ok(())
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Deal the share of an outgoing authority to the incoming authorities.
+		///
+		/// `signature` is the dealer's signature of the SCALE encoded `dealing`.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::WeightInfo::submit_dealing(dealing.commitments.len() as u32))]
+		pub fn submit_dealing(
+			origin: OriginFor<T>,
+			dealing: Dealing<T>,
+			signature: AuthoritySignature<T>,
+		) -> DispatchResult {
+			ensure_none(origin)?;
+			Self::check_dealing(&dealing, &signature)?;
+
+			let Dealing { set_id, dealer, commitments, shares } = dealing;
+			for (authority, share) in shares.into_iter().enumerate() {
+				DealtShares::<T>::insert(authority as u32, dealer, share);
+			}
+			Dealings::<T>::insert(dealer, commitments);
+			Self::deposit_event(Event::Dealt { set_id, dealer });
+			if Dealings::<T>::count() == Self::threshold() {
+				Self::deposit_event(Event::Qualified { set_id });
+			}
+			Ok(())
+		}
+
+		/// Submit the new share of an incoming authority, encrypted to itself.
+		///
+		/// `signature` is the authority's signature of the SCALE encoded `share`.
+		#[pallet::call_index(1)]
+		#[pallet::weight(T::WeightInfo::submit_share(T::MaxAuthorities::get()))]
+		pub fn submit_share(
+			origin: OriginFor<T>,
+			share: NewShare<T>,
+			signature: AuthoritySignature<T>,
+		) -> DispatchResult {
+			ensure_none(origin)?;
+			let commitment = Self::check_share(&share, &signature)?;
+
+			let NewShare { set_id, authority, share } = share;
+			NewShares::<T>::insert(authority, (commitment, share));
+			Self::deposit_event(Event::ShareSubmitted { set_id, authority });
+			Ok(())
+		}
+
+		/// Reject the dealing of an outgoing authority whose evaluation dealt to an incoming
+		/// authority does not decrypt to the commitment to it.
+		///
+		/// The dealer cannot deal again in the round, and the new shares submitted so far have
+		/// to be submitted again without its dealing. `signature` is the incoming authority's
+		/// signature of the SCALE encoded `complaint`.
+		#[pallet::call_index(2)]
+		#[pallet::weight(T::WeightInfo::submit_complaint(T::MaxAuthorities::get()))]
+		pub fn submit_complaint(
+			origin: OriginFor<T>,
+			complaint: Complaint,
+			signature: AuthoritySignature<T>,
+		) -> DispatchResult {
+			ensure_none(origin)?;
+			Self::check_complaint(&complaint, &signature)?;
+
+			let Complaint { set_id, authority, dealer } = complaint;
+			let incoming = Round::<T>::get().map_or(0, |round| round.incoming.len() as u32);
+			for recipient in 0..incoming {
+				DealtShares::<T>::remove(recipient, dealer);
+			}
+			Dealings::<T>::remove(dealer);
+			Rejected::<T>::insert(dealer, ());
+			Complaints::<T>::insert(authority, dealer);
+			// The new shares submitted so far were interpolated from the rejected dealing.
+			let _ = NewShares::<T>::clear(u32::MAX, None);
+			Self::deposit_event(Event::DealingRejected { set_id, dealer, authority });
+			Ok(())
+		}
+	}
+
+	#[pallet::validate_unsigned]
+	impl<T: Config> ValidateUnsigned for Pallet<T> {
+		type Call = Call<T>;
+
+		fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+			let (result, provides) = match call {
+				Call::submit_dealing { dealing, signature } => (
+					Self::check_dealing(dealing, signature),
+					(dealing.set_id, "dealing", dealing.dealer),
+				),
+				Call::submit_share { share, signature } => (
+					Self::check_share(share, signature).map(drop),
+					(share.set_id, "share", share.authority),
+				),
+				Call::submit_complaint { complaint, signature } => (
+					Self::check_complaint(complaint, signature),
+					(complaint.set_id, "complaint", complaint.authority),
+				),
+				_ => return InvalidTransaction::Call.into(),
+			};
+			result.map_err(|e| match e {
+				Error::<T>::BadSignature => InvalidTransaction::BadProof,
+				Error::<T>::NotQualified => InvalidTransaction::Future,
+				Error::<T>::UnknownAuthority |
+				Error::<T>::WrongLength |
+				Error::<T>::InvalidDealing |
+				Error::<T>::InvalidShare => InvalidTransaction::Call,
+				_ => InvalidTransaction::Stale,
+			})?;
+
+			ValidTransaction::with_tag_prefix("EtfResharing")
+				.priority(T::UnsignedPriority::get())
+				.and_provides(provides)
+				.propagate(true)
+				.build()
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Follow a change of the BEEFY authority set: `current` is the set that is now active
+		/// and `next` the set queued to follow it, with id `next_id`.
+		///
+		/// If the incoming set of the open round is now active it takes over the new shares.
+		/// A new round opens whenever `next` differs from `current`.
+		pub fn on_new_validator_set(
+			current: &[T::AuthorityId],
+			next: &[T::AuthorityId],
+			next_id: SetId,
+		) {
+			if let Some(round) = Round::<T>::take() {
+				if round.incoming.as_slice() == current {
+					Self::rotate(round);
+				} else {
+					Self::deposit_event(Event::ResharingFailed { set_id: round.set_id });
+				}
+				Self::clear();
+			}
+
+			if current != next {
+				let (Ok(outgoing), Ok(incoming)) =
+					(current.to_vec().try_into(), next.to_vec().try_into())
+				else {
+					return
+				};
+				let threshold = Self::threshold_for(next.len() as u32);
+				Round::<T>::put(ResharingRound { set_id: next_id, outgoing, incoming, threshold });
+				Self::deposit_event(Event::ResharingStarted { set_id: next_id });
+			}
+		}
+
+		/// The number of shares needed to recover the round secret.
+		pub fn threshold() -> u32 {
+			Self::threshold_for(T::Shares::commitments().len() as u32)
+		}
+
+		/// The number of shares out of `authorities` needed to recover the round secret: the
+		/// configured [`Threshold`], at most `authorities`, or the default for `authorities`.
+		pub fn threshold_for(authorities: u32) -> u32 {
+			match Threshold::<T>::get() {
+				0 => default_threshold(authorities),
+				threshold => threshold.min(authorities),
+			}
+		}
+
+		/// The encrypted evaluations dealt to the incoming authority at index `authority` in
+		/// the open round, each paired with the index of its dealer and the commitment to it.
+		pub fn dealt_shares(authority: u32) -> Vec<(u32, T::AuthorityId, Vec<u8>)> {
+			DealtShares::<T>::iter_prefix(authority)
+				.filter_map(|(dealer, share)| {
+					let commitment = Dealings::<T>::get(dealer)?.get(authority as usize)?.clone();
+					Some((dealer, commitment, share.into_inner()))
+				})
+				.collect()
+		}
+
+		/// The state of the open round, if any.
+		pub fn status() -> Option<RoundStatus<T::AuthorityId>> {
+			let round = Round::<T>::get()?;
+			let sorted = |mut indices: Vec<u32>| {
+				indices.sort();
+				indices
+			};
+			Some(RoundStatus {
+				set_id: round.set_id,
+				outgoing: round.outgoing.into_inner(),
+				incoming: round.incoming.into_inner(),
+				threshold: round.threshold,
+				dealt: sorted(Dealings::<T>::iter_keys().collect()),
+				rejected: sorted(Rejected::<T>::iter_keys().collect()),
+				qualified: Dealings::<T>::count() >= Self::threshold(),
+				submitted: sorted(NewShares::<T>::iter_keys().collect()),
+				complained: sorted(Complaints::<T>::iter_keys().collect()),
+			})
+		}
+
+		fn check_dealing(
+			dealing: &Dealing<T>,
+			signature: &AuthoritySignature<T>,
+		) -> Result<(), Error<T>> {
+			let round = Round::<T>::get()
+				.filter(|round| round.set_id == dealing.set_id)
+				.ok_or(Error::<T>::NoRound)?;
+			let dealer =
+				round.outgoing.get(dealing.dealer as usize).ok_or(Error::<T>::UnknownAuthority)?;
+			ensure!(!Dealings::<T>::contains_key(dealing.dealer), Error::<T>::AlreadyDealt);
+			ensure!(!Rejected::<T>::contains_key(dealing.dealer), Error::<T>::Rejected);
+			ensure!(Dealings::<T>::count() < Self::threshold(), Error::<T>::AlreadyQualified);
+			ensure!(
+				dealing.commitments.len() == round.incoming.len() &&
+					dealing.shares.len() == round.incoming.len(),
+				Error::<T>::WrongLength
+			);
+			ensure!(dealer.verify(&dealing.encode(), signature), Error::<T>::BadSignature);
+
+			let share = T::Shares::commitments()
+				.get(dealing.dealer as usize)
+				.cloned()
+				.ok_or(Error::<T>::InvalidDealing)?;
+			ensure!(
+				T::Crypto::verify_dealing(&share, &dealing.commitments, round.threshold),
+				Error::<T>::InvalidDealing
+			);
+			let mut recipients =
+				round.incoming.iter().zip(&dealing.commitments).zip(&dealing.shares);
+			ensure!(
+				recipients.all(|((recipient, commitment), share)| {
+					T::Crypto::verify_share(recipient, commitment, share)
+				}),
+				Error::<T>::InvalidShare
+			);
+			Ok(())
+		}
+
+		/// Check a new share, returning the commitment to it.
+		fn check_share(
+			share: &NewShare<T>,
+			signature: &AuthoritySignature<T>,
+		) -> Result<T::AuthorityId, Error<T>> {
+			let round = Round::<T>::get()
+				.filter(|round| round.set_id == share.set_id)
+				.ok_or(Error::<T>::NoRound)?;
+			let authority =
+				round.incoming.get(share.authority as usize).ok_or(Error::<T>::UnknownAuthority)?;
+			ensure!(Dealings::<T>::count() >= Self::threshold(), Error::<T>::NotQualified);
+			ensure!(!NewShares::<T>::contains_key(share.authority), Error::<T>::AlreadySubmitted);
+			ensure!(authority.verify(&share.encode(), signature), Error::<T>::BadSignature);
+
+			let commitment =
+				Self::dealt_commitment(share.authority).ok_or(Error::<T>::InvalidDealing)?;
+			ensure!(
+				T::Crypto::verify_share(authority, &commitment, &share.share),
+				Error::<T>::InvalidShare
+			);
+			Ok(commitment)
+		}
+
+		fn check_complaint(
+			complaint: &Complaint,
+			signature: &AuthoritySignature<T>,
+		) -> Result<(), Error<T>> {
+			let round = Round::<T>::get()
+				.filter(|round| round.set_id == complaint.set_id)
+				.ok_or(Error::<T>::NoRound)?;
+			let authority = round
+				.incoming
+				.get(complaint.authority as usize)
+				.ok_or(Error::<T>::UnknownAuthority)?;
+			ensure!(!Rejected::<T>::contains_key(complaint.dealer), Error::<T>::Rejected);
+			ensure!(Dealings::<T>::contains_key(complaint.dealer), Error::<T>::NotDealt);
+			ensure!(
+				!Complaints::<T>::contains_key(complaint.authority),
+				Error::<T>::AlreadyComplained
+			);
+			// Submitting a new share accepts the dealings it was interpolated from.
+			ensure!(
+				!NewShares::<T>::contains_key(complaint.authority),
+				Error::<T>::AlreadySubmitted
+			);
+			ensure!(authority.verify(&complaint.encode(), signature), Error::<T>::BadSignature);
+			Ok(())
+		}
+
+		/// The commitment to the new share of the incoming authority at index `authority`,
+		/// interpolated from the dealings of the open round.
+		pub fn dealt_commitment(authority: u32) -> Option<T::AuthorityId> {
+			let dealt = Dealings::<T>::iter()
+				.filter_map(|(dealer, commitments)| {
+					Some((dealer, commitments.get(authority as usize)?.clone()))
+				})
+				.collect::<Vec<_>>();
+			T::Crypto::interpolate(&dealt)
+		}
+
+		/// Hand the shares over to the incoming set of `round`, which just became active.
+		fn rotate(round: ResharingRound<T>) {
+			let set_id = round.set_id;
+			if Dealings::<T>::count() < Self::threshold() {
+				Self::deposit_event(Event::ResharingFailed { set_id });
+				return
+			}
+
+			let authorities = round.incoming.len() as u32;
+			let mut commitments = Vec::with_capacity(round.incoming.len());
+			let mut shares = Vec::with_capacity(round.incoming.len());
+			let mut submitted = 0;
+			for authority in 0..authorities {
+				// Authorities that did not submit their share cannot sign until the next
+				// resharing, but their commitments still have to be in place.
+				let (commitment, share) = match NewShares::<T>::get(authority) {
+					Some((commitment, share)) => {
+						submitted += 1;
+						(commitment, share.into_inner())
+					},
+					None => match Self::dealt_commitment(authority) {
+						Some(commitment) => (commitment, Vec::new()),
+						None => {
+							Self::deposit_event(Event::ResharingFailed { set_id });
+							return
+						},
+					},
+				};
+				commitments.push(commitment);
+				shares.push(share);
+			}
+			// The incoming set could not sign with fewer shares, while the outgoing one still
+			// can with the current shares.
+			if submitted < round.threshold {
+				Self::deposit_event(Event::ResharingFailed { set_id });
+				return
+			}
+
+			frame_system::Pallet::<T>::register_extra_weight_unchecked(
+				T::WeightInfo::rotate(authorities, authorities - submitted),
+				DispatchClass::Mandatory,
+			);
+			T::Shares::set_shares(commitments, shares);
+			Self::deposit_event(Event::SharesRotated { set_id, submitted });
+		}
+
+		fn clear() {
+			let _ = Dealings::<T>::clear(u32::MAX, None);
+			let _ = DealtShares::<T>::clear(u32::MAX, None);
+			let _ = NewShares::<T>::clear(u32::MAX, None);
+			let _ = Complaints::<T>::clear(u32::MAX, None);
+			let _ = Rejected::<T>::clear(u32::MAX, None);
+		}
+	}
+}
@@ -0,0 +1,227 @@
+//! Test environment for the ETF resharing pallet.
+//!
+//! Shares are plain integers modulo a small prime and the commitment to a share is the share
+//! itself, which is enough to check the bookkeeping of the pallet. A share is encrypted to an
+//! authority by pairing it with the authority's key.
+
+use super::*;
+use crate as pallet_etf_resharing;
+
+use frame_support::{derive_impl, traits::ConstU32};
+use sp_runtime::{
+	testing::{TestSignature, UintAuthorityId},
+	BuildStorage,
+};
+use std::cell::RefCell;
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test {
+		System: frame_system,
+		EtfResharing: pallet_etf_resharing,
+	}
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+impl frame_system::Config for Test {
+	type Block = Block;
+}
+
+/// The modulus of the field the shares live in.
+const P: u64 = 65_537;
+
+/// The coefficients of the polynomial of the genesis shares, the round secret first.
+pub const SECRET: [u64; 3] = [42, 7, 3];
+
+thread_local! {
+	static COMMITMENTS: RefCell<Vec<UintAuthorityId>> = RefCell::new(Vec::new());
+	static SHARES: RefCell<Vec<Vec<u8>>> = RefCell::new(Vec::new());
+}
+
+/// The commitments to the current shares.
+pub fn commitments() -> Vec<UintAuthorityId> {
+	COMMITMENTS.with(|commitments| commitments.borrow().clone())
+}
+
+/// The current encrypted shares.
+pub fn shares() -> Vec<Vec<u8>> {
+	SHARES.with(|shares| shares.borrow().clone())
+}
+
+fn pow(base: u64, exponent: u64) -> u64 {
+	(0..64).rev().fold(1, |acc, bit| {
+		let acc = acc * acc % P;
+		if (exponent >> bit) & 1 == 1 {
+			acc * base % P
+		} else {
+			acc
+		}
+	})
+}
+
+/// The Lagrange coefficient of the point at `xj` among `xs` when interpolating at `x`.
+fn lagrange(x: u64, xj: u64, xs: &[u64]) -> u64 {
+	xs.iter().filter(|&&xm| xm != xj).fold(1, |acc, &xm| {
+		acc * ((x + P - xm) % P) % P * pow((xj + P - xm) % P, P - 2) % P
+	})
+}
+
+/// Evaluate the polynomial with `coefficients` at `x`.
+pub fn evaluate(coefficients: &[u64], x: u64) -> u64 {
+	coefficients.iter().rev().fold(0, |acc, coefficient| (acc * x + coefficient) % P)
+}
+
+/// The genesis share of the authority at `index`.
+pub fn genesis_share(index: u32) -> u64 {
+	evaluate(&SECRET, index as u64 + 1)
+}
+
+/// The outgoing authorities.
+pub fn outgoing() -> Vec<UintAuthorityId> {
+	vec![11.into(), 12.into(), 13.into()]
+}
+
+/// The incoming authorities.
+pub fn incoming() -> Vec<UintAuthorityId> {
+	vec![21.into(), 22.into(), 23.into(), 24.into()]
+}
+
+/// The share `value` encrypted to `recipient`.
+pub fn encrypt(recipient: &UintAuthorityId, value: u64) -> Vec<u8> {
+	(recipient.0, value).encode()
+}
+
+/// Sign the SCALE encoding of `message` with the key of `signer`.
+pub fn sign<M: Encode>(signer: &UintAuthorityId, message: &M) -> TestSignature {
+	TestSignature(signer.0, message.encode())
+}
+
+/// The polynomial an outgoing authority deals its share on, of degree less than `threshold`.
+pub fn dealt_polynomial(dealer: u32, threshold: u32) -> Vec<u64> {
+	let mut coefficients = vec![genesis_share(dealer)];
+	coefficients.extend((1..threshold as u64).map(|k| dealer as u64 * 10 + k));
+	coefficients
+}
+
+/// The dealing of the outgoing authority at `dealer` to the incoming authorities.
+pub fn dealing(set_id: SetId, dealer: u32, threshold: u32) -> Dealing<Test> {
+	let coefficients = dealt_polynomial(dealer, threshold);
+	let commitments = (1..=incoming().len() as u64)
+		.map(|x| UintAuthorityId(evaluate(&coefficients, x)))
+		.collect::<Vec<_>>();
+	let shares = incoming()
+		.iter()
+		.zip(&commitments)
+		.map(|(recipient, commitment)| encrypt(recipient, commitment.0).try_into().unwrap())
+		.collect::<Vec<_>>();
+	Dealing {
+		set_id,
+		dealer,
+		commitments: commitments.try_into().unwrap(),
+		shares: shares.try_into().unwrap(),
+	}
+}
+
+pub struct MockShares;
+
+impl EtfShares<UintAuthorityId> for MockShares {
+	fn commitments() -> Vec<UintAuthorityId> {
+		commitments()
+	}
+
+	fn shares() -> Vec<Vec<u8>> {
+		shares()
+	}
+
+	fn set_shares(commitments: Vec<UintAuthorityId>, shares: Vec<Vec<u8>>) {
+		COMMITMENTS.with(|stored| *stored.borrow_mut() = commitments);
+		SHARES.with(|stored| *stored.borrow_mut() = shares);
+	}
+}
+
+pub struct MockCrypto;
+
+impl ResharingCrypto<UintAuthorityId> for MockCrypto {
+	fn verify_dealing(
+		dealer: &UintAuthorityId,
+		commitments: &[UintAuthorityId],
+		threshold: u32,
+	) -> bool {
+		let threshold = threshold as usize;
+		if threshold == 0 || threshold > commitments.len() {
+			return false
+		}
+		let points = core::iter::once(dealer.0)
+			.chain(commitments.iter().map(|commitment| commitment.0))
+			.collect::<Vec<_>>();
+		// The first `threshold` points determine the polynomial, the others have to lie on it.
+		let basis = (0..threshold as u64).collect::<Vec<_>>();
+		(threshold..points.len()).all(|k| {
+			let value = basis.iter().fold(0, |acc, &xj| {
+				(acc + lagrange(k as u64, xj, &basis) * points[xj as usize]) % P
+			});
+			value == points[k]
+		})
+	}
+
+	fn verify_share(
+		recipient: &UintAuthorityId,
+		commitment: &UintAuthorityId,
+		share: &[u8],
+	) -> bool {
+		share == encrypt(recipient, commitment.0).as_slice()
+	}
+
+	fn interpolate(dealt: &[(u32, UintAuthorityId)]) -> Option<UintAuthorityId> {
+		let xs = dealt.iter().map(|(dealer, _)| *dealer as u64 + 1).collect::<Vec<_>>();
+		let secret = dealt
+			.iter()
+			.zip(&xs)
+			.fold(0, |acc, ((_, commitment), &xj)| (acc + lagrange(0, xj, &xs) * commitment.0) % P);
+		Some(UintAuthorityId(secret))
+	}
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+impl BenchmarkHelper<UintAuthorityId> for MockCrypto {
+	fn dealing(
+		incoming: &[UintAuthorityId],
+		threshold: u32,
+	) -> (UintAuthorityId, Vec<UintAuthorityId>, Vec<Vec<u8>>) {
+		let coefficients = (1..=threshold.max(1) as u64).collect::<Vec<_>>();
+		let evaluations = (1..=incoming.len() as u64)
+			.map(|x| evaluate(&coefficients, x))
+			.collect::<Vec<_>>();
+		let shares = incoming.iter().zip(&evaluations).map(|(r, &v)| encrypt(r, v)).collect();
+		let commitments = evaluations.into_iter().map(UintAuthorityId).collect();
+		(UintAuthorityId(coefficients[0]), commitments, shares)
+	}
+}
+
+impl Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type AuthorityId = UintAuthorityId;
+	type Shares = MockShares;
+	type Crypto = MockCrypto;
+	type MaxAuthorities = ConstU32<8>;
+	type MaxShareLen = ConstU32<16>;
+	type UnsignedPriority = frame_support::traits::ConstU64<100>;
+	type WeightInfo = ();
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = MockCrypto;
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+	// The genesis shares are evaluations of `SECRET`, so three of them recover it.
+	pallet_etf_resharing::GenesisConfig::<Test> { threshold: 3, ..Default::default() }
+		.assimilate_storage(&mut t)
+		.unwrap();
+	let genesis = (0..outgoing().len() as u32).map(|i| UintAuthorityId(genesis_share(i))).collect();
+	MockShares::set_shares(genesis, vec![vec![0]; outgoing().len()]);
+
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
@@ -0,0 +1,347 @@
+//! Tests for the ETF resharing pallet.
+
+use super::*;
+use crate::mock::*;
+
+use frame_support::{assert_noop, assert_ok, pallet_prelude::*};
+use sp_runtime::testing::UintAuthorityId;
+
+fn submit_dealing(dealing: Dealing<Test>) -> DispatchResult {
+	let signature = sign(&outgoing()[dealing.dealer as usize], &dealing);
+	EtfResharing::submit_dealing(RuntimeOrigin::none(), dealing, signature)
+}
+
+/// The new share of the incoming authority at `authority`, interpolated from the dealings so
+/// far.
+fn new_share(authority: u32) -> NewShare<Test> {
+	let value = EtfResharing::dealt_commitment(authority).map_or(0, |commitment| commitment.0);
+	let share = encrypt(&incoming()[authority as usize], value).try_into().unwrap();
+	NewShare { set_id: 1, authority, share }
+}
+
+fn submit_share(authority: u32) -> DispatchResult {
+	let share = new_share(authority);
+	let signature = sign(&incoming()[authority as usize], &share);
+	EtfResharing::submit_share(RuntimeOrigin::none(), share, signature)
+}
+
+fn complaint(authority: u32, dealer: u32) -> Complaint {
+	Complaint { set_id: 1, authority, dealer }
+}
+
+fn submit_complaint(authority: u32, dealer: u32) -> DispatchResult {
+	let complaint = complaint(authority, dealer);
+	let signature = sign(&incoming()[authority as usize], &complaint);
+	EtfResharing::submit_complaint(RuntimeOrigin::none(), complaint, signature)
+}
+
+fn start_round() {
+	EtfResharing::on_new_validator_set(&outgoing(), &incoming(), 1);
+}
+
+#[test]
+fn changing_authority_sets_start_a_round() {
+	new_test_ext().execute_with(|| {
+		EtfResharing::on_new_validator_set(&outgoing(), &outgoing(), 1);
+		assert_eq!(Round::<Test>::get(), None);
+
+		start_round();
+		let round = Round::<Test>::get().unwrap();
+		assert_eq!(round.set_id, 1);
+		assert_eq!(round.outgoing.into_inner(), outgoing());
+		assert_eq!(round.incoming.into_inner(), incoming());
+		assert_eq!(round.threshold, 3);
+		System::assert_last_event(Event::ResharingStarted { set_id: 1 }.into());
+	});
+}
+
+#[test]
+fn dealings_are_checked() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(submit_dealing(dealing(1, 0, 3)), Error::<Test>::NoRound);
+		start_round();
+
+		assert_noop!(submit_dealing(dealing(2, 0, 3)), Error::<Test>::NoRound);
+		let mut unknown = dealing(1, 0, 3);
+		unknown.dealer = 3;
+		assert_noop!(
+			EtfResharing::submit_dealing(RuntimeOrigin::none(), unknown, sign(&outgoing()[0], &0)),
+			Error::<Test>::UnknownAuthority
+		);
+		let mut short = dealing(1, 0, 3);
+		short.shares.pop();
+		assert_noop!(submit_dealing(short), Error::<Test>::WrongLength);
+		assert_noop!(
+			EtfResharing::submit_dealing(
+				RuntimeOrigin::none(),
+				dealing(1, 0, 3),
+				sign(&outgoing()[1], &dealing(1, 0, 3)),
+			),
+			Error::<Test>::BadSignature
+		);
+
+		// The degree of the dealt polynomial is too high.
+		assert_noop!(submit_dealing(dealing(1, 0, 4)), Error::<Test>::InvalidDealing);
+		// The dealt polynomial does not go through the dealer's share.
+		let mut forged = dealing(1, 0, 3);
+		forged.commitments.iter_mut().for_each(|commitment| commitment.0 += 1);
+		assert_noop!(submit_dealing(forged), Error::<Test>::InvalidDealing);
+		// A share is encrypted to the wrong authority.
+		let mut misaddressed = dealing(1, 0, 3);
+		let mut shares = misaddressed.shares.into_inner();
+		shares.swap(0, 1);
+		misaddressed.shares = shares.try_into().unwrap();
+		assert_noop!(submit_dealing(misaddressed), Error::<Test>::InvalidShare);
+		// A share does not match its commitment.
+		let mut mismatched = dealing(1, 0, 3);
+		let mut shares = mismatched.shares.into_inner();
+		shares[3] = encrypt(&incoming()[3], 0).try_into().unwrap();
+		mismatched.shares = shares.try_into().unwrap();
+		assert_noop!(submit_dealing(mismatched), Error::<Test>::InvalidShare);
+
+		let valid = dealing(1, 0, 3);
+		assert_ok!(submit_dealing(valid.clone()));
+		System::assert_last_event(Event::Dealt { set_id: 1, dealer: 0 }.into());
+		assert_eq!(
+			EtfResharing::dealt_shares(2),
+			vec![(0, valid.commitments[2].clone(), valid.shares[2].to_vec())]
+		);
+		assert_noop!(submit_dealing(dealing(1, 0, 3)), Error::<Test>::AlreadyDealt);
+	});
+}
+
+#[test]
+fn new_shares_need_a_qualified_round() {
+	new_test_ext().execute_with(|| {
+		start_round();
+		assert_ok!(submit_dealing(dealing(1, 2, 3)));
+		assert_ok!(submit_dealing(dealing(1, 0, 3)));
+		assert_noop!(submit_share(0), Error::<Test>::NotQualified);
+
+		assert_ok!(submit_dealing(dealing(1, 1, 3)));
+		System::assert_last_event(Event::Qualified { set_id: 1 }.into());
+
+		let signature = sign(&incoming()[1], &0);
+		assert_noop!(
+			EtfResharing::submit_share(RuntimeOrigin::none(), new_share(0), signature),
+			Error::<Test>::BadSignature
+		);
+		// The share does not match the commitment interpolated from the dealings.
+		let mut wrong = new_share(0);
+		wrong.share = encrypt(&incoming()[0], 1).try_into().unwrap();
+		let signature = sign(&incoming()[0], &wrong);
+		assert_noop!(
+			EtfResharing::submit_share(RuntimeOrigin::none(), wrong, signature),
+			Error::<Test>::InvalidShare
+		);
+		assert_ok!(submit_share(0));
+		System::assert_last_event(Event::ShareSubmitted { set_id: 1, authority: 0 }.into());
+		assert_noop!(submit_share(0), Error::<Test>::AlreadySubmitted);
+	});
+}
+
+#[test]
+fn complaints_reject_dealings() {
+	new_test_ext().execute_with(|| {
+		start_round();
+		for dealer in 0..3 {
+			assert_ok!(submit_dealing(dealing(1, dealer, 3)));
+		}
+		assert_ok!(submit_share(0));
+
+		assert_noop!(submit_complaint(0, 1), Error::<Test>::AlreadySubmitted);
+		let signature = sign(&incoming()[2], &complaint(1, 1));
+		assert_noop!(
+			EtfResharing::submit_complaint(RuntimeOrigin::none(), complaint(1, 1), signature),
+			Error::<Test>::BadSignature
+		);
+		assert_ok!(submit_complaint(1, 1));
+		System::assert_last_event(
+			Event::DealingRejected { set_id: 1, dealer: 1, authority: 1 }.into(),
+		);
+		assert_eq!(Dealings::<Test>::count(), 2);
+		assert!(EtfResharing::dealt_shares(2).iter().all(|(dealer, ..)| *dealer != 1));
+		// The new share of authority 0 was interpolated from the rejected dealing.
+		assert!(!NewShares::<Test>::contains_key(0));
+
+		assert_noop!(submit_complaint(1, 0), Error::<Test>::AlreadyComplained);
+		assert_noop!(submit_complaint(2, 1), Error::<Test>::Rejected);
+		assert_noop!(submit_dealing(dealing(1, 1, 3)), Error::<Test>::Rejected);
+		assert_noop!(submit_share(0), Error::<Test>::NotQualified);
+
+		let status = EtfResharing::status().unwrap();
+		assert_eq!(status.dealt, vec![0, 2]);
+		assert_eq!(status.rejected, vec![1]);
+		assert_eq!(status.complained, vec![1]);
+		assert!(!status.qualified);
+	});
+}
+
+#[test]
+fn rejected_dealers_are_replaced() {
+	new_test_ext().execute_with(|| {
+		Threshold::<Test>::put(2);
+		start_round();
+		assert_ok!(submit_dealing(dealing(1, 0, 2)));
+		assert_ok!(submit_dealing(dealing(1, 1, 2)));
+		assert_ok!(submit_complaint(3, 0));
+
+		// Another outgoing authority takes the place of the rejected one.
+		assert_ok!(submit_dealing(dealing(1, 2, 2)));
+		System::assert_last_event(Event::Qualified { set_id: 1 }.into());
+		for authority in 0..4 {
+			assert_ok!(submit_share(authority));
+		}
+
+		EtfResharing::on_new_validator_set(&incoming(), &incoming(), 2);
+		System::assert_last_event(Event::SharesRotated { set_id: 1, submitted: 4 }.into());
+		assert_eq!(Rejected::<Test>::iter().count(), 0);
+		assert_eq!(Complaints::<Test>::iter().count(), 0);
+	});
+}
+
+#[test]
+fn incoming_authorities_take_over_the_shares() {
+	new_test_ext().execute_with(|| {
+		start_round();
+		for dealer in 0..3 {
+			assert_ok!(submit_dealing(dealing(1, dealer, 3)));
+		}
+		let submitted = [0, 1, 3].map(|authority| new_share(authority).share.into_inner());
+		for authority in [0, 1, 3] {
+			assert_ok!(submit_share(authority));
+		}
+
+		EtfResharing::on_new_validator_set(&incoming(), &incoming(), 2);
+		System::assert_last_event(Event::SharesRotated { set_id: 1, submitted: 3 }.into());
+		let [first, second, fourth] = submitted;
+		assert_eq!(shares(), vec![first, second, vec![], fourth]);
+		assert_eq!(EtfResharing::threshold(), 3);
+		assert_eq!(Round::<Test>::get(), None);
+		assert_eq!(Dealings::<Test>::count(), 0);
+		assert!(EtfResharing::dealt_shares(0).is_empty());
+
+		// Any three of the new shares recover the round secret, including the share that was
+		// never submitted.
+		let commitments = commitments();
+		for indices in [[0, 1, 2], [1, 2, 3], [0, 1, 3]] {
+			let dealt = indices.map(|i| (i, commitments[i as usize].clone()));
+			assert_eq!(MockCrypto::interpolate(&dealt), Some(UintAuthorityId(SECRET[0])));
+		}
+	});
+}
+
+#[test]
+fn unqualified_rounds_leave_the_shares() {
+	new_test_ext().execute_with(|| {
+		let genesis = commitments();
+		start_round();
+		assert_ok!(submit_dealing(dealing(1, 1, 3)));
+
+		EtfResharing::on_new_validator_set(&incoming(), &incoming(), 2);
+		System::assert_last_event(Event::ResharingFailed { set_id: 1 }.into());
+		assert_eq!(commitments(), genesis);
+		assert_eq!(EtfResharing::threshold(), 3);
+		assert_eq!(Dealings::<Test>::count(), 0);
+	});
+}
+
+#[test]
+fn rounds_without_a_threshold_of_new_shares_leave_the_shares() {
+	new_test_ext().execute_with(|| {
+		let (genesis_commitments, genesis_shares) = (commitments(), shares());
+		start_round();
+		for dealer in 0..3 {
+			assert_ok!(submit_dealing(dealing(1, dealer, 3)));
+		}
+		// Only two of the four incoming authorities submit their share, below the threshold
+		// of three.
+		assert_ok!(submit_share(0));
+		assert_ok!(submit_share(2));
+
+		EtfResharing::on_new_validator_set(&incoming(), &incoming(), 2);
+		System::assert_last_event(Event::ResharingFailed { set_id: 1 }.into());
+		assert_eq!(commitments(), genesis_commitments);
+		assert_eq!(shares(), genesis_shares);
+		assert_eq!(NewShares::<Test>::iter().count(), 0);
+	});
+}
+
+#[test]
+fn thresholds_follow_the_size_of_each_set_by_default() {
+	new_test_ext().execute_with(|| {
+		Threshold::<Test>::put(0);
+		assert_eq!(EtfResharing::threshold(), 2);
+		assert_eq!(EtfResharing::threshold_for(1), 1);
+		assert_eq!(EtfResharing::threshold_for(2), 1);
+		assert_eq!(EtfResharing::threshold_for(3), 2);
+		assert_eq!(EtfResharing::threshold_for(4), 3);
+		assert_eq!(EtfResharing::threshold_for(10), 7);
+	});
+}
+
+#[test]
+fn configured_thresholds_are_carried_across_rounds() {
+	new_test_ext().execute_with(|| {
+		Threshold::<Test>::put(2);
+		start_round();
+		assert_eq!(Round::<Test>::get().unwrap().threshold, 2);
+		assert_ok!(submit_dealing(dealing(1, 0, 2)));
+		assert_ok!(submit_dealing(dealing(1, 1, 2)));
+		assert_noop!(submit_dealing(dealing(1, 2, 2)), Error::<Test>::AlreadyQualified);
+		for authority in 0..4 {
+			assert_ok!(submit_share(authority));
+		}
+
+		EtfResharing::on_new_validator_set(&incoming(), &incoming(), 2);
+		System::assert_last_event(Event::SharesRotated { set_id: 1, submitted: 4 }.into());
+		assert_eq!(EtfResharing::threshold(), 2);
+
+		// A configured threshold above the size of a set is capped to it.
+		Threshold::<Test>::put(6);
+		EtfResharing::on_new_validator_set(&incoming(), &outgoing(), 3);
+		assert_eq!(Round::<Test>::get().unwrap().threshold, 3);
+	});
+}
+
+#[test]
+fn submissions_are_validated_in_the_pool() {
+	new_test_ext().execute_with(|| {
+		start_round();
+		let validate = |call| EtfResharing::validate_unsigned(TransactionSource::External, &call);
+
+		let first = dealing(1, 0, 3);
+		let signature = sign(&outgoing()[0], &first);
+		let valid = validate(Call::submit_dealing { dealing: first, signature }).unwrap();
+		assert_eq!(valid.priority, 100);
+		assert_eq!(valid.provides, vec![("EtfResharing", (1u64, "dealing", 0u32)).encode()]);
+
+		let signature = sign(&incoming()[1], &0);
+		assert_eq!(
+			validate(Call::submit_dealing { dealing: dealing(1, 1, 3), signature }),
+			InvalidTransaction::BadProof.into()
+		);
+		let signature = sign(&incoming()[0], &new_share(0));
+		assert_eq!(
+			validate(Call::submit_share { share: new_share(0), signature }),
+			InvalidTransaction::Future.into()
+		);
+		let forged = dealing(1, 0, 4);
+		let signature = sign(&outgoing()[0], &forged);
+		assert_eq!(
+			validate(Call::submit_dealing { dealing: forged, signature }),
+			InvalidTransaction::Call.into()
+		);
+
+		assert_ok!(submit_dealing(dealing(1, 0, 3)));
+		let signature = sign(&incoming()[2], &complaint(2, 0));
+		let valid =
+			validate(Call::submit_complaint { complaint: complaint(2, 0), signature }).unwrap();
+		assert_eq!(valid.provides, vec![("EtfResharing", (1u64, "complaint", 2u32)).encode()]);
+		let signature = sign(&incoming()[2], &complaint(2, 1));
+		assert_eq!(
+			validate(Call::submit_complaint { complaint: complaint(2, 1), signature }),
+			InvalidTransaction::Stale.into()
+		);
+	});
+}
@@ -0,0 +1,121 @@
+//! Weights for `pallet_etf_resharing`.
+//!
+//! The benchmarks of the pallet are in `benchmarking.rs`. These values are estimated from the
+//! storage accesses of the benchmarks, and are to be replaced by the output of
+//! `benchmark pallet --pallet pallet_etf_resharing` on reference hardware, see the README.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+#![allow(missing_docs)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use core::marker::PhantomData;
+
+/// Weight functions needed for `pallet_etf_resharing`.
+pub trait WeightInfo {
+	fn submit_dealing(n: u32, ) -> Weight;
+	fn submit_share(t: u32, ) -> Weight;
+	fn rotate(n: u32, m: u32, ) -> Weight;
+	fn submit_complaint(n: u32, ) -> Weight;
+}
+
+/// Weights for `pallet_etf_resharing` using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: `EtfResharing::Round` (r:1 w:0)
+	/// Storage: `EtfResharing::Dealings` (r:1 w:1)
+	/// Storage: `EtfResharing::Rejected` (r:1 w:0)
+	/// Storage: `EtfResharing::CounterForDealings` (r:1 w:1)
+	/// Storage: `EtfResharing::Threshold` (r:1 w:0)
+	/// Storage: `Etf::Commitments` (r:1 w:0)
+	/// Storage: `EtfResharing::DealtShares` (r:0 w:n)
+	/// The range of component `n` is `[1, 100]`, the number of incoming authorities.
+	fn submit_dealing(n: u32, ) -> Weight {
+		// Two multi-scalar multiplications of `n + 1` points, one per group, and the proofs of
+		// the `n` encrypted shares.
+		Weight::from_parts(50_000_000, 0)
+			.saturating_add(Weight::from_parts(16_000_000, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(6_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(n.into())))
+	}
+	/// Storage: `EtfResharing::Round` (r:1 w:0)
+	/// Storage: `EtfResharing::CounterForDealings` (r:1 w:0)
+	/// Storage: `EtfResharing::Threshold` (r:1 w:0)
+	/// Storage: `EtfResharing::NewShares` (r:1 w:1)
+	/// Storage: `EtfResharing::Dealings` (r:t w:0)
+	/// The range of component `t` is `[1, 100]`, the number of dealers.
+	fn submit_share(t: u32, ) -> Weight {
+		// The interpolation of `t` commitments and the proof of the encrypted share.
+		Weight::from_parts(40_000_000, 0)
+			.saturating_add(Weight::from_parts(6_000_000, 0).saturating_mul(t.into()))
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(t.into())))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `EtfResharing::NewShares` (r:n w:n)
+	/// Storage: `EtfResharing::Dealings` (r:100 w:0)
+	/// Storage: `Etf::Commitments` (r:0 w:1)
+	/// Storage: `Etf::Shares` (r:0 w:1)
+	/// The range of component `n` is `[1, 100]`, the number of incoming authorities.
+	/// The range of component `m` is `[0, 100]`, the incoming authorities without a new share.
+	fn rotate(n: u32, m: u32, ) -> Weight {
+		Weight::from_parts(10_000_000, 0)
+			.saturating_add(Weight::from_parts(600_000_000, 0).saturating_mul(m.into()))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().reads((100_u64).saturating_mul(m.into())))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(n.into())))
+	}
+	/// Storage: `EtfResharing::Round` (r:1 w:0)
+	/// Storage: `EtfResharing::Rejected` (r:1 w:1)
+	/// Storage: `EtfResharing::Dealings` (r:1 w:1)
+	/// Storage: `EtfResharing::CounterForDealings` (r:1 w:1)
+	/// Storage: `EtfResharing::Complaints` (r:1 w:1)
+	/// Storage: `EtfResharing::DealtShares` (r:0 w:n)
+	/// Storage: `EtfResharing::NewShares` (r:n w:n)
+	/// The range of component `n` is `[1, 100]`, the number of incoming authorities.
+	fn submit_complaint(n: u32, ) -> Weight {
+		Weight::from_parts(30_000_000, 0)
+			.saturating_add(Weight::from_parts(2_000_000, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(6_u64))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+			.saturating_add(T::DbWeight::get().writes((2_u64).saturating_mul(n.into())))
+	}
+}
+
+// For backwards compatibility and tests.
+impl WeightInfo for () {
+	fn submit_dealing(n: u32, ) -> Weight {
+		Weight::from_parts(50_000_000, 0)
+			.saturating_add(Weight::from_parts(16_000_000, 0).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(6_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+			.saturating_add(RocksDbWeight::get().writes((1_u64).saturating_mul(n.into())))
+	}
+	fn submit_share(t: u32, ) -> Weight {
+		Weight::from_parts(40_000_000, 0)
+			.saturating_add(Weight::from_parts(6_000_000, 0).saturating_mul(t.into()))
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().reads((1_u64).saturating_mul(t.into())))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	fn rotate(n: u32, m: u32, ) -> Weight {
+		Weight::from_parts(10_000_000, 0)
+			.saturating_add(Weight::from_parts(600_000_000, 0).saturating_mul(m.into()))
+			.saturating_add(RocksDbWeight::get().reads((1_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().reads((100_u64).saturating_mul(m.into())))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+			.saturating_add(RocksDbWeight::get().writes((1_u64).saturating_mul(n.into())))
+	}
+	fn submit_complaint(n: u32, ) -> Weight {
+		Weight::from_parts(30_000_000, 0)
+			.saturating_add(Weight::from_parts(2_000_000, 0).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(6_u64))
+			.saturating_add(RocksDbWeight::get().reads((1_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
+			.saturating_add(RocksDbWeight::get().writes((2_u64).saturating_mul(n.into())))
+	}
+}
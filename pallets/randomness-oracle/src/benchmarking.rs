@@ -0,0 +1,122 @@
+//! Benchmarks for the randomness oracle pallet.
+
+use super::*;
+use crate::Pallet as RandomnessOracle;
+
+use frame_benchmarking::v2::*;
+use frame_support::traits::{Get, Hooks};
+use frame_system::{pallet_prelude::BlockNumberFor, RawOrigin};
+use sp_runtime::traits::One;
+
+/// A callback dispatching a remark, which has to be decoded when it is requested and fulfilled.
+fn remark_callback<T: Config>() -> Callback<T>
+where
+	<T as Config>::RuntimeCall: From<frame_system::Call<T>>,
+{
+	let call = <T as Config>::RuntimeCall::from(frame_system::Call::remark { remark: vec![] });
+	Callback::Call(BoundedVec::truncate_from(call.encode()))
+}
+
+/// The longest subject.
+fn subject<T: Config>() -> BoundedVec<u8, T::MaxSubjectLen> {
+	BoundedVec::truncate_from(vec![0; T::MaxSubjectLen::get() as usize])
+}
+
+/// An account that can pay the deposits of a few requests.
+fn funded_requester<T: Config>() -> T::AccountId {
+	let requester = account("requester", 0, 0);
+	let funds = T::Currency::minimum_balance()
+		.saturating_add(T::RequestDeposit::get().saturating_mul(10u32.into()));
+	T::Currency::make_free_balance_be(&requester, funds);
+	requester
+}
+
+/// Fill the pending target blocks but for one, with the blocks after `block`.
+fn pend_blocks_after<T: Config>(block: BlockNumberFor<T>) {
+	let pending = (1..T::MaxPendingBlocks::get())
+		.map(|k| block.saturating_add(k.into()))
+		.collect::<Vec<_>>();
+	PendingBlocks::<T>::put(BoundedVec::truncate_from(pending));
+}
+
+#[benchmarks(where <T as Config>::RuntimeCall: From<frame_system::Call<T>>)]
+mod benchmarks {
+	use super::*;
+
+	#[benchmark]
+	fn request() {
+		// The target block goes before all other pending blocks, and its queue is full but for
+		// one request.
+		let requester = funded_requester::<T>();
+		let block = frame_system::Pallet::<T>::block_number().saturating_add(One::one());
+		pend_blocks_after::<T>(block);
+		let queued = T::MaxRequestsPerBlock::get().saturating_sub(1) as RequestId;
+		Queue::<T>::insert(block, BoundedVec::truncate_from((0..queued).collect::<Vec<_>>()));
+		NextRequestId::<T>::put(queued);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(requester), block, subject::<T>(), remark_callback::<T>());
+
+		assert!(Requests::<T>::contains_key(queued));
+	}
+
+	#[benchmark]
+	fn cancel() -> Result<(), BenchmarkError> {
+		// The only request for its target block, which is removed from the pending blocks.
+		let requester = funded_requester::<T>();
+		let block = frame_system::Pallet::<T>::block_number().saturating_add(One::one());
+		pend_blocks_after::<T>(block);
+		let id = RandomnessOracle::<T>::do_request(
+			requester.clone(),
+			block,
+			subject::<T>(),
+			remark_callback::<T>(),
+		)
+		.map_err(|_| BenchmarkError::Stop("The request failed"))?;
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(requester), id);
+
+		assert!(!Requests::<T>::contains_key(id));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn fulfil() -> Result<(), BenchmarkError> {
+		let requester = funded_requester::<T>();
+		let block = frame_system::Pallet::<T>::block_number().saturating_add(One::one());
+		let id = RandomnessOracle::<T>::do_request(
+			requester,
+			block,
+			subject::<T>(),
+			remark_callback::<T>(),
+		)
+		.map_err(|_| BenchmarkError::Stop("The request failed"))?;
+
+		#[block]
+		{
+			RandomnessOracle::<T>::fulfil(id, &[7; 32]);
+		}
+
+		assert!(!Requests::<T>::contains_key(id));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn on_initialize(p: Linear<0, { T::MaxPendingBlocks::get() }>) {
+		// The beacon went past all `p` pending blocks, but none of their pulses is known.
+		let pending = (1..=p).map(Into::into).collect::<Vec<BlockNumberFor<T>>>();
+		PendingBlocks::<T>::put(BoundedVec::truncate_from(pending));
+		T::BenchmarkHelper::publish(p.saturating_add(1).into());
+		let now = frame_system::Pallet::<T>::block_number();
+
+		#[block]
+		{
+			RandomnessOracle::<T>::on_initialize(now);
+		}
+
+		assert_eq!(PendingBlocks::<T>::get().len() as u32, p);
+	}
+
+	impl_benchmark_test_suite!(RandomnessOracle, crate::mock::new_test_ext(), crate::mock::Test);
+}
@@ -0,0 +1,497 @@
+//! # Randomness Oracle Pallet
+//!
+//! Lets contracts and pallets request randomness for a future block instead of polling the
+//! randomness beacon.
+//!
+//! A request names the target block, a subject and a callback, and reserves
+//! [`Config::RequestDeposit`] from the requester. Once the beacon has published the pulse for
+//! the target block, the request is fulfilled in `on_initialize`: the deposit is returned less
+//! [`Config::FulfilmentFee`], and the callback is dispatched with the randomness of the pulse
+//! hashed with the subject, so that requests for the same block get independent values.
+//!
+//! Callbacks run in `on_initialize`, where nobody pays for them. Their declared weight is
+//! therefore part of the weight of the request, so that it is paid for up front.
+//!
+//! Callbacks are either
+//! - a contract message, called with the SCALE encoded `(RequestId, [u8; 32])` appended to its
+//!   selector, or
+//! - a runtime call, dispatched with the requester as the signed origin. While it runs, the
+//!   randomness can be read with [`Pallet::current_randomness`].
+//!
+//! Requests are kept in a bounded queue per target block, and at most
+//! [`Config::MaxPendingBlocks`] target blocks can be pending at once. Targets can be at most
+//! [`Config::MaxLookahead`] blocks ahead, so that the pending blocks are freed up again within
+//! a bounded time instead of being held by far-future requests. One target block is
+//! fulfilled per block. If the pulse of a target block is known to be permanently missing, its
+//! requests are dropped with [`Pallet::expire`].
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+pub mod weights;
+pub use weights::WeightInfo;
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::{
+	dispatch::{GetDispatchInfo, PostDispatchInfo},
+	traits::{Currency, OnUnbalanced, ReservableCurrency},
+	weights::Weight,
+	BoundedVec, CloneNoBound, EqNoBound, PartialEqNoBound, RuntimeDebugNoBound,
+};
+use frame_system::pallet_prelude::BlockNumberFor;
+use scale_info::TypeInfo;
+use sp_runtime::{
+	traits::{Dispatchable, Saturating},
+	DispatchError, DispatchResult, Perbill,
+};
+use sp_std::prelude::*;
+
+/// The identifier of a randomness request.
+pub type RequestId = u64;
+
+type BalanceOf<T> =
+	<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+type NegativeImbalanceOf<T> = <<T as Config>::Currency as Currency<
+	<T as frame_system::Config>::AccountId,
+>>::NegativeImbalance;
+
+/// A source of randomness for past blocks.
+pub trait RandomnessSource<BlockNumber> {
+	/// The latest block with published randomness.
+	fn latest() -> BlockNumber;
+
+	/// The randomness published for `block`, if there is any yet.
+	fn randomness_at(block: BlockNumber) -> Option<[u8; 32]>;
+}
+
+/// Sets up the randomness source for the benchmarks of this pallet.
+#[cfg(feature = "runtime-benchmarks")]
+pub trait BenchmarkHelper<BlockNumber> {
+	/// Publish the randomness of `block`, and of no other block.
+	fn publish(block: BlockNumber);
+}
+
+/// Calls contract messages to deliver randomness.
+pub trait ContractCallback<AccountId> {
+	/// Call `contract` on behalf of `origin` with `input`, spending at most `gas_limit`.
+	///
+	/// Returns the weight consumed and the outcome of the call.
+	fn call(
+		origin: AccountId,
+		contract: AccountId,
+		input: Vec<u8>,
+		gas_limit: Weight,
+	) -> (Weight, DispatchResult);
+}
+
+impl<AccountId> ContractCallback<AccountId> for () {
+	fn call(_: AccountId, _: AccountId, _: Vec<u8>, _: Weight) -> (Weight, DispatchResult) {
+		(Weight::zero(), Err(DispatchError::Other("Contract callbacks are not supported")))
+	}
+}
+
+/// What to do with the randomness once it is published.
+#[derive(
+	CloneNoBound,
+	PartialEqNoBound,
+	EqNoBound,
+	Encode,
+	Decode,
+	MaxEncodedLen,
+	TypeInfo,
+	RuntimeDebugNoBound,
+)]
+#[scale_info(skip_type_params(T))]
+#[codec(mel_bound())]
+pub enum Callback<T: Config> {
+	/// Call the message with `selector` of `contract`.
+	Contract {
+		/// The contract to call.
+		contract: T::AccountId,
+		/// The selector of the message.
+		selector: [u8; 4],
+		/// The gas limit of the call.
+		gas_limit: Weight,
+	},
+	/// Dispatch the SCALE encoded runtime call as the requester.
+	Call(BoundedVec<u8, T::MaxCallLen>),
+}
+
+/// A pending randomness request.
+#[derive(
+	CloneNoBound,
+	PartialEqNoBound,
+	EqNoBound,
+	Encode,
+	Decode,
+	MaxEncodedLen,
+	TypeInfo,
+	RuntimeDebugNoBound,
+)]
+#[scale_info(skip_type_params(T))]
+#[codec(mel_bound())]
+pub struct Request<T: Config> {
+	/// The account that made the request and paid the deposit.
+	pub requester: T::AccountId,
+	/// The block whose randomness is requested.
+	pub block: BlockNumberFor<T>,
+	/// The subject the randomness is separated by.
+	pub subject: BoundedVec<u8, T::MaxSubjectLen>,
+	/// What to do with the randomness.
+	pub callback: Callback<T>,
+	/// The reserved deposit.
+	pub deposit: BalanceOf<T>,
+}
+
+/// Hash the randomness of a pulse with the subject of a request.
+pub fn subject_randomness(randomness: &[u8; 32], subject: &[u8]) -> [u8; 32] {
+	sp_io::hashing::blake2_256(&(b"randomness-oracle", randomness, subject).encode())
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The calls callbacks can dispatch.
+		type RuntimeCall: Parameter
+			+ Dispatchable<RuntimeOrigin = Self::RuntimeOrigin, PostInfo = PostDispatchInfo>
+			+ GetDispatchInfo;
+
+		/// The currency the request deposits are reserved in.
+		type Currency: ReservableCurrency<Self::AccountId>;
+
+		/// Where the randomness comes from.
+		type Randomness: RandomnessSource<BlockNumberFor<Self>>;
+
+		/// How contract callbacks are called.
+		type Contracts: ContractCallback<Self::AccountId>;
+
+		/// The deposit reserved for each request until it is fulfilled or cancelled.
+		#[pallet::constant]
+		type RequestDeposit: Get<BalanceOf<Self>>;
+
+		/// The part of the deposit kept when a request is fulfilled.
+		#[pallet::constant]
+		type FulfilmentFee: Get<Perbill>;
+
+		/// Where the kept part of fulfilled deposits goes.
+		type FeeDestination: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+		/// The maximum number of requests for one target block.
+		#[pallet::constant]
+		type MaxRequestsPerBlock: Get<u32>;
+
+		/// The maximum number of target blocks with pending requests.
+		#[pallet::constant]
+		type MaxPendingBlocks: Get<u32>;
+
+		/// How many blocks ahead of the current one a target block can be at most.
+		#[pallet::constant]
+		type MaxLookahead: Get<BlockNumberFor<Self>>;
+
+		/// The maximum length of a subject.
+		#[pallet::constant]
+		type MaxSubjectLen: Get<u32>;
+
+		/// The maximum length of an encoded callback call.
+		#[pallet::constant]
+		type MaxCallLen: Get<u32>;
+
+		/// The maximum weight of a single callback.
+		#[pallet::constant]
+		type MaxCallbackWeight: Get<Weight>;
+
+		/// Weight information for extrinsics in this pallet.
+		type WeightInfo: WeightInfo;
+
+		/// Sets up the randomness source for the benchmarks.
+		#[cfg(feature = "runtime-benchmarks")]
+		type BenchmarkHelper: BenchmarkHelper<BlockNumberFor<Self>>;
+	}
+
+	/// The id of the next request.
+	#[pallet::storage]
+	pub type NextRequestId<T> = StorageValue<_, RequestId, ValueQuery>;
+
+	/// The pending requests.
+	#[pallet::storage]
+	pub type Requests<T: Config> = StorageMap<_, Twox64Concat, RequestId, Request<T>>;
+
+	/// The pending requests of each target block.
+	#[pallet::storage]
+	pub type Queue<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		BlockNumberFor<T>,
+		BoundedVec<RequestId, T::MaxRequestsPerBlock>,
+		ValueQuery,
+	>;
+
+	/// The target blocks with pending requests, in ascending order.
+	#[pallet::storage]
+	pub type PendingBlocks<T: Config> =
+		StorageValue<_, BoundedVec<BlockNumberFor<T>, T::MaxPendingBlocks>, ValueQuery>;
+
+	/// The request being fulfilled and its randomness, while its callback call is dispatched.
+	#[pallet::storage]
+	pub type CurrentRandomness<T> = StorageValue<_, (RequestId, [u8; 32])>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// Randomness was requested.
+		Requested { id: RequestId, requester: T::AccountId, block: BlockNumberFor<T> },
+		/// A request was fulfilled and its callback dispatched.
+		Fulfilled { id: RequestId, result: DispatchResult },
+		/// A request was cancelled.
+		Cancelled { id: RequestId },
+		/// A request was dropped because the pulse of its target block is permanently missing.
+		Expired { id: RequestId },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The target block has to be in the future.
+		TargetNotInFuture,
+		/// The target block is more than the maximum lookahead ahead.
+		TargetTooFarAhead,
+		/// The target block has the maximum number of requests.
+		QueueFull,
+		/// Too many target blocks have pending requests.
+		TooManyPendingBlocks,
+		/// The callback may use more than the maximum callback weight.
+		CallbackTooHeavy,
+		/// The callback call cannot be decoded.
+		UndecodableCall,
+		/// There is no pending request with this id.
+		UnknownRequest,
+		/// Only the requester can cancel a request.
+		NotRequester,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(_n: BlockNumberFor<T>) -> Weight {
+			let latest = T::Randomness::latest();
+			let mut pending = PendingBlocks::<T>::get();
+			let mut weight = T::WeightInfo::on_initialize(pending.len() as u32);
+
+			// Target blocks are sorted, and those without a pulse are left pending, in case it
+			// is published late.
+			let ready = pending
+				.iter()
+				.enumerate()
+				.take_while(|(_, block)| **block <= latest)
+				.find_map(|(idx, block)| {
+					T::Randomness::randomness_at(*block).map(|r| (idx, *block, r))
+				});
+			if let Some((idx, block, randomness)) = ready {
+				pending.remove(idx);
+				PendingBlocks::<T>::put(pending);
+				for id in Queue::<T>::take(block) {
+					weight.saturating_accrue(Self::fulfil(id, &randomness));
+				}
+			}
+			weight
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Request the randomness of `block`, separated by `subject`, to be delivered to
+		/// `callback`.
+		///
+		/// The weight includes the declared weight of the callback, which is dispatched later
+		/// in `on_initialize`.
+		#[pallet::call_index(0)]
+		#[pallet::weight(
+			T::WeightInfo::request().saturating_add(Pallet::<T>::callback_weight(callback))
+		)]
+		pub fn request(
+			origin: OriginFor<T>,
+			block: BlockNumberFor<T>,
+			subject: BoundedVec<u8, T::MaxSubjectLen>,
+			callback: Callback<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_request(who, block, subject, callback).map(|_| ())
+		}
+
+		/// Cancel a pending request and return its deposit.
+		#[pallet::call_index(1)]
+		#[pallet::weight(T::WeightInfo::cancel())]
+		pub fn cancel(origin: OriginFor<T>, id: RequestId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let request = Requests::<T>::get(id).ok_or(Error::<T>::UnknownRequest)?;
+			ensure!(request.requester == who, Error::<T>::NotRequester);
+
+			Requests::<T>::remove(id);
+			let mut queue = Queue::<T>::get(request.block);
+			queue.retain(|queued| *queued != id);
+			if queue.is_empty() {
+				Queue::<T>::remove(request.block);
+				PendingBlocks::<T>::mutate(|pending| {
+					pending.retain(|block| *block != request.block)
+				});
+			} else {
+				Queue::<T>::insert(request.block, queue);
+			}
+			T::Currency::unreserve(&who, request.deposit);
+
+			Self::deposit_event(Event::Cancelled { id });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Request randomness on behalf of `requester`, reserving the deposit from it.
+		///
+		/// This is how pallets and the contracts chain extension make requests.
+		pub fn do_request(
+			requester: T::AccountId,
+			block: BlockNumberFor<T>,
+			subject: BoundedVec<u8, T::MaxSubjectLen>,
+			callback: Callback<T>,
+		) -> Result<RequestId, DispatchError> {
+			let now = frame_system::Pallet::<T>::block_number();
+			ensure!(block > now, Error::<T>::TargetNotInFuture);
+			ensure!(
+				block <= now.saturating_add(T::MaxLookahead::get()),
+				Error::<T>::TargetTooFarAhead
+			);
+			let callback_weight = match &callback {
+				Callback::Contract { gas_limit, .. } => *gas_limit,
+				Callback::Call(call) => Self::decode_call(call)?.get_dispatch_info().weight,
+			};
+			// Callers charge `callback_weight`, which relies on this bound.
+			ensure!(
+				callback_weight.all_lte(T::MaxCallbackWeight::get()),
+				Error::<T>::CallbackTooHeavy
+			);
+
+			// Check every bound before anything is written.
+			let id = NextRequestId::<T>::get();
+			let mut queue = Queue::<T>::get(block);
+			queue.try_push(id).map_err(|_| Error::<T>::QueueFull)?;
+			let mut pending = PendingBlocks::<T>::get();
+			if let Err(idx) = pending.binary_search(&block) {
+				pending.try_insert(idx, block).map_err(|_| Error::<T>::TooManyPendingBlocks)?;
+			}
+			let deposit = T::RequestDeposit::get();
+			T::Currency::reserve(&requester, deposit)?;
+
+			Queue::<T>::insert(block, queue);
+			PendingBlocks::<T>::put(pending);
+			Requests::<T>::insert(
+				id,
+				Request { requester: requester.clone(), block, subject, callback, deposit },
+			);
+			NextRequestId::<T>::put(id.saturating_add(1));
+
+			Self::deposit_event(Event::Requested { id, requester, block });
+			Ok(id)
+		}
+
+		/// Drop the requests for `block`, whose pulse will never be published, returning their
+		/// deposits.
+		///
+		/// Returns the weight consumed.
+		pub fn expire(block: BlockNumberFor<T>) -> Weight {
+			let mut pending = PendingBlocks::<T>::get();
+			let Ok(idx) = pending.binary_search(&block) else {
+				return T::DbWeight::get().reads(1)
+			};
+			pending.remove(idx);
+			PendingBlocks::<T>::put(pending);
+
+			let queue = Queue::<T>::take(block);
+			for id in &queue {
+				if let Some(request) = Requests::<T>::take(id) {
+					T::Currency::unreserve(&request.requester, request.deposit);
+					Self::deposit_event(Event::Expired { id: *id });
+				}
+			}
+			T::WeightInfo::cancel().saturating_mul(queue.len() as u64)
+		}
+
+		/// The request being fulfilled and its randomness, for runtime call callbacks.
+		pub fn current_randomness() -> Option<(RequestId, [u8; 32])> {
+			CurrentRandomness::<T>::get()
+		}
+
+		/// The weight `callback` is charged at request time: its declared weight, capped at
+		/// [`Config::MaxCallbackWeight`].
+		///
+		/// Callbacks that cannot be decoded or are too heavy are rejected by
+		/// [`Pallet::do_request`], so they are not charged for.
+		pub fn callback_weight(callback: &Callback<T>) -> Weight {
+			let weight = match callback {
+				Callback::Contract { gas_limit, .. } => *gas_limit,
+				Callback::Call(call) => Self::decode_call(call)
+					.map(|call| call.get_dispatch_info().weight)
+					.unwrap_or_default(),
+			};
+			weight.min(T::MaxCallbackWeight::get())
+		}
+
+		fn decode_call(call: &[u8]) -> Result<<T as Config>::RuntimeCall, Error<T>> {
+			Decode::decode(&mut &call[..]).map_err(|_| Error::<T>::UndecodableCall)
+		}
+
+		/// Return the deposit of request `id`, less the fulfilment fee, and dispatch its
+		/// callback.
+		pub(crate) fn fulfil(id: RequestId, randomness: &[u8; 32]) -> Weight {
+			let Some(request) = Requests::<T>::take(id) else {
+				return T::DbWeight::get().reads(1)
+			};
+			let fee = T::FulfilmentFee::get() * request.deposit;
+			let (imbalance, _) = T::Currency::slash_reserved(&request.requester, fee);
+			T::FeeDestination::on_unbalanced(imbalance);
+			T::Currency::unreserve(&request.requester, request.deposit.saturating_sub(fee));
+			let randomness = subject_randomness(randomness, &request.subject);
+
+			let (used, result) = match request.callback {
+				Callback::Contract { contract, selector, gas_limit } => {
+					let mut input = selector.to_vec();
+					(id, randomness).encode_to(&mut input);
+					T::Contracts::call(request.requester, contract, input, gas_limit)
+				},
+				Callback::Call(call) => match Self::decode_call(&call) {
+					Ok(call) => {
+						let info = call.get_dispatch_info();
+						CurrentRandomness::<T>::put((id, randomness));
+						let origin = frame_system::RawOrigin::Signed(request.requester).into();
+						let result = call.dispatch(origin);
+						CurrentRandomness::<T>::kill();
+						(
+							frame_support::dispatch::extract_actual_weight(&result, &info),
+							result.map(|_| ()).map_err(|e| e.error),
+						)
+					},
+					Err(e) => (Weight::zero(), Err(e.into())),
+				},
+			};
+
+			Self::deposit_event(Event::Fulfilled { id, result });
+			T::WeightInfo::fulfil().saturating_add(used)
+		}
+	}
+}
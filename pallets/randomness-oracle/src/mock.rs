@@ -0,0 +1,107 @@
+//! Test environment for the randomness oracle pallet.
+
+use super::*;
+use crate as pallet_randomness_oracle;
+
+use frame_support::{derive_impl, parameter_types, traits::Hooks};
+use sp_runtime::BuildStorage;
+use std::{cell::RefCell, collections::BTreeMap};
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test {
+		System: frame_system,
+		Balances: pallet_balances,
+		RandomnessOracle: pallet_randomness_oracle,
+	}
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+impl frame_system::Config for Test {
+	type Block = Block;
+	type AccountData = pallet_balances::AccountData<u64>;
+}
+
+#[derive_impl(pallet_balances::config_preludes::TestDefaultConfig)]
+impl pallet_balances::Config for Test {
+	type AccountStore = System;
+}
+
+thread_local! {
+	static PULSES: RefCell<BTreeMap<u64, [u8; 32]>> = RefCell::new(BTreeMap::new());
+}
+
+/// Publish `randomness` as the pulse of `block`.
+pub fn publish(block: u64, randomness: [u8; 32]) {
+	PULSES.with(|pulses| pulses.borrow_mut().insert(block, randomness));
+}
+
+pub struct MockBeacon;
+
+impl RandomnessSource<u64> for MockBeacon {
+	fn latest() -> u64 {
+		PULSES.with(|pulses| pulses.borrow().keys().last().copied().unwrap_or_default())
+	}
+
+	fn randomness_at(block: u64) -> Option<[u8; 32]> {
+		PULSES.with(|pulses| pulses.borrow().get(&block).copied())
+	}
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+impl BenchmarkHelper<u64> for MockBeacon {
+	fn publish(block: u64) {
+		publish(block, [block as u8; 32])
+	}
+}
+
+parameter_types! {
+	pub const RequestDeposit: u64 = 10;
+	pub const FulfilmentFee: Perbill = Perbill::from_percent(20);
+	pub const MaxCallbackWeight: Weight = Weight::from_parts(1_000_000_000, 0);
+}
+
+impl Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type RuntimeCall = RuntimeCall;
+	type Currency = Balances;
+	type Randomness = MockBeacon;
+	type Contracts = ();
+	type RequestDeposit = RequestDeposit;
+	type FulfilmentFee = FulfilmentFee;
+	type FeeDestination = ();
+	type MaxRequestsPerBlock = frame_support::traits::ConstU32<2>;
+	type MaxPendingBlocks = frame_support::traits::ConstU32<3>;
+	type MaxLookahead = frame_support::traits::ConstU64<10>;
+	type MaxSubjectLen = frame_support::traits::ConstU32<32>;
+	type MaxCallLen = frame_support::traits::ConstU32<256>;
+	type MaxCallbackWeight = MaxCallbackWeight;
+	type WeightInfo = ();
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = MockBeacon;
+}
+
+pub const ALICE: u64 = 1;
+pub const BOB: u64 = 2;
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+	pallet_balances::GenesisConfig::<Test> { balances: vec![(ALICE, 100), (BOB, 100)] }
+		.assimilate_storage(&mut t)
+		.unwrap();
+	PULSES.with(|pulses| pulses.borrow_mut().clear());
+
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+/// Advance to block `n`, running the hooks of the oracle.
+pub fn run_to_block(n: u64) {
+	while System::block_number() < n {
+		let next = System::block_number() + 1;
+		System::set_block_number(next);
+		RandomnessOracle::on_initialize(next);
+	}
+}
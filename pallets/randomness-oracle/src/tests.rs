@@ -0,0 +1,228 @@
+//! Tests for the randomness oracle pallet.
+
+use super::*;
+use crate::mock::*;
+
+use frame_support::{assert_noop, assert_ok};
+
+fn remark_callback() -> Callback<Test> {
+	let call = RuntimeCall::System(frame_system::Call::remark_with_event { remark: vec![1, 2] });
+	Callback::Call(call.encode().try_into().unwrap())
+}
+
+fn subject(s: &[u8]) -> BoundedVec<u8, <Test as Config>::MaxSubjectLen> {
+	s.to_vec().try_into().unwrap()
+}
+
+#[test]
+fn request_reserves_the_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(RandomnessOracle::request(
+			RuntimeOrigin::signed(ALICE),
+			5,
+			subject(b"lottery"),
+			remark_callback(),
+		));
+
+		assert_eq!(Balances::reserved_balance(ALICE), 10);
+		assert_eq!(Queue::<Test>::get(5).into_inner(), vec![0]);
+		assert_eq!(PendingBlocks::<Test>::get().into_inner(), vec![5]);
+		assert_eq!(NextRequestId::<Test>::get(), 1);
+		System::assert_last_event(Event::Requested { id: 0, requester: ALICE, block: 5 }.into());
+	});
+}
+
+#[test]
+fn request_checks_its_bounds() {
+	new_test_ext().execute_with(|| {
+		let request = |block| {
+			RandomnessOracle::request(
+				RuntimeOrigin::signed(ALICE),
+				block,
+				subject(b""),
+				remark_callback(),
+			)
+		};
+		assert_noop!(request(1), Error::<Test>::TargetNotInFuture);
+
+		assert_ok!(request(2));
+		assert_ok!(request(2));
+		assert_noop!(request(2), Error::<Test>::QueueFull);
+
+		assert_ok!(request(3));
+		assert_ok!(request(4));
+		assert_noop!(request(5), Error::<Test>::TooManyPendingBlocks);
+
+		assert_noop!(
+			RandomnessOracle::request(
+				RuntimeOrigin::signed(ALICE),
+				5,
+				subject(b""),
+				Callback::Call(vec![0xff; 4].try_into().unwrap()),
+			),
+			Error::<Test>::UndecodableCall
+		);
+		assert_noop!(
+			RandomnessOracle::request(
+				RuntimeOrigin::signed(ALICE),
+				5,
+				subject(b""),
+				Callback::Contract {
+					contract: BOB,
+					selector: [0; 4],
+					gas_limit: Weight::from_parts(u64::MAX, 0),
+				},
+			),
+			Error::<Test>::CallbackTooHeavy
+		);
+	});
+}
+
+#[test]
+fn targets_are_bounded_by_the_lookahead() {
+	new_test_ext().execute_with(|| {
+		let request = |block| {
+			RandomnessOracle::request(
+				RuntimeOrigin::signed(ALICE),
+				block,
+				subject(b""),
+				remark_callback(),
+			)
+		};
+		// A far-future target cannot hold a pending block.
+		assert_noop!(request(12), Error::<Test>::TargetTooFarAhead);
+		assert_ok!(request(11));
+
+		// The lookahead moves on with the chain.
+		run_to_block(2);
+		assert_ok!(request(12));
+	});
+}
+
+#[test]
+fn requests_are_fulfilled_once_the_pulse_is_published() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(RandomnessOracle::request(
+			RuntimeOrigin::signed(ALICE),
+			3,
+			subject(b"lottery"),
+			remark_callback(),
+		));
+
+		// The target block passes without a pulse.
+		run_to_block(4);
+		assert!(Requests::<Test>::contains_key(0));
+
+		publish(3, [7; 32]);
+		run_to_block(5);
+		assert!(!Requests::<Test>::contains_key(0));
+		assert!(PendingBlocks::<Test>::get().is_empty());
+		// The fulfilment fee is kept.
+		assert_eq!(Balances::reserved_balance(ALICE), 0);
+		assert_eq!(Balances::free_balance(ALICE), 98);
+		let hash = sp_io::hashing::blake2_256(&[1, 2]).into();
+		System::assert_has_event(frame_system::Event::Remarked { sender: ALICE, hash }.into());
+		System::assert_last_event(Event::Fulfilled { id: 0, result: Ok(()) }.into());
+		assert_eq!(RandomnessOracle::current_randomness(), None);
+	});
+}
+
+#[test]
+fn contract_callbacks_report_failures() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(RandomnessOracle::request(
+			RuntimeOrigin::signed(ALICE),
+			2,
+			subject(b""),
+			Callback::Contract { contract: BOB, selector: [1; 4], gas_limit: Weight::zero() },
+		));
+
+		publish(2, [7; 32]);
+		run_to_block(2);
+		System::assert_last_event(
+			Event::Fulfilled {
+				id: 0,
+				result: Err(DispatchError::Other("Contract callbacks are not supported")),
+			}
+			.into(),
+		);
+		assert_eq!(Balances::reserved_balance(ALICE), 0);
+		assert_eq!(Balances::free_balance(ALICE), 98);
+	});
+}
+
+#[test]
+fn requests_pay_for_their_callback() {
+	new_test_ext().execute_with(|| {
+		let gas_limit = Weight::from_parts(5_000, 0);
+		let contract = Callback::Contract { contract: BOB, selector: [1; 4], gas_limit };
+		let call = Call::<Test>::request { block: 2, subject: subject(b""), callback: contract };
+		assert_eq!(
+			call.get_dispatch_info().weight,
+			<() as WeightInfo>::request().saturating_add(gas_limit)
+		);
+
+		let remark = RuntimeCall::System(frame_system::Call::remark_with_event { remark: vec![1] });
+		let weight = remark.get_dispatch_info().weight;
+		assert!(weight.any_gt(Weight::zero()));
+		let callback = Callback::Call(remark.encode().try_into().unwrap());
+		assert_eq!(RandomnessOracle::callback_weight(&callback), weight);
+
+		// Undecodable calls are rejected, so they are not charged for.
+		let garbage = Callback::Call(vec![0xff; 4].try_into().unwrap());
+		assert_eq!(RandomnessOracle::callback_weight(&garbage), Weight::zero());
+	});
+}
+
+#[test]
+fn only_the_requester_can_cancel() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(RandomnessOracle::request(
+			RuntimeOrigin::signed(ALICE),
+			5,
+			subject(b""),
+			remark_callback(),
+		));
+
+		assert_noop!(
+			RandomnessOracle::cancel(RuntimeOrigin::signed(BOB), 0),
+			Error::<Test>::NotRequester
+		);
+		assert_noop!(
+			RandomnessOracle::cancel(RuntimeOrigin::signed(ALICE), 1),
+			Error::<Test>::UnknownRequest
+		);
+
+		assert_ok!(RandomnessOracle::cancel(RuntimeOrigin::signed(ALICE), 0));
+		assert_eq!(Balances::reserved_balance(ALICE), 0);
+		assert_eq!(Balances::free_balance(ALICE), 100);
+		assert!(Queue::<Test>::get(5).is_empty());
+		assert!(PendingBlocks::<Test>::get().is_empty());
+		System::assert_last_event(Event::Cancelled { id: 0 }.into());
+	});
+}
+
+#[test]
+fn subjects_separate_randomness() {
+	let randomness = [7u8; 32];
+	assert_ne!(subject_randomness(&randomness, b"a"), subject_randomness(&randomness, b"b"));
+}
+
+#[test]
+fn requests_for_missing_pulses_expire() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(RandomnessOracle::request(
+			RuntimeOrigin::signed(ALICE),
+			3,
+			subject(b""),
+			remark_callback(),
+		));
+
+		RandomnessOracle::expire(3);
+		assert!(!Requests::<Test>::contains_key(0));
+		assert!(PendingBlocks::<Test>::get().is_empty());
+		assert_eq!(Balances::reserved_balance(ALICE), 0);
+		assert_eq!(Balances::free_balance(ALICE), 100);
+		System::assert_last_event(Event::Expired { id: 0 }.into());
+	});
+}
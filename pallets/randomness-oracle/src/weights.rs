@@ -0,0 +1,89 @@
+//! Weights for `pallet_randomness_oracle`.
+//!
+//! The benchmarks of the pallet are in `benchmarking.rs`. These values are estimated from the
+//! storage accesses of the benchmarks, and are to be replaced by the output of
+//! `benchmark pallet --pallet pallet_randomness_oracle` on reference hardware, see the README.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+#![allow(missing_docs)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use core::marker::PhantomData;
+
+/// Weight functions needed for `pallet_randomness_oracle`.
+pub trait WeightInfo {
+	fn request() -> Weight;
+	fn cancel() -> Weight;
+	fn fulfil() -> Weight;
+	fn on_initialize(p: u32, ) -> Weight;
+}
+
+/// Weights for `pallet_randomness_oracle` using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: `RandomnessOracle::NextRequestId` (r:1 w:1)
+	/// Storage: `RandomnessOracle::Queue` (r:1 w:1)
+	/// Storage: `RandomnessOracle::PendingBlocks` (r:1 w:1)
+	/// Storage: `System::Account` (r:1 w:1)
+	/// Storage: `RandomnessOracle::Requests` (r:0 w:1)
+	fn request() -> Weight {
+		Weight::from_parts(40_000_000, 0)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(5_u64))
+	}
+	/// Storage: `RandomnessOracle::Requests` (r:1 w:1)
+	/// Storage: `RandomnessOracle::Queue` (r:1 w:1)
+	/// Storage: `RandomnessOracle::PendingBlocks` (r:1 w:1)
+	/// Storage: `System::Account` (r:1 w:1)
+	fn cancel() -> Weight {
+		Weight::from_parts(35_000_000, 0)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+	}
+	/// Storage: `RandomnessOracle::Requests` (r:1 w:1)
+	/// Storage: `System::Account` (r:2 w:2)
+	/// Storage: `RandomnessOracle::CurrentRandomness` (r:0 w:2)
+	/// The weight of the callback itself is added on top.
+	fn fulfil() -> Weight {
+		Weight::from_parts(30_000_000, 0)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(5_u64))
+	}
+	/// Storage: `RandomnessOracle::PendingBlocks` (r:1 w:1)
+	/// Storage: `RandomnessOracle::Queue` (r:1 w:1)
+	/// Storage: `RandomnessBeacon::Pulses` (r:p w:0)
+	/// The range of component `p` is `[0, 64]`, the number of pending target blocks.
+	fn on_initialize(p: u32, ) -> Weight {
+		Weight::from_parts(5_000_000, 0)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().reads(p.into()))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+}
+
+// For backwards compatibility and tests.
+impl WeightInfo for () {
+	fn request() -> Weight {
+		Weight::from_parts(40_000_000, 0)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().writes(5_u64))
+	}
+	fn cancel() -> Weight {
+		Weight::from_parts(35_000_000, 0)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
+	}
+	fn fulfil() -> Weight {
+		Weight::from_parts(30_000_000, 0)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(5_u64))
+	}
+	fn on_initialize(p: u32, ) -> Weight {
+		Weight::from_parts(5_000_000, 0)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().reads(p.into()))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+}
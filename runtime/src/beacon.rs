@@ -0,0 +1,596 @@
+//! Helpers to read and verify randomness beacon pulses.
+//!
+//! Everything that needs to check a pulse (runtime APIs, chain extensions, transaction
+//! validation) should go through this module so that there is a single definition of what a
+//! valid pulse is.
+
+use polkadot_sdk::*;
+
+use ark_ff::{Field, Zero};
+use ark_serialize::CanonicalDeserialize;
+use beefy_primitives::{known_payloads, Commitment, Payload, ValidatorSetId};
+use codec::Encode;
+use etf_crypto_primitives::{encryption::tlock::TLECiphertext, ibe::fullident::Identity};
+use node_primitives::BlockNumber;
+use sp_runtime::{
+	traits::ValidateUnsigned,
+	transaction_validity::{
+		InvalidTransaction, TransactionLongevity, TransactionPriority, TransactionSource,
+		TransactionValidity, TransactionValidityError, ValidTransaction,
+	},
+};
+use sp_std::prelude::*;
+use w3f_bls::{DoublePublicKey, EngineBLS, Message, SerializableToBytes, Signature, TinyBLS377};
+
+use crate::{resharing, BeaconLiveness, Runtime, RuntimeCall, System};
+
+/// A pulse as stored by the randomness beacon pallet.
+pub type Pulse = pallet_randomness_beacon::Pulse<BlockNumber>;
+
+/// The pairing engine used by the ETF authorities, on BLS12-377 like the BEEFY keys.
+pub type BeaconEngine = TinyBLS377;
+
+/// Decode a serialized round public key.
+fn decode_round_pubkey(mut bytes: &[u8]) -> Option<DoublePublicKey<BeaconEngine>> {
+	DoublePublicKey::<BeaconEngine>::deserialize_compressed(&mut bytes).ok()
+}
+
+/// The BEEFY commitment the ETF authorities sign for `block`.
+///
+/// The BEEFY-ETF gadget signs the encoded commitment of each block with an empty
+/// `ETF_SIGNATURE` payload, under the id of the authority set signing it, and
+/// `pallet_randomness_beacon` verifies the pulses it writes against the same commitment.
+pub fn pulse_commitment(
+	block: BlockNumber,
+	validator_set_id: ValidatorSetId,
+) -> Commitment<BlockNumber> {
+	let payload = Payload::from_single_entry(known_payloads::ETF_SIGNATURE, Vec::new());
+	Commitment { payload, block_number: block, validator_set_id }
+}
+
+/// The message signed by the authority set `validator_set_id` for `block`.
+pub fn pulse_message(block: BlockNumber, validator_set_id: ValidatorSetId) -> Message {
+	Message::new(b"", &pulse_commitment(block, validator_set_id).encode())
+}
+
+/// The identity a message is timelocked to, so that the pulse signed by the authority set
+/// `validator_set_id` for `block` decrypts it.
+pub fn pulse_identity(block: BlockNumber, validator_set_id: ValidatorSetId) -> Identity {
+	Identity::new(b"", vec![pulse_commitment(block, validator_set_id).encode()])
+}
+
+/// The id of the current BEEFY-ETF authority set.
+pub fn validator_set_id() -> ValidatorSetId {
+	pallet_beefy_etf::ValidatorSetId::<Runtime>::get()
+}
+
+/// The id of the BEEFY-ETF authority set that signs the pulse of `block`, i.e. the set that was
+/// active at `block`.
+///
+/// Blocks after the current one resolve to the current set, which may still change before they
+/// are imported.
+pub fn authority_set_at(block: BlockNumber) -> Option<ValidatorSetId> {
+	pallet_beacon_backfill::Pallet::<Runtime>::authority_set_at(block)
+}
+
+/// Returns the pulse published for `block`, if any.
+pub fn pulse(block: BlockNumber) -> Option<Pulse> {
+	pallet_randomness_beacon::Pulses::<Runtime>::get(block)
+}
+
+/// Returns the signature of the pulse of `block`, whether the beacon published it or it was
+/// backfilled later.
+pub fn signature_at(block: BlockNumber) -> Option<Vec<u8>> {
+	pulse(block)
+		.map(|pulse| pulse.body.signature.into_inner())
+		.or_else(|| pallet_beacon_backfill::Pallet::<Runtime>::backfilled(block))
+}
+
+/// Returns 32 bytes of randomness derived from the pulse of `block`, published or backfilled.
+///
+/// Published pulses carry their randomness, backfilled ones only the signature, which is
+/// hashed instead.
+pub fn randomness_at(block: BlockNumber) -> Option<[u8; 32]> {
+	match pulse(block) {
+		Some(pulse) => Some(sp_io::hashing::blake2_256(&pulse.body.randomness.into_inner())),
+		None => pallet_beacon_backfill::Pallet::<Runtime>::backfilled(block)
+			.map(|signature| sp_io::hashing::blake2_256(&signature)),
+	}
+}
+
+/// Returns the block whose pulse `call` publishes or backfills, if any.
+pub fn pulse_written_by(call: &RuntimeCall) -> Option<BlockNumber> {
+	match call {
+		RuntimeCall::RandomnessBeacon(pallet_randomness_beacon::Call::write_pulse {
+			block_number,
+			..
+		}) => Some(*block_number),
+		RuntimeCall::BeaconBackfill(pallet_beacon_backfill::Call::backfill { block, .. }) =>
+			Some(*block),
+		_ => None,
+	}
+}
+
+/// Returns the serialized round public key.
+pub fn round_pubkey() -> Vec<u8> {
+	pallet_etf::RoundPublic::<Runtime>::get().into_inner()
+}
+
+/// Verify an aggregated beacon signature for `block` against the round public key.
+///
+/// The signature has to be signed by the authority set that was active at `block`, see
+/// [`authority_set_at`]. Returns `false` if the set is not known, or if either the round public
+/// key or the signature cannot be decoded.
+pub fn verify_signature(block: BlockNumber, signature: &[u8]) -> bool {
+	authority_set_at(block)
+		.map_or(false, |set_id| verify_signature_by(block, set_id, signature))
+}
+
+/// Verify an aggregated beacon signature for `block`, signed by the authority set
+/// `validator_set_id`, against the round public key.
+pub fn verify_signature_by(
+	block: BlockNumber,
+	validator_set_id: ValidatorSetId,
+	signature: &[u8],
+) -> bool {
+	let Some(public) = decode_round_pubkey(&round_pubkey()) else { return false };
+	let Ok(signature) = Signature::<BeaconEngine>::from_bytes(signature) else { return false };
+
+	let message = pulse_message(block, validator_set_id);
+	signature.verify(&message, &w3f_bls::single::PublicKey(public.1))
+}
+
+/// Why a timelock ciphertext could not be decrypted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecryptError {
+	/// No pulse has been published for the target block yet.
+	NoPulse,
+	/// The ciphertext is malformed or was not encrypted for the target block.
+	InvalidCiphertext,
+}
+
+/// Decrypt a timelock `ciphertext` for `block` with the pulse published or backfilled for it.
+pub fn decrypt(block: BlockNumber, ciphertext: &[u8]) -> Result<Vec<u8>, DecryptError> {
+	let signature = signature_at(block).ok_or(DecryptError::NoPulse)?;
+	let signature = Signature::<BeaconEngine>::from_bytes(&signature)
+		.map_err(|_| DecryptError::InvalidCiphertext)?;
+	let ciphertext = TLECiphertext::<BeaconEngine>::deserialize_compressed(&mut &ciphertext[..])
+		.map_err(|_| DecryptError::InvalidCiphertext)?;
+	ciphertext.tld(signature.0).map_err(|_| DecryptError::InvalidCiphertext)
+}
+
+/// Why a pulse submission is invalid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PulseError {
+	/// The pulse of the block has already been published.
+	AlreadyPublished,
+	/// The block has not been imported yet.
+	Future,
+	/// There are no signatures, only withheld ones, or more signatures than BEEFY-ETF
+	/// authorities.
+	Malformed,
+	/// Fewer than a threshold of the signatures verify against the commitments to the shares
+	/// of their authorities, or they do not recover a pulse signed under the round public key.
+	BadSignature,
+}
+
+impl From<PulseError> for TransactionValidityError {
+	fn from(error: PulseError) -> Self {
+		match error {
+			PulseError::AlreadyPublished => InvalidTransaction::Stale,
+			PulseError::Future => InvalidTransaction::Future,
+			PulseError::Malformed => InvalidTransaction::Call,
+			PulseError::BadSignature => InvalidTransaction::BadProof,
+		}
+		.into()
+	}
+}
+
+/// Check the target block and the shape of a pulse submission.
+pub fn check_pulse_submission(
+	block: BlockNumber,
+	signatures: &[Vec<u8>],
+) -> Result<(), PulseError> {
+	if block > System::block_number() {
+		return Err(PulseError::Future)
+	}
+	if pulse(block).is_some() {
+		return Err(PulseError::AlreadyPublished)
+	}
+	let authorities = pallet_beefy_etf::Authorities::<Runtime>::decode_len().unwrap_or_default();
+	if signers(signatures).next().is_none() || signatures.len() > authorities {
+		return Err(PulseError::Malformed)
+	}
+	Ok(())
+}
+
+/// Check a pulse submission and verify the signature it recovers against the round public key.
+///
+/// Every signature is verified against the commitment to the share of its authority, and the
+/// pulse signature is recovered from the first threshold of the valid ones, so invalid
+/// signatures cannot keep the others from publishing the pulse. Returns the indices of the
+/// authorities whose signatures are valid.
+///
+/// The signatures are checked against the shares of the current authorities, so a pulse signed
+/// with shares that were reshared since cannot be published, but can still be backfilled.
+pub fn verify_pulse_submission(
+	block: BlockNumber,
+	signatures: &[Vec<u8>],
+) -> Result<Vec<u32>, PulseError> {
+	check_pulse_submission(block, signatures)?;
+	let set_id = authority_set_at(block).ok_or(PulseError::BadSignature)?;
+	let valid = valid_signers(block, set_id, signatures);
+	let threshold = pallet_etf_resharing::Pallet::<Runtime>::threshold() as usize;
+	if threshold == 0 || valid.len() < threshold {
+		return Err(PulseError::BadSignature)
+	}
+	let signature =
+		aggregate_signatures(signatures, &valid[..threshold]).ok_or(PulseError::BadSignature)?;
+	match verify_signature_by(block, set_id, &signature) {
+		true => Ok(valid),
+		false => Err(PulseError::BadSignature),
+	}
+}
+
+/// The indices of the BEEFY-ETF authorities that contributed a signature to a pulse submission.
+///
+/// The signature of an authority that withheld its share is left empty.
+pub fn signers(signatures: &[Vec<u8>]) -> impl Iterator<Item = u32> + '_ {
+	signatures
+		.iter()
+		.enumerate()
+		.filter(|(_, signature)| !signature.is_empty())
+		.map(|(index, _)| index as u32)
+}
+
+/// The indices of the BEEFY-ETF authorities whose signature of the pulse of `block` by the
+/// authority set `validator_set_id` verifies against the commitment to their share.
+pub fn valid_signers(
+	block: BlockNumber,
+	validator_set_id: ValidatorSetId,
+	signatures: &[Vec<u8>],
+) -> Vec<u32> {
+	let commitments = pallet_etf::Commitments::<Runtime>::get();
+	let message = pulse_message(block, validator_set_id);
+	signers(signatures)
+		.filter(|&j| {
+			let share = commitments
+				.get(j as usize)
+				.and_then(resharing::decode_commitment::<BeaconEngine>);
+			let signature = Signature::<BeaconEngine>::from_bytes(&signatures[j as usize]).ok();
+			share.zip(signature).map_or(false, |(share, signature)| {
+				signature.verify(&message, &w3f_bls::single::PublicKey(share.1))
+			})
+		})
+		.collect()
+}
+
+/// Recover the pulse signature from the signatures of the BEEFY-ETF authorities at `signers`,
+/// out of the signatures of the whole set.
+///
+/// Each signature is the evaluation at the index of its authority, so any threshold of valid
+/// signatures interpolates to the pulse signature.
+pub fn aggregate_signatures(signatures: &[Vec<u8>], signers: &[u32]) -> Option<Vec<u8>> {
+	let indices = signers.iter().map(|&j| j as usize).collect::<Vec<_>>();
+	let mut point = <BeaconEngine as EngineBLS>::SignatureGroup::zero();
+	for &j in &indices {
+		let signature = Signature::<BeaconEngine>::from_bytes(signatures.get(j)?).ok()?;
+		point += signature.0 * lagrange_at_zero::<BeaconEngine>(j, &indices);
+	}
+	Some(Signature::<BeaconEngine>(point).to_bytes())
+}
+
+/// The Lagrange coefficient of the share at `j` when interpolating `indices` at zero.
+///
+/// The share of the authority at index `j` is the evaluation at `j + 1`.
+pub(crate) fn lagrange_at_zero<E: EngineBLS>(j: usize, indices: &[usize]) -> E::Scalar {
+	let x = |index: usize| E::Scalar::from((index + 1) as u64);
+	indices.iter().filter(|&&m| m != j).fold(E::Scalar::from(1u64), |acc, &m| {
+		acc * x(m) * (x(m) - x(j)).inverse().expect("share indices are distinct; qed")
+	})
+}
+
+/// The priority of pulse submissions in the transaction pool.
+pub const PULSE_PRIORITY: TransactionPriority = TransactionPriority::MAX / 2;
+
+/// The number of blocks a pulse submission stays valid in the transaction pool.
+pub const PULSE_LONGEVITY: TransactionLongevity = 64;
+
+/// Validates unsigned pulse submissions before they enter the transaction pool.
+///
+/// `pallet_randomness_beacon` accepts `write_pulse` without checking who submitted it and has
+/// no `ValidateUnsigned` of its own, so the runtime validates the unsigned transactions with
+/// this type instead, see [`crate::Executive`]: a submission has to target an imported block
+/// without a pulse, carry at most one signature per BEEFY-ETF authority and recover a signature
+/// valid under the round public key from a threshold of valid signatures, see
+/// [`verify_pulse_submission`]. Submissions for the same block are deduplicated by their
+/// `provides` tag. Every other unsigned call is validated by its pallet.
+///
+/// Submissions are verified again by `pallet_beacon_liveness` when they are included in a
+/// block, which charges the benchmarked weight of the verification and notes the authorities
+/// whose signatures are valid, to report those that withhold their shares. The pallet only
+/// credits them if the dispatch of the submission published the pulse.
+pub struct ValidatePulse;
+
+impl ValidatePulse {
+	fn pulse_call(call: &RuntimeCall) -> Option<(BlockNumber, &[Vec<u8>])> {
+		match call {
+			RuntimeCall::RandomnessBeacon(pallet_randomness_beacon::Call::write_pulse {
+				signatures,
+				block_number,
+			}) => Some((*block_number, &signatures[..])),
+			_ => None,
+		}
+	}
+}
+
+impl ValidateUnsigned for ValidatePulse {
+	type Call = RuntimeCall;
+
+	fn validate_unsigned(source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+		let Some((block, signatures)) = Self::pulse_call(call) else {
+			return <Runtime as ValidateUnsigned>::validate_unsigned(source, call)
+		};
+		verify_pulse_submission(block, signatures)?;
+
+		ValidTransaction::with_tag_prefix("RandomnessBeaconPulse")
+			.priority(PULSE_PRIORITY)
+			.and_provides(block)
+			.longevity(PULSE_LONGEVITY)
+			.propagate(true)
+			.build()
+	}
+
+	fn pre_dispatch(call: &Self::Call) -> Result<(), TransactionValidityError> {
+		// Only a submission that writes the pulse may be credited to its signers, so it is
+		// verified again before they are noted.
+		let Some((block, signatures)) = Self::pulse_call(call) else {
+			return <Runtime as ValidateUnsigned>::pre_dispatch(call)
+		};
+		BeaconLiveness::verify_and_note_pulse(block, signatures)?;
+		Ok(())
+	}
+}
+
+impl pallet_beacon_liveness::VerifyPulse<BlockNumber> for ValidatePulse {
+	type Error = PulseError;
+
+	fn verify_pulse(block: BlockNumber, signatures: &[Vec<u8>]) -> Result<Vec<u32>, PulseError> {
+		verify_pulse_submission(block, signatures)
+	}
+}
+
+/// Publishes pulses signed with a known round secret, for the benchmarks of the pallets that
+/// read the beacon.
+#[cfg(feature = "runtime-benchmarks")]
+pub mod benchmarking {
+	use super::*;
+	use ark_serialize::CanonicalSerialize;
+	use frame_support::BoundedVec;
+	use sp_runtime::traits::Dispatchable;
+
+	use crate::RuntimeOrigin;
+
+	fn keypair() -> w3f_bls::KeypairVT<BeaconEngine> {
+		let secret = w3f_bls::SecretKeyVT::<BeaconEngine>(7u64.into());
+		w3f_bls::KeypairVT::<BeaconEngine> { public: secret.into_public(), secret }
+	}
+
+	/// Make the round secret of the benchmarks the one of the beacon.
+	fn set_round_key() {
+		let keypair = keypair();
+		let public =
+			DoublePublicKey(keypair.into_public_key_in_signature_group().0, keypair.public.0);
+		let mut round_pubkey = Vec::new();
+		public
+			.serialize_compressed(&mut round_pubkey)
+			.expect("serializing into a vector does not fail; qed");
+		pallet_etf::RoundPublic::<Runtime>::put(BoundedVec::truncate_from(round_pubkey));
+	}
+
+	/// The aggregated signature of the pulse of `block` by the authority set `set_id`, valid
+	/// under the round public key of the benchmarks.
+	pub fn sign(block: BlockNumber, set_id: ValidatorSetId) -> Vec<u8> {
+		set_round_key();
+		keypair().sign(&pulse_message(block, set_id)).to_bytes()
+	}
+
+	/// Make `signers` authorities the BEEFY-ETF authorities, each holding a share of the round
+	/// secret of the benchmarks, and return their signatures of the pulse of `block`.
+	pub fn sign_pulse(block: BlockNumber, signers: u32) -> Vec<Vec<u8>> {
+		type Scalar = <BeaconEngine as EngineBLS>::Scalar;
+		set_round_key();
+		// The round secret is the constant term of a polynomial of degree less than the
+		// threshold, the share of the authority at `j` is the evaluation at `j + 1`.
+		let threshold = pallet_etf_resharing::Pallet::<Runtime>::threshold_for(signers);
+		let coefficients = core::iter::once(7)
+			.chain(1..threshold as u64)
+			.map(Scalar::from)
+			.collect::<Vec<_>>();
+		let shares = (1..=signers as u64)
+			.map(|x| {
+				let x = Scalar::from(x);
+				coefficients.iter().rev().fold(Scalar::zero(), |acc, c| acc * x + c)
+			})
+			.collect::<Vec<_>>();
+
+		let commitments = shares
+			.iter()
+			.map(|share| resharing::commit::<BeaconEngine>(*share))
+			.collect::<Vec<_>>();
+		pallet_beefy_etf::Authorities::<Runtime>::put(BoundedVec::truncate_from(
+			commitments.clone(),
+		));
+		pallet_etf::Commitments::<Runtime>::put(BoundedVec::truncate_from(commitments));
+
+		let set_id = authority_set_at(block).unwrap_or_else(validator_set_id);
+		let message = pulse_message(block, set_id);
+		shares
+			.into_iter()
+			.map(|share| {
+				let secret = w3f_bls::SecretKeyVT::<BeaconEngine>(share);
+				w3f_bls::KeypairVT::<BeaconEngine> { public: secret.into_public(), secret }
+					.sign(&message)
+					.to_bytes()
+			})
+			.collect()
+	}
+
+	/// Publish the pulse of `block`, moving on to `block` first if it is ahead.
+	pub fn publish(block: BlockNumber) {
+		if System::block_number() < block {
+			System::set_block_number(block);
+		}
+		let set_id = authority_set_at(block).unwrap_or_else(validator_set_id);
+		let call = RuntimeCall::RandomnessBeacon(pallet_randomness_beacon::Call::write_pulse {
+			signatures: vec![sign(block, set_id)],
+			block_number: block,
+		});
+		if let Err(e) = call.dispatch(RuntimeOrigin::none()) {
+			log::error!(target: "runtime::beacon", "Failed to publish pulse {}: {:?}", block, e);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{impls::BeaconPulses, BeaconBackfill};
+	use ark_serialize::CanonicalSerialize;
+	use frame_support::BoundedVec;
+	use pallet_beacon_backfill::PulseSource;
+	use sp_runtime::BuildStorage;
+
+	/// Run `test` with the round secret held by `keypair` alone.
+	fn with_round_key(test: impl FnOnce(&w3f_bls::KeypairVT<BeaconEngine>)) {
+		let secret = w3f_bls::SecretKeyVT::<BeaconEngine>(7u64.into());
+		let keypair = w3f_bls::KeypairVT::<BeaconEngine> { public: secret.into_public(), secret };
+		let public =
+			DoublePublicKey(keypair.into_public_key_in_signature_group().0, keypair.public.0);
+		let mut round_pubkey = Vec::new();
+		public.serialize_compressed(&mut round_pubkey).unwrap();
+
+		let mut ext: sp_io::TestExternalities =
+			frame_system::GenesisConfig::<Runtime>::default().build_storage().unwrap().into();
+		ext.execute_with(|| {
+			pallet_etf::RoundPublic::<Runtime>::put(BoundedVec::truncate_from(round_pubkey));
+			test(&keypair)
+		});
+	}
+
+	/// Rotate to the authority set `set_id` at the current block, as `pallet_beefy_etf` and
+	/// `OnNewEtfValidatorSet` do at a session change.
+	fn rotate_authority_set(set_id: ValidatorSetId) {
+		pallet_beefy_etf::ValidatorSetId::<Runtime>::put(set_id);
+		BeaconBackfill::note_authority_set(set_id);
+	}
+
+	#[test]
+	fn pulses_are_verified_against_the_set_of_their_block_across_rotations() {
+		with_round_key(|keypair| {
+			let sign = |block, set_id| keypair.sign(&pulse_message(block, set_id)).to_bytes();
+			System::set_block_number(10);
+			rotate_authority_set(1);
+			System::set_block_number(12);
+
+			assert_eq!(authority_set_at(5), Some(0));
+			assert_eq!(authority_set_at(10), Some(1));
+			assert_eq!(validator_set_id(), 1);
+
+			// Pulses of the previous session stay valid, so they can still be backfilled.
+			assert!(verify_signature(5, &sign(5, 0)));
+			assert!(!verify_signature(5, &sign(5, 1)));
+			assert!(BeaconPulses::verify(5, 0, &sign(5, 0)));
+			// The new set signs from the block it took over at.
+			assert!(verify_signature(10, &sign(10, 1)));
+			assert!(verify_signature(12, &sign(12, 1)));
+			assert!(!verify_signature(12, &sign(12, 0)));
+		});
+	}
+
+	#[test]
+	fn pulses_are_recovered_from_a_threshold_of_valid_signatures() {
+		type Scalar = <BeaconEngine as EngineBLS>::Scalar;
+		let sign = |secret: Scalar, block| {
+			let secret = w3f_bls::SecretKeyVT::<BeaconEngine>(secret);
+			let public = secret.into_public();
+			w3f_bls::KeypairVT::<BeaconEngine> { public, secret }
+				.sign(&pulse_message(block, 0))
+				.to_bytes()
+		};
+		// The round secret 7 is shared on `7 + 5x`, the share of the authority at `j` is the
+		// evaluation at `j + 1`.
+		let share = |j: u64| Scalar::from(7 + 5 * (j + 1));
+
+		with_round_key(|_| {
+			let commitments = (0..3)
+				.map(|j| resharing::commit::<BeaconEngine>(share(j)))
+				.collect::<Vec<_>>();
+			pallet_beefy_etf::Authorities::<Runtime>::put(BoundedVec::truncate_from(
+				commitments.clone(),
+			));
+			pallet_etf::Commitments::<Runtime>::put(BoundedVec::truncate_from(commitments));
+			pallet_etf_resharing::Threshold::<Runtime>::put(2);
+			System::set_block_number(3);
+
+			// The authority at 1 signs with a share it does not hold.
+			let forged = sign(Scalar::from(1u64), 2);
+			let signatures = vec![sign(share(0), 2), forged.clone(), sign(share(2), 2)];
+			assert_eq!(valid_signers(2, 0, &signatures), vec![0, 2]);
+			assert_eq!(verify_pulse_submission(2, &signatures), Ok(vec![0, 2]));
+			let signature = aggregate_signatures(&signatures, &[0, 2]).unwrap();
+			assert!(verify_signature(2, &signature));
+			// Interpolating every signature would not recover the pulse.
+			let signature = aggregate_signatures(&signatures, &[0, 1, 2]).unwrap();
+			assert!(!verify_signature(2, &signature));
+
+			let signatures = vec![sign(share(0), 2), forged, Vec::new()];
+			assert_eq!(verify_pulse_submission(2, &signatures), Err(PulseError::BadSignature));
+		});
+	}
+
+	#[test]
+	fn lagrange_coefficients_interpolate_constants() {
+		type Scalar = <TinyBLS377 as EngineBLS>::Scalar;
+		let indices = [0, 2, 3];
+		let sum = indices
+			.iter()
+			.fold(Scalar::zero(), |acc, j| acc + lagrange_at_zero::<TinyBLS377>(*j, &indices));
+		assert_eq!(sum, Scalar::from(1u64));
+	}
+
+	#[test]
+	fn withheld_signatures_are_not_signers() {
+		let signatures = vec![vec![1], vec![], vec![2], vec![]];
+		assert_eq!(signers(&signatures).collect::<Vec<_>>(), vec![0, 2]);
+	}
+
+	#[test]
+	fn published_and_backfilled_pulses_are_recognised() {
+		let published = RuntimeCall::RandomnessBeacon(pallet_randomness_beacon::Call::write_pulse {
+			signatures: vec![vec![1; 48]],
+			block_number: 7,
+		});
+		let backfilled = RuntimeCall::BeaconBackfill(pallet_beacon_backfill::Call::backfill {
+			block: 5,
+			signature: vec![2; 48].try_into().unwrap(),
+		});
+		let remark = RuntimeCall::System(frame_system::Call::remark { remark: vec![1, 2, 3] });
+
+		assert_eq!(pulse_written_by(&published), Some(7));
+		assert_eq!(pulse_written_by(&backfilled), Some(5));
+		assert_eq!(pulse_written_by(&remark), None);
+	}
+
+	#[test]
+	fn pulse_errors_map_to_invalid_transactions() {
+		assert_eq!(
+			TransactionValidityError::from(PulseError::AlreadyPublished),
+			InvalidTransaction::Stale.into()
+		);
+		assert_eq!(
+			TransactionValidityError::from(PulseError::BadSignature),
+			InvalidTransaction::BadProof.into()
+		);
+	}
+}
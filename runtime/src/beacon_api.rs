@@ -0,0 +1,57 @@
+//! Runtime API definition for the randomness beacon.
+
+use polkadot_sdk::*;
+
+use codec::Codec;
+use sp_std::vec::Vec;
+
+pub use pallet_beacon_backfill::PulseStatus;
+
+sp_api::decl_runtime_apis! {
+	pub trait RandomnessBeaconApi<BlockNumber, Pulse>
+	where
+		BlockNumber: Codec,
+		Pulse: Codec,
+	{
+		/// Returns the pulse published for the given block, if any.
+		fn pulse(block: BlockNumber) -> Option<Pulse>;
+
+		/// Returns the latest block number for which a pulse was published.
+		fn latest_height() -> BlockNumber;
+
+		/// Returns the serialized round public key of the ETF authorities.
+		fn round_pubkey() -> Vec<u8>;
+
+		/// Returns whether `signature` is a valid beacon signature for `block` under the
+		/// round public key, signed by the authority set that was active at `block`.
+		fn verify_pulse(block: BlockNumber, signature: Vec<u8>) -> bool;
+
+		/// Returns the id of the BEEFY-ETF authority set that signs the pulse of the given
+		/// block, i.e. the set that was active at the block.
+		///
+		/// Blocks after the current one resolve to the current set, which may still change
+		/// before they are imported.
+		fn authority_set_at(block: BlockNumber) -> Option<u64>;
+
+		/// Returns the SCALE encoded commitments to the shares of the round secret held by
+		/// the current authority set, indexed like the set, and the number of shares that
+		/// recover a pulse.
+		///
+		/// Called at a block, these are the keys the pulse of the block is signed with.
+		fn share_commitments() -> (Vec<Vec<u8>>, u32);
+
+		/// Returns the signature of the pulse of the given block, published or backfilled.
+		fn signature_at(block: BlockNumber) -> Option<Vec<u8>>;
+
+		/// Returns the randomness the runtime serves for the given block, derived from its
+		/// pulse whether published or backfilled.
+		fn randomness_at(block: BlockNumber) -> Option<[u8; 32]>;
+
+		/// Returns whether the pulse of the given block is published, backfilled, can still be
+		/// backfilled or is permanently missing.
+		///
+		/// Timelocked calls and randomness requests targeting a block whose pulse is missing
+		/// are dropped when its backfill window closes.
+		fn pulse_status(block: BlockNumber) -> PulseStatus<BlockNumber>;
+	}
+}
@@ -0,0 +1,276 @@
+//! The chain extension giving ink! contracts access to the randomness beacon and to timelock
+//! decryption.
+//!
+//! | func id | input                               | output        |
+//! |---------|-------------------------------------|---------------|
+//! | 1101    | -                                   | `[u8; 32]`    |
+//! | 1102    | `BlockNumber`                       | `[u8; 32]`    |
+//! | 1103    | `Vec<u8>` subject                   | `[u8; 32]`    |
+//! | 1104    | `(Vec<u8>, u64)` subject, bound     | `u64`         |
+//! | 1105    | -                                   | `BlockNumber` |
+//! | 1106    | `(BlockNumber, Vec<u8>)` signature  | `bool`        |
+//! | 1107    | `(BlockNumber, Vec<u8>)` ciphertext | `Vec<u8>`     |
+//! | 1108    | see [`RandomnessRequest`]           | `RequestId`   |
+//!
+//! Failures a contract can recover from are reported as [`RandomnessError`] status codes, the
+//! output buffer is only written on success. The randomness returned by 1101, 1103 and 1104 is
+//! derived from the latest pulse exactly as 1102 derives it for that block, and all four fail
+//! with [`RandomnessError::NoPulse`] until there is a pulse.
+//!
+//! Requests made with 1108 are served by `pallet_randomness_oracle`, which calls back the
+//! requesting contract once the pulse of the target block is published.
+
+use polkadot_sdk::*;
+
+use codec::{Decode, Encode};
+use frame_support::{traits::Get, weights::Weight};
+use node_primitives::BlockNumber;
+use pallet_contracts::chain_extension::{
+	ChainExtension, Environment, Ext, InitState, RetVal, SysConfig,
+};
+use pallet_randomness_oracle::{Callback, WeightInfo as _};
+use sp_core::crypto::UncheckedFrom;
+use sp_runtime::DispatchError;
+use sp_std::prelude::*;
+
+use crate::{
+	beacon::{self, DecryptError},
+	AccountId, RandomnessBeacon, RandomnessOracle, Runtime,
+};
+
+/// The latest randomness.
+pub const RANDOM: u16 = 1101;
+/// The randomness of a past block, whether its pulse was published or backfilled.
+pub const RANDOM_AT: u16 = 1102;
+/// The latest randomness, separated by a subject.
+pub const RANDOM_FOR_SUBJECT: u16 = 1103;
+/// A uniform integer below a bound, derived from the latest randomness and a subject.
+pub const RANDOM_BELOW: u16 = 1104;
+/// The latest block with a pulse.
+pub const HEIGHT: u16 = 1105;
+/// Whether a signature is the pulse of a block.
+pub const VERIFY_PULSE: u16 = 1106;
+/// Decrypt a timelock ciphertext with the pulse of its target block.
+pub const DECRYPT: u16 = 1107;
+/// Request the randomness of a future block, delivered by calling back the contract.
+pub const REQUEST_RANDOMNESS: u16 = 1108;
+
+/// The weight of hashing one byte of a subject.
+const WEIGHT_PER_SUBJECT_BYTE: Weight = Weight::from_parts(2_000, 0);
+/// The weight of hashing the randomness together with the subject.
+const SUBJECT_HASH_WEIGHT: Weight = Weight::from_parts(200_000, 0);
+/// The weight of verifying a pulse signature, two pairings.
+const VERIFY_PULSE_WEIGHT: Weight = Weight::from_parts(600_000_000, 0);
+/// The weight of a timelock decryption, as charged by `pallet_murmur`.
+const DECRYPT_WEIGHT: Weight = Weight::from_parts(900_000_000, 0);
+/// The weight of decrypting one byte of a ciphertext.
+const WEIGHT_PER_CIPHERTEXT_BYTE: Weight = Weight::from_parts(2_000, 0);
+
+/// The status codes returned by the extension.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Encode, Decode)]
+#[repr(u32)]
+pub enum RandomnessError {
+	/// The call succeeded.
+	Success = 0,
+	/// No pulse was published or backfilled for the requested block.
+	NoPulse = 1,
+	/// The bound of a uniform integer must not be zero.
+	ZeroBound = 2,
+	/// The func id is not implemented.
+	UnknownFunction = 3,
+	/// The ciphertext is malformed or was not encrypted for the requested block.
+	DecryptionFailed = 4,
+	/// The randomness oracle rejected the request.
+	RequestFailed = 5,
+}
+
+impl From<RandomnessError> for RetVal {
+	fn from(error: RandomnessError) -> Self {
+		RetVal::Converging(error as u32)
+	}
+}
+
+/// The input of [`REQUEST_RANDOMNESS`].
+///
+/// The contract is called back with the message `selector`, taking the
+/// [`RequestId`](pallet_randomness_oracle::RequestId) and the randomness as `[u8; 32]`. The
+/// contract pays the request deposit.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct RandomnessRequest {
+	/// The block whose randomness is requested.
+	pub block: BlockNumber,
+	/// The subject the randomness is separated by.
+	pub subject: Vec<u8>,
+	/// The selector of the callback message.
+	pub selector: [u8; 4],
+	/// The gas limit of the callback.
+	pub gas_limit: Weight,
+}
+
+/// Hash `randomness` with `subject`, so that different uses of the same pulse are independent.
+pub fn subject_randomness(randomness: &[u8], subject: &[u8]) -> [u8; 32] {
+	sp_io::hashing::blake2_256(&(b"etf-subject", randomness, subject).encode())
+}
+
+/// A uniform integer in `0..bound` derived from `seed`.
+///
+/// The seed is reduced as a 128-bit integer, so the bias is at most `bound / 2^128`.
+pub fn uniform_below(seed: [u8; 32], bound: u64) -> Option<u64> {
+	let mut wide = [0u8; 16];
+	wide.copy_from_slice(&seed[..16]);
+	(bound != 0).then(|| (u128::from_le_bytes(wide) % bound as u128) as u64)
+}
+
+/// The randomness of the latest pulse, the same [`RANDOM_AT`] returns for its block.
+fn latest_randomness() -> Option<[u8; 32]> {
+	beacon::randomness_at(RandomnessBeacon::height())
+}
+
+/// The randomness chain extension.
+#[derive(Default)]
+pub struct RandomnessExtension;
+
+impl ChainExtension<Runtime> for RandomnessExtension {
+	fn call<E: Ext>(
+		&mut self,
+		env: Environment<E, InitState>,
+	) -> Result<RetVal, DispatchError>
+	where
+		<E::T as SysConfig>::AccountId: UncheckedFrom<<E::T as SysConfig>::Hash> + AsRef<[u8]>,
+	{
+		let func_id = env.func_id();
+		log::trace!(target: "runtime", "[ChainExtension]|call|func_id:{:}", func_id);
+		let reads = |n| <Runtime as frame_system::Config>::DbWeight::get().reads(n);
+		let mut env = env.buf_in_buf_out();
+
+		let output = match func_id {
+			RANDOM => {
+				env.charge_weight(reads(3))?;
+				let Some(randomness) = latest_randomness() else {
+					return Ok(RandomnessError::NoPulse.into())
+				};
+				randomness.encode()
+			},
+			RANDOM_AT => {
+				env.charge_weight(reads(2))?;
+				let block: BlockNumber = env.read_as()?;
+				let Some(randomness) = beacon::randomness_at(block) else {
+					return Ok(RandomnessError::NoPulse.into())
+				};
+				randomness.encode()
+			},
+			RANDOM_FOR_SUBJECT | RANDOM_BELOW => {
+				let in_len = env.in_len();
+				env.charge_weight(
+					reads(3)
+						.saturating_add(SUBJECT_HASH_WEIGHT)
+						.saturating_add(WEIGHT_PER_SUBJECT_BYTE.saturating_mul(in_len.into())),
+				)?;
+				let (subject, bound) = if func_id == RANDOM_BELOW {
+					let (subject, bound): (Vec<u8>, u64) = env.read_as_unbounded(in_len)?;
+					(subject, Some(bound))
+				} else {
+					(env.read_as_unbounded(in_len)?, None)
+				};
+
+				let Some(randomness) = latest_randomness() else {
+					return Ok(RandomnessError::NoPulse.into())
+				};
+				let seed = subject_randomness(&randomness, &subject);
+				match bound {
+					Some(bound) => match uniform_below(seed, bound) {
+						Some(value) => value.encode(),
+						None => return Ok(RandomnessError::ZeroBound.into()),
+					},
+					None => seed.encode(),
+				}
+			},
+			HEIGHT => {
+				env.charge_weight(reads(1))?;
+				RandomnessBeacon::height().encode()
+			},
+			VERIFY_PULSE => {
+				let in_len = env.in_len();
+				env.charge_weight(reads(1).saturating_add(VERIFY_PULSE_WEIGHT))?;
+				let (block, signature): (BlockNumber, Vec<u8>) = env.read_as_unbounded(in_len)?;
+				beacon::verify_signature(block, &signature).encode()
+			},
+			DECRYPT => {
+				let in_len = env.in_len();
+				env.charge_weight(
+					reads(2)
+						.saturating_add(DECRYPT_WEIGHT)
+						.saturating_add(WEIGHT_PER_CIPHERTEXT_BYTE.saturating_mul(in_len.into())),
+				)?;
+				let (block, ciphertext): (BlockNumber, Vec<u8>) = env.read_as_unbounded(in_len)?;
+				match beacon::decrypt(block, &ciphertext) {
+					Ok(plaintext) => plaintext.encode(),
+					Err(DecryptError::NoPulse) => return Ok(RandomnessError::NoPulse.into()),
+					Err(DecryptError::InvalidCiphertext) =>
+						return Ok(RandomnessError::DecryptionFailed.into()),
+				}
+			},
+			REQUEST_RANDOMNESS => {
+				let in_len = env.in_len();
+				env.charge_weight(
+					<Runtime as pallet_randomness_oracle::Config>::WeightInfo::request(),
+				)?;
+				let request: RandomnessRequest = env.read_as_unbounded(in_len)?;
+				// The contract is both the requester paying the deposit and the callee.
+				let contract = AccountId::decode(&mut env.ext().address().as_ref())
+					.map_err(|_| DispatchError::Other("Invalid contract address"))?;
+				let Ok(subject) = request.subject.try_into() else {
+					return Ok(RandomnessError::RequestFailed.into())
+				};
+				let callback = Callback::Contract {
+					contract: contract.clone(),
+					selector: request.selector,
+					gas_limit: request.gas_limit,
+				};
+				// The callback runs in `on_initialize`, so the contract pays for it now.
+				env.charge_weight(RandomnessOracle::callback_weight(&callback))?;
+				match RandomnessOracle::do_request(contract, request.block, subject, callback) {
+					Ok(id) => id.encode(),
+					Err(e) => {
+						log::debug!(target: "runtime", "Randomness request failed: {:?}", e);
+						return Ok(RandomnessError::RequestFailed.into())
+					},
+				}
+			},
+			_ => {
+				log::error!("Called an unregistered `func_id`: {:}", func_id);
+				return Ok(RandomnessError::UnknownFunction.into())
+			},
+		};
+
+		env.write(&output, false, None)?;
+		Ok(RandomnessError::Success.into())
+	}
+
+	fn enabled() -> bool {
+		true
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn subjects_separate_randomness() {
+		let randomness = [7u8; 32];
+		assert_ne!(
+			subject_randomness(&randomness, b"lottery"),
+			subject_randomness(&randomness, b"raffle")
+		);
+	}
+
+	#[test]
+	fn uniform_below_respects_the_bound() {
+		assert_eq!(uniform_below([0xff; 32], 0), None);
+		assert_eq!(uniform_below([0xff; 32], 1), Some(0));
+		for byte in 0..=255u8 {
+			assert!(uniform_below([byte; 32], 6).unwrap() < 6);
+		}
+	}
+}
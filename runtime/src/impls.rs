@@ -19,22 +19,30 @@
 
 use polkadot_sdk::*;
 
+use beefy_primitives::{bls_crypto::AuthorityId as BeefyId, OnNewValidatorSet, ValidatorSet};
 use frame_support::{
 	pallet_prelude::*,
 	traits::{
 		fungibles::{Balanced, Credit},
+		schedule::v3::Anon as ScheduleAnon,
 		Currency, OnUnbalanced,
 	},
 };
+use node_primitives::{Balance, BlockNumber};
 use pallet_alliance::{IdentityVerifier, ProposalIndex, ProposalProvider};
 use pallet_asset_tx_payment::HandleCredit;
 use pallet_identity::legacy::IdentityField;
+use pallet_scheduler::WeightInfo as _;
+use pallet_staking::Exposure;
+use sp_runtime::DispatchResult;
+use sp_staking::SessionIndex;
 use sp_std::prelude::*;
 
 use crate::{
-	AccountId, AllianceCollective, AllianceMotion, 
-	Assets, Authorship, Balances, Hash,
-	NegativeImbalance, Runtime, RuntimeCall,
+	beacon, dynamic_params, resharing, AccountId, AllianceCollective, AllianceMotion, 
+	Assets, Authorship, Balances, BeaconBackfill, BeaconLiveness, Contracts, EtfResharing, Hash,
+	MmrLeaf, NegativeImbalance, OriginCaller, RandomnessBeacon, RandomnessOracle, Runtime,
+	RuntimeCall, Scheduler, Staking,
 };
 
 pub struct Author;
@@ -46,6 +54,283 @@ impl OnUnbalanced<NegativeImbalance> for Author {
 	}
 }
 
+/// Serves randomness oracle requests from the pulses of the randomness beacon.
+pub struct BeaconRandomness;
+impl pallet_randomness_oracle::RandomnessSource<BlockNumber> for BeaconRandomness {
+	fn latest() -> BlockNumber {
+		RandomnessBeacon::height()
+	}
+
+	fn randomness_at(block: BlockNumber) -> Option<[u8; 32]> {
+		beacon::randomness_at(block)
+	}
+}
+
+/// The pulses of the randomness beacon, for the backfill of skipped ones and the liveness of
+/// the authorities.
+pub struct BeaconPulses;
+impl pallet_beacon_backfill::PulseSource<BlockNumber> for BeaconPulses {
+	fn latest() -> BlockNumber {
+		RandomnessBeacon::height()
+	}
+
+	fn is_published(block: BlockNumber) -> bool {
+		beacon::pulse(block).is_some()
+	}
+
+	fn verify(block: BlockNumber, set_id: u64, signature: &[u8]) -> bool {
+		beacon::verify_signature_by(block, set_id, signature)
+	}
+}
+
+impl pallet_beacon_liveness::PulseSource<BlockNumber> for BeaconPulses {
+	fn is_published(block: BlockNumber) -> bool {
+		beacon::pulse(block).is_some()
+	}
+}
+
+/// Awards staking era points to the authorities whose shares are included in published pulses,
+/// as many as the `PulseContributionPoints` runtime parameter.
+///
+/// `pallet_beacon_liveness` rewards each published pulse once, to the validators of the
+/// authority set that signed it, and only for the verified submission that wrote it.
+pub struct PulseEraPoints;
+impl pallet_beacon_liveness::RewardContributors<AccountId> for PulseEraPoints {
+	fn reward_contributors(contributors: Vec<AccountId>) {
+		let points = dynamic_params::beacon::PulseContributionPoints::get();
+		if points > 0 {
+			Staking::reward_by_ids(contributors.into_iter().map(|who| (who, points)));
+		}
+	}
+}
+
+/// Staking as the session manager, after reporting the authorities that withheld their beacon
+/// shares in the ending session.
+pub struct StakingWithBeaconLiveness;
+impl pallet_session::historical::SessionManager<AccountId, Exposure<AccountId, Balance>>
+	for StakingWithBeaconLiveness
+{
+	fn new_session(
+		new_index: SessionIndex,
+	) -> Option<Vec<(AccountId, Exposure<AccountId, Balance>)>> {
+		<Staking as pallet_session::historical::SessionManager<_, _>>::new_session(new_index)
+	}
+
+	fn new_session_genesis(
+		new_index: SessionIndex,
+	) -> Option<Vec<(AccountId, Exposure<AccountId, Balance>)>> {
+		<Staking as pallet_session::historical::SessionManager<_, _>>::new_session_genesis(
+			new_index,
+		)
+	}
+
+	fn start_session(start_index: SessionIndex) {
+		<Staking as pallet_session::historical::SessionManager<_, _>>::start_session(start_index)
+	}
+
+	fn end_session(end_index: SessionIndex) {
+		BeaconLiveness::end_session(end_index);
+		<Staking as pallet_session::historical::SessionManager<_, _>>::end_session(end_index)
+	}
+}
+
+/// Abandons everything waiting for a pulse that is permanently missing.
+///
+/// Timelocked calls in the scheduler agenda of the block can never be decrypted and are
+/// canceled through the scheduler, like any other anonymous task, and randomness requests for
+/// it are dropped with their deposits returned.
+pub struct AbandonMissingPulse;
+impl pallet_beacon_backfill::OnPulseMissing<BlockNumber> for AbandonMissingPulse {
+	fn on_pulse_missing(block: BlockNumber) -> Weight {
+		let agenda = pallet_scheduler::Agenda::<Runtime>::get(block);
+		let scheduled = agenda.len() as u32;
+		let timelocked = agenda
+			.iter()
+			.enumerate()
+			.filter(|(_, task)| task.as_ref().map_or(false, |t| t.maybe_ciphertext.is_some()))
+			.map(|(index, _)| index as u32)
+			.collect::<Vec<_>>();
+
+		let cancel = <Scheduler as ScheduleAnon<BlockNumber, RuntimeCall, OriginCaller>>::cancel;
+		let mut weight = <Runtime as frame_system::Config>::DbWeight::get().reads(1);
+		for index in timelocked {
+			weight.saturating_accrue(
+				<Runtime as pallet_scheduler::Config>::WeightInfo::cancel(scheduled),
+			);
+			if let Err(e) = cancel((block, index)) {
+				log::warn!(
+					target: "runtime::beacon",
+					"Failed to cancel timelocked call {} of block {} without a pulse: {:?}",
+					index,
+					block,
+					e,
+				);
+			}
+		}
+
+		weight.saturating_add(RandomnessOracle::expire(block))
+	}
+}
+
+/// Sets up the beacon, the validators and the resharing commitments for the benchmarks of the
+/// beacon pallets.
+#[cfg(feature = "runtime-benchmarks")]
+pub struct BeaconBenchmarkHelper;
+
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_beacon_backfill::BenchmarkHelper<BlockNumber> for BeaconBenchmarkHelper {
+	fn publish(block: BlockNumber) {
+		beacon::benchmarking::publish(block)
+	}
+
+	fn sign(block: BlockNumber, set_id: u64) -> Vec<u8> {
+		beacon::benchmarking::sign(block, set_id)
+	}
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_beacon_liveness::BenchmarkHelper<BlockNumber> for BeaconBenchmarkHelper {
+	fn publish(block: BlockNumber) {
+		beacon::benchmarking::publish(block)
+	}
+
+	fn set_validators(count: u32) {
+		let validators = (0..count)
+			.map(|index| frame_benchmarking::account::<AccountId>("validator", index, 0))
+			.collect::<Vec<_>>();
+		pallet_session::Validators::<Runtime>::put(validators);
+		// Validators are identified by their exposure in the active era.
+		if pallet_staking::ActiveEra::<Runtime>::get().is_none() {
+			pallet_staking::ActiveEra::<Runtime>::put(pallet_staking::ActiveEraInfo {
+				index: 0,
+				start: None,
+			});
+		}
+	}
+
+	fn sign_pulse(block: BlockNumber, signers: u32) -> Vec<Vec<u8>> {
+		beacon::benchmarking::sign_pulse(block, signers)
+	}
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_randomness_oracle::BenchmarkHelper<BlockNumber> for BeaconBenchmarkHelper {
+	fn publish(block: BlockNumber) {
+		beacon::benchmarking::publish(block)
+	}
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_etf_resharing::BenchmarkHelper<BeefyId> for BeaconBenchmarkHelper {
+	fn dealing(incoming: &[BeefyId], threshold: u32) -> (BeefyId, Vec<BeefyId>, Vec<Vec<u8>>) {
+		resharing::benchmark_dealing(incoming, threshold)
+	}
+}
+
+/// Follows changes of the BEEFY-ETF authority set: the MMR leaf commits to the next set, the
+/// beacon notes which set signs the pulses from now on and the shares of the round secret are
+/// reshared to the next set.
+pub struct OnNewEtfValidatorSet;
+impl OnNewValidatorSet<BeefyId> for OnNewEtfValidatorSet {
+	fn on_new_validator_set(
+		validator_set: &ValidatorSet<BeefyId>,
+		next_validator_set: &ValidatorSet<BeefyId>,
+	) {
+		<MmrLeaf as OnNewValidatorSet<BeefyId>>::on_new_validator_set(
+			validator_set,
+			next_validator_set,
+		);
+		BeaconBackfill::note_authority_set(validator_set.id());
+		EtfResharing::on_new_validator_set(
+			validator_set.validators(),
+			next_validator_set.validators(),
+			next_validator_set.id(),
+		);
+	}
+}
+
+/// The id of the current BEEFY-ETF authority set, noted by the beacon backfill when it is added
+/// to a running chain.
+pub struct CurrentEtfSetId;
+impl Get<u64> for CurrentEtfSetId {
+	fn get() -> u64 {
+		beacon::validator_set_id()
+	}
+}
+
+/// The shares of the round secret and the commitments to them, as stored by `pallet_etf`.
+pub struct EtfShares;
+impl pallet_etf_resharing::EtfShares<BeefyId> for EtfShares {
+	fn commitments() -> Vec<BeefyId> {
+		pallet_etf::Commitments::<Runtime>::get().into_iter().collect()
+	}
+
+	fn shares() -> Vec<Vec<u8>> {
+		pallet_etf::Shares::<Runtime>::get().into_iter().map(|share| share.into_inner()).collect()
+	}
+
+	fn set_shares(commitments: Vec<BeefyId>, shares: Vec<Vec<u8>>) {
+		// `pallet_etf` bounds both by the number of authorities, like the resharing does, so
+		// they only have to be re-encoded into its storage types.
+		let (Ok(commitments), Ok(shares)) = (
+			Decode::decode(&mut &commitments.encode()[..]),
+			Decode::decode(&mut &shares.encode()[..]),
+		) else {
+			frame_support::defensive!("reshared shares exceed the bounds of pallet_etf");
+			return
+		};
+		pallet_etf::Commitments::<Runtime>::set(commitments);
+		pallet_etf::Shares::<Runtime>::set(shares);
+	}
+}
+
+/// Checks the dealt shares and combines the commitments to them in a resharing, see
+/// [`resharing`].
+pub struct EtfResharingCrypto;
+impl pallet_etf_resharing::ResharingCrypto<BeefyId> for EtfResharingCrypto {
+	fn verify_dealing(dealer: &BeefyId, commitments: &[BeefyId], threshold: u32) -> bool {
+		resharing::verify_dealing(dealer, commitments, threshold)
+	}
+
+	fn verify_share(recipient: &BeefyId, commitment: &BeefyId, share: &[u8]) -> bool {
+		resharing::verify_share(recipient, commitment, share)
+	}
+
+	fn interpolate(dealt: &[(u32, BeefyId)]) -> Option<BeefyId> {
+		resharing::interpolate(dealt)
+	}
+}
+
+/// Delivers randomness oracle callbacks to contracts.
+///
+/// A callback that reverts is reported as failed, the randomness is not delivered again.
+pub struct ContractsCallback;
+impl pallet_randomness_oracle::ContractCallback<AccountId> for ContractsCallback {
+	fn call(
+		origin: AccountId,
+		contract: AccountId,
+		input: Vec<u8>,
+		gas_limit: Weight,
+	) -> (Weight, DispatchResult) {
+		let outcome = Contracts::bare_call(
+			origin,
+			contract,
+			0,
+			gas_limit,
+			None,
+			input,
+			pallet_contracts::DebugInfo::Skip,
+			pallet_contracts::CollectEvents::Skip,
+			pallet_contracts::Determinism::Enforced,
+		);
+		let result = outcome.result.and_then(|value| match value.did_revert() {
+			true => Err(DispatchError::Other("Randomness callback reverted")),
+			false => Ok(()),
+		});
+		(outcome.gas_consumed, result)
+	}
+}
+
 /// A `HandleCredit` implementation that naively transfers the fees to the block author.
 /// Will drop and burn the assets in case the transfer fails.
 pub struct CreditToBlockAuthor;
@@ -492,3 +777,39 @@ mod multiplier_tests {
 		});
 	}
 }
+
+#[cfg(test)]
+mod etf_shares_tests {
+	use pallet_etf_resharing::EtfShares as _;
+	use polkadot_sdk::*;
+	use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+	use sp_runtime::BuildStorage;
+
+	use super::EtfShares;
+	use crate::{beacon::BeaconEngine, resharing, Runtime};
+
+	#[test]
+	fn shares_round_trip_through_the_storage_of_pallet_etf() {
+		let storage = frame_system::GenesisConfig::<Runtime>::default().build_storage().unwrap();
+		sp_io::TestExternalities::new(storage).execute_with(|| {
+			let mut rng = ChaCha20Rng::from_seed([7; 32]);
+			let commitments = (1..=3u64)
+				.map(|secret| resharing::commit::<BeaconEngine>(secret.into()))
+				.collect::<Vec<_>>();
+			let shares = (1..=3u64)
+				.zip(&commitments)
+				.map(|(secret, recipient)| {
+					resharing::encrypt::<BeaconEngine, _>(secret.into(), recipient, &mut rng)
+						.unwrap()
+				})
+				.collect::<Vec<_>>();
+
+			EtfShares::set_shares(commitments.clone(), shares.clone());
+
+			assert_eq!(pallet_etf::Commitments::<Runtime>::decode_len(), Some(3));
+			assert_eq!(pallet_etf::Shares::<Runtime>::decode_len(), Some(3));
+			assert_eq!(EtfShares::commitments(), commitments);
+			assert_eq!(EtfShares::shares(), shares);
+		});
+	}
+}
@@ -109,19 +109,6 @@ use sp_version::NativeVersion;
 use sp_version::RuntimeVersion;
 use static_assertions::const_assert;
 
-use sp_core::crypto::UncheckedFrom;
-use sp_runtime::DispatchError;
-use pallet_contracts::{
-	DebugInfo, 
-	chain_extension::{
-		ChainExtension,
-		Environment,
-		Ext,
-		InitState,
-		RetVal,
-		SysConfig,
-	}
-};
 
 #[cfg(any(feature = "std", test))]
 pub use frame_system::Call as SystemCall;
@@ -156,6 +143,21 @@ mod voter_bags;
 /// Runtime API definition for assets.
 pub mod assets_api;
 
+/// Runtime API definition for the randomness beacon.
+pub mod beacon_api;
+
+/// Runtime API definition for the resharing of the ETF round secret.
+pub mod resharing_api;
+
+/// Randomness beacon pulse lookup and verification.
+pub mod beacon;
+
+/// The randomness chain extension for ink! contracts.
+pub mod chain_extension;
+
+/// Resharing of the ETF round secret to new authority sets.
+pub mod resharing;
+
 // Make the WASM binary available.
 #[cfg(feature = "std")]
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
@@ -185,7 +187,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	// and set impl_version to 0. If only runtime
 	// implementation changes and behavior does not, then leave spec_version as
 	// is and increment impl_version.
-	spec_version: 268,
+	spec_version: 269,
 	impl_version: 0,
 	apis: RUNTIME_API_VERSIONS,
 	transaction_version: 2,
@@ -466,6 +468,74 @@ impl pallet_murmur::Config for Runtime {
 	type TlockProvider = RandomnessBeacon;
 }
 
+parameter_types! {
+	pub const RandomnessRequestDeposit: Balance = deposit(1, 128);
+	pub const RandomnessFulfilmentFee: Perbill = Perbill::from_percent(20);
+	// Targets cannot hold one of the pending blocks for more than a day.
+	pub const RandomnessMaxLookahead: BlockNumber = DAYS;
+	// A full queue of callbacks fits in a third of a block. Requests pay for their callback's
+	// weight, since it runs in `on_initialize`.
+	pub MaxRandomnessCallbackWeight: Weight = Perbill::from_percent(2) *
+		RuntimeBlockWeights::get().max_block;
+}
+
+impl pallet_randomness_oracle::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type RuntimeCall = RuntimeCall;
+	type Currency = Balances;
+	type Randomness = impls::BeaconRandomness;
+	type Contracts = impls::ContractsCallback;
+	type RequestDeposit = RandomnessRequestDeposit;
+	type FulfilmentFee = RandomnessFulfilmentFee;
+	type FeeDestination = Treasury;
+	type MaxRequestsPerBlock = ConstU32<16>;
+	type MaxPendingBlocks = ConstU32<64>;
+	type MaxLookahead = RandomnessMaxLookahead;
+	type MaxSubjectLen = ConstU32<64>;
+	type MaxCallLen = ConstU32<512>;
+	type MaxCallbackWeight = MaxRandomnessCallbackWeight;
+	type WeightInfo = pallet_randomness_oracle::weights::SubstrateWeight<Runtime>;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = impls::BeaconBenchmarkHelper;
+}
+
+parameter_types! {
+	pub const PulseBackfillWindow: BlockNumber = HOURS;
+	pub const PulseBackfillPriority: TransactionPriority = beacon::PULSE_PRIORITY;
+}
+
+impl pallet_beacon_backfill::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type Beacon = impls::BeaconPulses;
+	type OnPulseMissing = impls::AbandonMissingPulse;
+	type BackfillWindow = PulseBackfillWindow;
+	type MaxClosedPerBlock = ConstU32<16>;
+	type MaxSignatureLen = ConstU32<96>;
+	type UnsignedPriority = PulseBackfillPriority;
+	type WeightInfo = pallet_beacon_backfill::weights::SubstrateWeight<Runtime>;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = impls::BeaconBenchmarkHelper;
+}
+
+parameter_types! {
+	pub const BeaconMinParticipation: Perbill = Perbill::from_percent(50);
+}
+
+impl pallet_beacon_liveness::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type ValidatorSet = Historical;
+	type ReportUnresponsiveness = Offences;
+	type Beacon = impls::BeaconPulses;
+	type PulseVerifier = beacon::ValidatePulse;
+	type Reward = impls::PulseEraPoints;
+	type MaxAuthorities = MaxAuthorities;
+	type MaxPulsesPerBlock = ConstU32<16>;
+	type MinParticipation = BeaconMinParticipation;
+	type WeightInfo = pallet_beacon_liveness::weights::SubstrateWeight<Runtime>;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = impls::BeaconBenchmarkHelper;
+}
+
 parameter_types! {
 	pub MaximumSchedulerWeight: Weight = Perbill::from_percent(80) *
 		RuntimeBlockWeights::get().max_block;
@@ -657,7 +727,8 @@ impl pallet_session::Config for Runtime {
 	type ValidatorIdOf = pallet_staking::StashOf<Self>;
 	type ShouldEndSession = Babe;
 	type NextSessionRotation = Babe;
-	type SessionManager = pallet_session::historical::NoteHistoricalRoot<Self, Staking>;
+	type SessionManager =
+		pallet_session::historical::NoteHistoricalRoot<Self, impls::StakingWithBeaconLiveness>;
 	type SessionHandler = <SessionKeys as OpaqueKeys>::KeyTypeIdProviders;
 	type Keys = SessionKeys;
 	type WeightInfo = pallet_session::weights::SubstrateWeight<Runtime>;
@@ -1391,7 +1462,7 @@ impl pallet_contracts::Config for Runtime {
 	type CallStack = [pallet_contracts::Frame<Self>; 5];
 	type WeightPrice = pallet_transaction_payment::Pallet<Self>;
 	type WeightInfo = pallet_contracts::weights::SubstrateWeight<Self>;
-	type ChainExtension = RandomnessExtension;
+	type ChainExtension = chain_extension::RandomnessExtension;
 	type Schedule = Schedule;
 	type AddressGenerator = pallet_contracts::DefaultAddressGenerator;
 	type MaxCodeLen = ConstU32<{ 123 * 1024 }>;
@@ -2210,6 +2281,15 @@ pub mod dynamic_params {
 		#[codec(index = 1)]
 		pub static ByteDeposit: Balance = 1 * CENTS;
 	}
+
+	#[dynamic_pallet_params]
+	#[codec(index = 1)]
+	pub mod beacon {
+		/// The staking era points awarded to each authority whose share is included in a
+		/// published pulse. Authoring a block earns 20.
+		#[codec(index = 0)]
+		pub static PulseContributionPoints: u32 = 2;
+	}
 }
 
 #[cfg(feature = "runtime-benchmarks")]
@@ -2231,7 +2311,7 @@ impl EnsureOriginWithArg<RuntimeOrigin, RuntimeParametersKey> for DynamicParamet
 		key: &RuntimeParametersKey,
 	) -> Result<Self::Success, RuntimeOrigin> {
 		match key {
-			RuntimeParametersKey::Storage(_) => {
+			RuntimeParametersKey::Storage(_) | RuntimeParametersKey::Beacon(_) => {
 				frame_system::ensure_root(origin.clone()).map_err(|_| origin)?;
 				return Ok(())
 			},
@@ -2521,6 +2601,18 @@ mod runtime {
 
 	#[runtime::pallet_index(80)]
 	pub type Murmur = pallet_murmur;
+
+	#[runtime::pallet_index(81)]
+	pub type RandomnessOracle = pallet_randomness_oracle;
+
+	#[runtime::pallet_index(82)]
+	pub type BeaconBackfill = pallet_beacon_backfill;
+
+	#[runtime::pallet_index(83)]
+	pub type EtfResharing = pallet_etf_resharing;
+
+	#[runtime::pallet_index(84)]
+	pub type BeaconLiveness = pallet_beacon_liveness;
 }
 
 /// The address format for describing accounts.
@@ -2561,11 +2653,15 @@ pub type SignedPayload = generic::SignedPayload<RuntimeCall, SignedExtra>;
 /// Extrinsic type that has already been checked.
 pub type CheckedExtrinsic = generic::CheckedExtrinsic<AccountId, RuntimeCall, SignedExtra>;
 /// Executive: handles dispatch to the various modules.
+///
+/// Unsigned transactions are validated by [`beacon::ValidatePulse`], which checks the pulse
+/// submissions `pallet_randomness_beacon` does not validate itself and hands every other call
+/// to the pallets.
 pub type Executive = frame_executive::Executive<
 	Runtime,
 	Block,
 	frame_system::ChainContext<Runtime>,
-	Runtime,
+	beacon::ValidatePulse,
 	AllPalletsWithSystem,
 	Migrations,
 >;
@@ -2581,6 +2677,7 @@ type Migrations = (
 	pallet_alliance::migration::Migration<Runtime>,
 	pallet_contracts::Migration<Runtime>,
 	pallet_identity::migration::versioned::V0ToV1<Runtime, IDENTITY_MIGRATION_KEY_LIMIT>,
+	pallet_beacon_backfill::migrations::NoteCurrentAuthoritySet<Runtime, impls::CurrentEtfSetId>,
 );
 
 type EventRecord = frame_system::EventRecord<
@@ -2609,7 +2706,7 @@ impl pallet_beefy_etf::Config for Runtime {
 	type MaxAuthorities = MaxAuthorities;
 	type MaxNominators = ConstU32<0>;
 	type MaxSetIdSessionEntries = BeefySetIdSessionEntries;
-	type OnNewValidatorSet = MmrLeaf;
+	type OnNewValidatorSet = impls::OnNewEtfValidatorSet;
 	type WeightInfo = ();
 	type KeyOwnerProof = <Historical as KeyOwnerProofSystem<(KeyTypeId, BeefyId)>>::Proof;
 	type EquivocationReportSystem =
@@ -2617,6 +2714,23 @@ impl pallet_beefy_etf::Config for Runtime {
 	type RoundCommitmentProvider = Etf;
 }
 
+parameter_types! {
+	pub const EtfResharingPriority: TransactionPriority = beacon::PULSE_PRIORITY;
+}
+
+impl pallet_etf_resharing::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type AuthorityId = BeefyId;
+	type Shares = impls::EtfShares;
+	type Crypto = impls::EtfResharingCrypto;
+	type MaxAuthorities = MaxAuthorities;
+	type MaxShareLen = ConstU32<1024>;
+	type UnsignedPriority = EtfResharingPriority;
+	type WeightInfo = pallet_etf_resharing::weights::SubstrateWeight<Runtime>;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = impls::BeaconBenchmarkHelper;
+}
+
 /// MMR helper types.
 mod mmr {
 	use super::*;
@@ -2637,6 +2751,8 @@ mod benches {
 		[pallet_babe, Babe]
 		[pallet_bags_list, VoterList]
 		[pallet_balances, Balances]
+		[pallet_beacon_backfill, BeaconBackfill]
+		[pallet_beacon_liveness, BeaconLiveness]
 		[pallet_bounties, Bounties]
 		[pallet_broker, Broker]
 		[pallet_child_bounties, ChildBounties]
@@ -2650,6 +2766,7 @@ mod benches {
 		[pallet_election_provider_multi_phase, ElectionProviderMultiPhase]
 		[pallet_election_provider_support_benchmarking, EPSBench::<Runtime>]
 		[pallet_elections_phragmen, Elections]
+		[pallet_etf_resharing, EtfResharing]
 		[pallet_fast_unstake, FastUnstake]
 		[pallet_nis, Nis]
 		[pallet_parameters, Parameters]
@@ -2667,6 +2784,7 @@ mod benches {
 		[pallet_offences, OffencesBench::<Runtime>]
 		[pallet_preimage, Preimage]
 		[pallet_proxy, Proxy]
+		[pallet_randomness_oracle, RandomnessOracle]
 		[pallet_ranked_collective, RankedCollective]
 		[pallet_referenda, Referenda]
 		[pallet_recovery, Recovery]
@@ -2923,6 +3041,61 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl beacon_api::RandomnessBeaconApi<Block, BlockNumber, beacon::Pulse> for Runtime {
+		fn pulse(block: BlockNumber) -> Option<beacon::Pulse> {
+			beacon::pulse(block)
+		}
+
+		fn latest_height() -> BlockNumber {
+			RandomnessBeacon::height()
+		}
+
+		fn round_pubkey() -> Vec<u8> {
+			beacon::round_pubkey()
+		}
+
+		fn verify_pulse(block: BlockNumber, signature: Vec<u8>) -> bool {
+			beacon::verify_signature(block, &signature)
+		}
+
+		fn authority_set_at(block: BlockNumber) -> Option<u64> {
+			beacon::authority_set_at(block)
+		}
+
+		fn share_commitments() -> (Vec<Vec<u8>>, u32) {
+			let commitments = pallet_etf::Commitments::<Runtime>::get();
+			(commitments.iter().map(Encode::encode).collect(), EtfResharing::threshold())
+		}
+
+		fn signature_at(block: BlockNumber) -> Option<Vec<u8>> {
+			beacon::signature_at(block)
+		}
+
+		fn randomness_at(block: BlockNumber) -> Option<[u8; 32]> {
+			beacon::randomness_at(block)
+		}
+
+		fn pulse_status(block: BlockNumber) -> pallet_beacon_backfill::PulseStatus<BlockNumber> {
+			BeaconBackfill::status(block)
+		}
+	}
+
+	impl resharing_api::EtfResharingApi<Block, BeefyId> for Runtime {
+		fn round() -> Option<resharing_api::RoundStatus<BeefyId>> {
+			EtfResharing::status()
+		}
+
+		fn share(authority: u32) -> Option<(BeefyId, Vec<u8>)> {
+			use pallet_etf_resharing::EtfShares;
+			let shares = impls::EtfShares::shares();
+			impls::EtfShares::commitments().into_iter().zip(shares).nth(authority as usize)
+		}
+
+		fn dealt_shares(authority: u32) -> Vec<(u32, BeefyId, Vec<u8>)> {
+			EtfResharing::dealt_shares(authority)
+		}
+	}
+
 	impl pallet_contracts::ContractsApi<Block, AccountId, Balance, BlockNumber, Hash, EventRecord> for Runtime
 	{
 		fn call(
@@ -3147,9 +3320,20 @@ impl_runtime_apis! {
             signature_bytes: Vec<Vec<u8>>,
 			block_number: BlockNumber,
         ) -> Option<()> {
-			// let key_owner_proof = key_owner_proof.decode()?;
+			// Invalid pulses would be rejected by the pool anyway, see `beacon::ValidatePulse`.
+			// Invalid signatures are withheld, so that the pulse is published from valid shares
+			// only.
+			let valid = beacon::verify_pulse_submission(block_number, &signature_bytes).ok()?;
+			let signatures = signature_bytes
+				.into_iter()
+				.enumerate()
+				.map(|(j, signature)| match valid.contains(&(j as u32)) {
+					true => signature,
+					false => Vec::new(),
+				})
+				.collect();
             RandomnessBeacon::publish_pulse(
-                signature_bytes,
+                signatures,
                 block_number,
             )
         }
@@ -3380,46 +3564,3 @@ mod tests {
 	}
 }
 
-
-#[derive(Default)]
-pub struct RandomnessExtension;
-
-impl ChainExtension<Runtime> for RandomnessExtension {
-	
-    fn call<E: Ext>(
-        &mut self,
-        env: Environment<E, InitState>,
-    ) -> Result<RetVal, DispatchError>
-    where
-        <E::T as SysConfig>::AccountId:
-            UncheckedFrom<<E::T as SysConfig>::Hash> + AsRef<[u8]>,
-    {
-		let func_id = env.func_id();
-		log::trace!(
-			target: "runtime",
-			"[ChainExtension]|call|func_id:{:}",
-			func_id
-		);
-        match func_id {	
-            1101 => {
-                let mut env = env.buf_in_buf_out();
-				// let prev_block = System::block_number() - 1;
-				let latest = RandomnessBeacon::height();
-				let rand = RandomnessBeacon::random_at(latest);
-				env.write(&rand.encode(), false, None).map_err(|_| {
-					DispatchError::Other("Failed to write output randomness")
-				})?;
-				
-				Ok(RetVal::Converging(0))
-            },
-            _ => {
-                log::error!("Called an unregistered `func_id`: {:}", func_id);
-                Err(DispatchError::Other("Unimplemented func_id"))
-            }
-        }
-    }
-
-    fn enabled() -> bool {
-        true
-    }
-}
\ No newline at end of file
@@ -0,0 +1,238 @@
+//! The cryptography of the resharing of the ETF round secret to new authority sets.
+//!
+//! A commitment to a share is its double public key, in the 144 byte encoding of BEEFY keys,
+//! on the curve of the beacon. A share is encrypted to an authority like the genesis shares,
+//! as a proof of knowledge of the share and of its blinding, encrypted to the authority's key.
+//! See `pallet_etf_resharing` for the protocol.
+
+use polkadot_sdk::*;
+
+use ark_ff::{Field, PrimeField, Zero};
+use ark_serialize::CanonicalDeserialize;
+use beefy_primitives::bls_crypto::AuthorityId as BeefyId;
+use codec::Encode;
+use etf_crypto_primitives::proofs::hashed_el_gamal_sigma::BatchPoK;
+use sp_core::crypto::ByteArray;
+use sp_std::prelude::*;
+use w3f_bls::{DoublePublicKey, EngineBLS, SerializableToBytes};
+
+#[cfg(any(test, feature = "runtime-benchmarks"))]
+use rand_chacha::rand_core::{CryptoRng, RngCore};
+
+use crate::beacon::{self, BeaconEngine};
+
+/// Check that the commitments dealt to the incoming authorities lie on a polynomial of degree
+/// less than `threshold` whose constant term is the share committed to by `dealer`.
+pub fn verify_dealing(dealer: &BeefyId, commitments: &[BeefyId], threshold: u32) -> bool {
+	verify_dealing_with::<BeaconEngine>(dealer, commitments, threshold)
+}
+
+/// Check that `share` proves that it encrypts to `recipient` the share committed to by
+/// `commitment`.
+pub fn verify_share(recipient: &BeefyId, commitment: &BeefyId, share: &[u8]) -> bool {
+	verify_share_with::<BeaconEngine>(recipient, commitment, share)
+}
+
+/// Interpolate at zero the commitments dealt to an incoming authority, each paired with the
+/// index of its dealer, into the commitment to its new share.
+pub fn interpolate(dealt: &[(u32, BeefyId)]) -> Option<BeefyId> {
+	interpolate_with::<BeaconEngine>(dealt)
+}
+
+/// Decode a commitment to a share, the public key of the share in both groups.
+pub(crate) fn decode_commitment<E: EngineBLS>(commitment: &BeefyId) -> Option<DoublePublicKey<E>> {
+	DoublePublicKey::<E>::from_bytes(commitment.as_slice()).ok()
+}
+
+fn verify_dealing_with<E: EngineBLS>(
+	dealer: &BeefyId,
+	commitments: &[BeefyId],
+	threshold: u32,
+) -> bool {
+	let Some(points) = core::iter::once(dealer)
+		.chain(commitments)
+		.map(decode_commitment::<E>)
+		.collect::<Option<Vec<_>>>()
+	else {
+		return false
+	};
+	let threshold = threshold as usize;
+	if threshold == 0 || threshold >= points.len() {
+		return false
+	}
+
+	// The points are the evaluations at zero, for the dealer, and at `j + 1`, for the incoming
+	// authority at `j`. Evaluations of a polynomial of degree less than `threshold` are
+	// orthogonal to `v_k * m(k)` for `v_k = prod_{l != k} 1 / (k - l)` and any polynomial `m`
+	// of degree less than `points.len() - threshold`, while other points are orthogonal to a
+	// random `m` only with negligible probability. `m` is derived from the dealing, so the
+	// dealer cannot choose it.
+	let seed = sp_io::hashing::blake2_256(&(dealer, commitments, threshold as u32).encode());
+	let m = (0..(points.len() - threshold) as u32)
+		.map(|i| {
+			E::Scalar::from_le_bytes_mod_order(&sp_io::hashing::blake2_256(&(seed, i).encode()))
+		})
+		.collect::<Vec<_>>();
+	let x = |k: usize| E::Scalar::from(k as u64);
+
+	let (mut signature_group, mut public_key_group) =
+		(E::SignatureGroup::zero(), E::PublicKeyGroup::zero());
+	for (k, point) in points.iter().enumerate() {
+		let v = (0..points.len())
+			.filter(|&l| l != k)
+			.fold(E::Scalar::from(1u64), |acc, l| acc * (x(k) - x(l)))
+			.inverse()
+			.expect("evaluation points are distinct; qed");
+		let weight = v * m.iter().rev().fold(E::Scalar::zero(), |acc, c| acc * x(k) + c);
+		signature_group += point.0 * weight;
+		public_key_group += point.1 * weight;
+	}
+	signature_group.is_zero() && public_key_group.is_zero()
+}
+
+fn verify_share_with<E: EngineBLS>(
+	recipient: &BeefyId,
+	commitment: &BeefyId,
+	share: &[u8],
+) -> bool {
+	let (Some(recipient), Some(commitment)) =
+		(decode_commitment::<E>(recipient), decode_commitment::<E>(commitment))
+	else {
+		return false
+	};
+	let Ok(resharing) = BatchPoK::<E::PublicKeyGroup>::deserialize_compressed(&mut &share[..])
+	else {
+		return false
+	};
+	// The first secret of the proof is the share, the second one its blinding.
+	resharing.commitments_to_secrets.first() == Some(&commitment.1) &&
+		resharing.verify(recipient.1)
+}
+
+fn interpolate_with<E: EngineBLS>(dealt: &[(u32, BeefyId)]) -> Option<BeefyId> {
+	let indices = dealt.iter().map(|(dealer, _)| *dealer as usize).collect::<Vec<_>>();
+	let (mut signature_group, mut public_key_group) =
+		(E::SignatureGroup::zero(), E::PublicKeyGroup::zero());
+	for (dealer, commitment) in dealt {
+		let commitment = decode_commitment::<E>(commitment)?;
+		let lambda = beacon::lagrange_at_zero::<E>(*dealer as usize, &indices);
+		signature_group += commitment.0 * lambda;
+		public_key_group += commitment.1 * lambda;
+	}
+	BeefyId::from_slice(&DoublePublicKey::<E>(signature_group, public_key_group).to_bytes()).ok()
+}
+
+/// The commitment to `secret`.
+#[cfg(any(test, feature = "runtime-benchmarks"))]
+pub(crate) fn commit<E: EngineBLS>(secret: E::Scalar) -> BeefyId {
+	let secret = w3f_bls::SecretKeyVT::<E>(secret);
+	let keypair = w3f_bls::KeypairVT::<E> { public: secret.into_public(), secret };
+	let public = DoublePublicKey(keypair.into_public_key_in_signature_group().0, keypair.public.0);
+	BeefyId::from_slice(&public.to_bytes()).expect("commitments are BEEFY keys; qed")
+}
+
+/// Encrypt `secret` to `recipient`, in the format of the dealt shares.
+#[cfg(any(test, feature = "runtime-benchmarks"))]
+pub(crate) fn encrypt<E: EngineBLS, R: RngCore + CryptoRng>(
+	secret: E::Scalar,
+	recipient: &BeefyId,
+	rng: &mut R,
+) -> Option<Vec<u8>> {
+	use ark_ff::UniformRand;
+	use ark_serialize::CanonicalSerialize;
+
+	let recipient = w3f_bls::single::PublicKey::<E>(decode_commitment::<E>(recipient)?.1);
+	let blinding = E::Scalar::rand(rng);
+	let (_, resharing) = etf_crypto_primitives::dpss::acss::DoubleSecret::<E>(secret, blinding)
+		.reshare(&[recipient], 1, rng)
+		.ok()?
+		.pop()?;
+	let mut bytes = Vec::new();
+	resharing.serialize_compressed(&mut bytes).ok()?;
+	Some(bytes)
+}
+
+/// The commitment to a share, along with the commitments to the evaluations of a polynomial of
+/// degree less than `threshold` whose constant term is that share, at the indices of the
+/// `incoming` authorities, and the evaluations encrypted to them, for the benchmarks of
+/// `pallet_etf_resharing`.
+#[cfg(feature = "runtime-benchmarks")]
+pub fn benchmark_dealing(
+	incoming: &[BeefyId],
+	threshold: u32,
+) -> (BeefyId, Vec<BeefyId>, Vec<Vec<u8>>) {
+	use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+
+	type Scalar = <BeaconEngine as EngineBLS>::Scalar;
+	let mut rng = ChaCha20Rng::from_seed([7; 32]);
+	let coefficients = (1..=threshold.max(1) as u64).map(Scalar::from).collect::<Vec<_>>();
+	let evaluate = |x: u64| {
+		let x = Scalar::from(x);
+		coefficients.iter().rev().fold(Scalar::zero(), |acc, c| acc * x + c)
+	};
+
+	let (mut commitments, mut shares) = (Vec::new(), Vec::new());
+	for (j, recipient) in incoming.iter().enumerate() {
+		let evaluation = evaluate(j as u64 + 1);
+		commitments.push(commit::<BeaconEngine>(evaluation));
+		let share = encrypt::<BeaconEngine, _>(evaluation, recipient, &mut rng);
+		shares.push(share.unwrap_or_default());
+	}
+	(commit::<BeaconEngine>(coefficients[0]), commitments, shares)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use w3f_bls::TinyBLS377;
+
+	fn commitment(secret: u64) -> BeefyId {
+		commit::<TinyBLS377>(secret.into())
+	}
+
+	/// The commitments to `f(1), .., f(count)` for `f(x) = 5 + 3x + 2x^2`.
+	fn dealt(count: u64) -> Vec<BeefyId> {
+		(1..=count).map(|x| commitment(5 + 3 * x + 2 * x * x)).collect()
+	}
+
+	#[test]
+	fn dealings_on_the_dealers_share_are_accepted() {
+		assert!(verify_dealing_with::<TinyBLS377>(&commitment(5), &dealt(4), 3));
+		// A polynomial of degree less than three also has degree less than four.
+		assert!(verify_dealing_with::<TinyBLS377>(&commitment(5), &dealt(4), 4));
+	}
+
+	#[test]
+	fn inconsistent_dealings_are_rejected() {
+		// The degree of the polynomial is too high.
+		assert!(!verify_dealing_with::<TinyBLS377>(&commitment(5), &dealt(4), 2));
+		// The polynomial does not go through the dealer's share.
+		assert!(!verify_dealing_with::<TinyBLS377>(&commitment(6), &dealt(4), 3));
+		// Not enough incoming authorities for the threshold.
+		assert!(!verify_dealing_with::<TinyBLS377>(&commitment(5), &dealt(2), 3));
+	}
+
+	#[test]
+	fn shares_are_checked_against_their_commitment_and_recipient() {
+		use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+
+		let mut rng = ChaCha20Rng::from_seed([7; 32]);
+		// Any valid key works as a recipient.
+		let (alice, bob) = (commitment(1001), commitment(1002));
+		let share = encrypt::<TinyBLS377, _>(5u64.into(), &alice, &mut rng).unwrap();
+
+		assert!(verify_share_with::<TinyBLS377>(&alice, &commitment(5), &share));
+		assert!(!verify_share_with::<TinyBLS377>(&alice, &commitment(6), &share));
+		assert!(!verify_share_with::<TinyBLS377>(&bob, &commitment(5), &share));
+		assert!(!verify_share_with::<TinyBLS377>(&alice, &commitment(5), &share[..8]));
+	}
+
+	#[test]
+	fn dealt_commitments_interpolate_to_the_secret() {
+		let indexed = dealt(3).into_iter().enumerate().map(|(j, c)| (j as u32, c));
+		assert_eq!(
+			interpolate_with::<TinyBLS377>(&indexed.collect::<Vec<_>>()),
+			Some(commitment(5))
+		);
+	}
+}
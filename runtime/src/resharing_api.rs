@@ -0,0 +1,27 @@
+//! Runtime API definition for the resharing of the ETF round secret.
+
+use polkadot_sdk::*;
+
+use codec::Codec;
+use sp_std::vec::Vec;
+
+pub use pallet_etf_resharing::RoundStatus;
+
+sp_api::decl_runtime_apis! {
+	pub trait EtfResharingApi<AuthorityId>
+	where
+		AuthorityId: Codec,
+	{
+		/// Returns the state of the open resharing round, if any.
+		fn round() -> Option<RoundStatus<AuthorityId>>;
+
+		/// Returns the commitment to the share of the current authority at index `authority`,
+		/// along with the share encrypted to it.
+		fn share(authority: u32) -> Option<(AuthorityId, Vec<u8>)>;
+
+		/// Returns the evaluations dealt to the incoming authority at index `authority` in the
+		/// open round, each paired with the index of its dealer and the commitment to it, and
+		/// encrypted to the authority.
+		fn dealt_shares(authority: u32) -> Vec<(u32, AuthorityId, Vec<u8>)>;
+	}
+}
@@ -1,5 +1,12 @@
+import json
+import sys
+
 from statsmodels.sandbox.stats.runs import runstest_1samp 
 
+# Usage: python runs_test.py [pulses.jsonl]
+# The pulses file is written by `substrate-node export-pulses`. Without it, the sample pulses
+# below are tested.
+
 hex_strings = [
 "0x906958f50f3af06e40cd66a95df9c1a58b1fd8225e214df7f28f2e35968eb92d5349c50724b22ffcf08ebe5e4bfd59b7",
 "0xa422c73f77bbd6711f950d50b93d5998ebe8271e4cd0df439cba412d52f5b3815c144aaff39dcd26c9ef5bbe02ae87ff",
@@ -34,6 +41,10 @@ hex_strings = [
 "0x89995d9035bdc3935b436da83238f8913e1a3ea27981c01bca7b3a7b48376e497657c893327212130a5478b1988ff900",
 ]
 
+if len(sys.argv) > 1:
+    with open(sys.argv[1]) as pulses:
+        hex_strings = [json.loads(line)["signature"] for line in pulses if line.strip()]
+
 not_random = []
 
 for hex_string in hex_strings: